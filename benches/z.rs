@@ -0,0 +1,50 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rkz::eos::Eos;
+use rkz::gas::Gas;
+use rkz::units::{Kelvin, Pascal};
+use rkz::{compute_z, z_grid};
+use std::io::Write;
+
+fn bench_scalar(c: &mut Criterion) {
+    for spec in ["N2", "78%N2+21%O2+Ar"].iter() {
+        let gas = Gas::from_string(spec).unwrap();
+        for &eos in Eos::all() {
+            c.bench_function(&format!("z {} {:?}", spec, eos), |b| {
+                b.iter(|| {
+                    compute_z(
+                        black_box(&gas),
+                        eos,
+                        black_box(Pascal(200e5)),
+                        black_box(Kelvin(293.15)),
+                    )
+                })
+            });
+        }
+    }
+}
+
+fn bench_grid(c: &mut Criterion) {
+    // same grid as `rkz -g H2 -p 0:1000:10 -t -40:80`
+    let gas = Gas::from_string("H2").unwrap();
+    let ps: Vec<Pascal> = (0..=100).map(|i| Pascal(1e5 + i as f64 * 10e5)).collect();
+    let ts: Vec<Kelvin> = (-40..=80).map(|t| Kelvin(t as f64 + 273.15)).collect();
+    for &eos in Eos::all() {
+        c.bench_function(&format!("csv grid H2 {:?}", eos), |b| {
+            b.iter(|| {
+                let grid = z_grid(&gas, eos, &ps, &ts).unwrap();
+                let mut out = Vec::new();
+                for (i, p) in grid.pressures().iter().enumerate() {
+                    write!(out, "{}", p / 1e5).unwrap();
+                    for z in grid.row(i).iter() {
+                        write!(out, "\t{}", z).unwrap();
+                    }
+                    writeln!(out).unwrap();
+                }
+                out
+            })
+        });
+    }
+}
+
+criterion_group!(benches, bench_scalar, bench_grid);
+criterion_main!(benches);
@@ -0,0 +1,196 @@
+//! Opt-in memoization of the compression factor
+use crate::eos::{Eos, EosError, EosGas};
+use crate::gas::{BRule, Gas};
+use crate::units::{Kelvin, Pascal};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+
+/// Cache key: gas hash, equation of state and quantized pressure and temperature
+type Key = (u64, Eos, i64, i64);
+
+/// Least recently used cache of compression factors.
+/// Pressures and temperatures are quantized to `p_step` and `t_step`: a request within a step
+/// of a cached one returns the cached Z, computed at the conditions of the first request.
+/// The results therefore depend on the order of the requests, hence the cache is opt-in.
+#[derive(Clone, Debug)]
+pub struct ZCache {
+    capacity: usize,
+    /// Quantization step of the pressure in Pa
+    p_step: f64,
+    /// Quantization step of the temperature in K
+    t_step: f64,
+    /// Cached Z and tick of last use
+    entries: HashMap<Key, (f64, u64)>,
+    /// Keys by tick of last use, the least recently used first
+    lru: BTreeMap<u64, Key>,
+    tick: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl ZCache {
+    /// Cache of at most `capacity` values, with steps of 1 Pa and 1 mK.
+    /// Fails if `capacity` is zero.
+    pub fn new(capacity: usize) -> Result<ZCache, String> {
+        ZCache::with_steps(capacity, 1f64, 1e-3)
+    }
+
+    /// Cache of at most `capacity` values, quantizing the pressures to `p_step` in Pa
+    /// and the temperatures to `t_step` in K.
+    /// Fails if `capacity` is zero or if a step is not positive and finite.
+    pub fn with_steps(capacity: usize, p_step: f64, t_step: f64) -> Result<ZCache, String> {
+        if capacity == 0 {
+            return Err("The cache capacity must be positive".into());
+        }
+        for &(name, step) in [("pressure", p_step), ("temperature", t_step)].iter() {
+            if !(step > 0f64 && step.is_finite()) {
+                return Err(format!(
+                    "The {} step of the cache must be positive (got {})",
+                    name, step
+                ));
+            }
+        }
+        Ok(ZCache {
+            capacity,
+            p_step,
+            t_step,
+            entries: HashMap::with_capacity(capacity),
+            lru: BTreeMap::new(),
+            tick: 0,
+            hits: 0,
+            misses: 0,
+        })
+    }
+
+    /// Compression factor of `gas` at pressure `p` and temperature `t`,
+    /// from the cache if a request quantized to the same conditions was made before.
+    /// Errors are not cached.
+    pub fn z(&mut self, gas: &Gas, eos: Eos, p: Pascal, t: Kelvin) -> Result<f64, EosError> {
+        let key = (
+            gas_hash(gas),
+            eos,
+            (p.0 / self.p_step).round() as i64,
+            (t.0 / self.t_step).round() as i64,
+        );
+        self.tick += 1;
+        if let Some(entry) = self.entries.get_mut(&key) {
+            self.lru.remove(&entry.1);
+            self.lru.insert(self.tick, key);
+            entry.1 = self.tick;
+            self.hits += 1;
+            return Ok(entry.0);
+        }
+        self.misses += 1;
+        let z = gas.z(eos, p, t)?;
+        if self.entries.len() >= self.capacity {
+            let oldest = self.lru.keys().next().copied();
+            if let Some(key) = oldest.and_then(|tick| self.lru.remove(&tick)) {
+                self.entries.remove(&key);
+            }
+        }
+        self.entries.insert(key, (z, self.tick));
+        self.lru.insert(self.tick, key);
+        Ok(z)
+    }
+
+    /// Number of requests served from the cache
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Number of requests that were computed
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    /// Number of cached values
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Removes all the cached values
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.lru.clear();
+    }
+}
+
+/// Hash of the components, molar fractions and mixing rule of `gas`
+fn gas_hash(gas: &Gas) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for (frac, g) in gas.components() {
+        g.id.hash(&mut hasher);
+        frac.to_bits().hash(&mut hasher);
+        for c in [g.tc, g.pc, g.acentric_factor(), g.rkpr_delta1()].iter() {
+            c.to_bits().hash(&mut hasher);
+        }
+    }
+    if let Gas::Mixture(mix) = gas {
+        (mix.b_rule == BRule::Lorentz).hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+#[test]
+fn test_z_cache() {
+    let air = Gas::from_string("78%N2+21%O2+Ar").unwrap();
+    let mut cache = ZCache::new(2).unwrap();
+
+    let z = cache
+        .z(&air, Eos::PengRobinson, Pascal(100e5), Kelvin(300f64))
+        .unwrap();
+    assert_eq!(
+        z,
+        air.z(Eos::PengRobinson, Pascal(100e5), Kelvin(300f64))
+            .unwrap()
+    );
+    assert_eq!((cache.hits(), cache.misses()), (0, 1));
+
+    // identical and near-identical requests
+    assert_eq!(
+        cache
+            .z(&air, Eos::PengRobinson, Pascal(100e5), Kelvin(300f64))
+            .unwrap(),
+        z
+    );
+    assert_eq!(
+        cache
+            .z(&air, Eos::PengRobinson, Pascal(100e5 + 0.1), Kelvin(300f64))
+            .unwrap(),
+        z
+    );
+    assert_eq!((cache.hits(), cache.misses()), (2, 1));
+
+    // other equation, other gas
+    cache
+        .z(&air, Eos::RedlichKwong, Pascal(100e5), Kelvin(300f64))
+        .unwrap();
+    let n2 = Gas::from_string("N2").unwrap();
+    cache
+        .z(&n2, Eos::PengRobinson, Pascal(100e5), Kelvin(300f64))
+        .unwrap();
+    assert_eq!((cache.hits(), cache.misses()), (2, 3));
+    assert_eq!(cache.len(), 2);
+
+    // the least recently used value (air with PR) was evicted
+    cache
+        .z(&air, Eos::PengRobinson, Pascal(100e5), Kelvin(300f64))
+        .unwrap();
+    assert_eq!(cache.misses(), 4);
+    cache
+        .z(&n2, Eos::PengRobinson, Pascal(100e5), Kelvin(300f64))
+        .unwrap();
+    assert_eq!(cache.hits(), 3);
+
+    cache.clear();
+    assert!(cache.is_empty());
+
+    assert!(ZCache::new(0).is_err());
+    assert!(ZCache::with_steps(2, 0f64, 1e-3).is_err());
+    assert!(ZCache::with_steps(2, 1f64, f64::NAN).is_err());
+}
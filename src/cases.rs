@@ -0,0 +1,109 @@
+//! Named computation cases read from a TOML file
+use crate::{parse_reference, Quantity};
+use rkz::eos::Eos;
+use rkz::gas::{Gas, ParseOpts};
+use rkz::units::{Bar, Celsius, Kelvin, Pascal};
+use toml::Value;
+
+/// Reference state of enthalpy and entropy when a case doesn't specify one
+const DEFAULT_REFERENCE: &str = "25,1";
+
+/// Named computation of a quantity for a gas at given conditions
+pub struct Case {
+    pub name: String,
+    pub gas: Gas,
+    pub eos: Eos,
+    pub p: Pascal,
+    pub t: Kelvin,
+    pub quantity: Quantity,
+}
+
+/// Parses the `[[case]]` array of a TOML document, with the gases parsed with `opts`
+pub fn parse_cases(input: &str, opts: &ParseOpts) -> Result<Vec<Case>, String> {
+    let doc: Value = input.parse().map_err(|err| format!("{}", err))?;
+    let cases = match doc.get("case") {
+        Some(Value::Array(cases)) => cases,
+        Some(_) => return Err("\"case\" must be an array of tables".into()),
+        None => return Err("no [[case]] found".into()),
+    };
+    cases
+        .iter()
+        .enumerate()
+        .map(|(i, case)| {
+            let name = match case.get("name") {
+                Some(Value::String(name)) => name.clone(),
+                _ => format!("case{}", i + 1),
+            };
+            parse_case(case, name.clone(), opts).map_err(|err| format!("{}: {}", name, err))
+        })
+        .collect()
+}
+
+fn parse_case(case: &Value, name: String, opts: &ParseOpts) -> Result<Case, String> {
+    let gas = Gas::from_string_with(get_str(case, "gas")?.ok_or("missing gas")?, opts)?;
+    let eos = match get_str(case, "eos")? {
+        Some(eos) => eos.parse()?,
+        None => Eos::RedlichKwong,
+    };
+    let p = Pascal::from(Bar(get_num(case, "pressure")?));
+    let t = Kelvin::from(Celsius(get_num(case, "temperature")?));
+    let reference = parse_reference(get_str(case, "reference")?.unwrap_or(DEFAULT_REFERENCE))?;
+    let quantity = Quantity::parse(get_str(case, "quantity")?.unwrap_or("z"), reference)?;
+    Ok(Case {
+        name,
+        gas,
+        eos,
+        p,
+        t,
+        quantity,
+    })
+}
+
+fn get_str<'a>(case: &'a Value, key: &str) -> Result<Option<&'a str>, String> {
+    match case.get(key) {
+        Some(Value::String(s)) => Ok(Some(s)),
+        Some(_) => Err(format!("{} must be a string", key)),
+        None => Ok(None),
+    }
+}
+
+fn get_num(case: &Value, key: &str) -> Result<f64, String> {
+    match case.get(key) {
+        Some(Value::Float(num)) => Ok(*num),
+        Some(Value::Integer(num)) => Ok(*num as f64),
+        Some(_) => Err(format!("{} must be a number", key)),
+        None => Err(format!("missing {}", key)),
+    }
+}
+
+#[test]
+fn test_parse_cases() {
+    let cases = parse_cases(
+        concat!(
+            "[[case]]\n",
+            "name = \"h2\"\n",
+            "gas = \"H2\"\n",
+            "eos = \"srk\"\n",
+            "pressure = 700\n",
+            "temperature = 15.5\n",
+            "quantity = \"enthalpy\"\n",
+            "reference = \"0,1\"\n",
+        ),
+        &ParseOpts::default(),
+    )
+    .unwrap();
+    assert_eq!(cases.len(), 1);
+    assert_eq!(cases[0].name, "h2");
+    assert_eq!(cases[0].p, Pascal(700e5));
+    assert_eq!(cases[0].t, Kelvin(288.65));
+    assert!(matches!(cases[0].eos, Eos::SoaveRedlichKwong));
+    assert!(
+        matches!(cases[0].quantity, Quantity::Enthalpy((t, p)) if t == Kelvin(273.15) && p == Pascal(1e5))
+    );
+
+    let err = parse_cases(
+        "[[case]]\nname = \"x\"\ngas = \"N2\"\npressure = 1\n",
+        &ParseOpts::default(),
+    );
+    assert_eq!(err.err().unwrap(), "x: missing temperature");
+}
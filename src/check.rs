@@ -0,0 +1,321 @@
+//! Systematic consistency checks of the equations of state
+use crate::eos::{rkpr_criticals, Eos, EosGas, R};
+use crate::gas::Gas;
+use crate::gases::GASES;
+use crate::units::{Bar, Celsius, Kelvin, Pascal};
+use crate::util;
+
+/// Reduced temperatures swept by the consistency check
+const TR_GRID: &[f64] = &[0.6, 0.8, 0.9, 1.0, 1.2, 1.5, 2.0, 3.0];
+/// Reduced pressures swept by the consistency check
+const PR_GRID: &[f64] = &[0.01, 0.1, 0.5, 1.0, 2.0, 5.0, 10.0];
+/// Reduced pressure at which the gas must behave as an ideal gas
+const PR_IDEAL: f64 = 1e-6;
+/// Tolerance on Z for the ideal gas limit
+const IDEAL_TOL: f64 = 1e-4;
+/// Relative tolerance on the pressure recomputed from the molar volume of Z
+const PRESSURE_TOL: f64 = 1e-6;
+/// Tolerance on Z at the critical point.
+/// The cubic has a triple root there, which amplifies the rounding of the EOS constants
+/// (about 0.014 for Peng-Robinson).
+const CRITICAL_TOL: f64 = 0.02;
+
+/// Reference Z-factors at metering conditions as (gas spec, P in bar, T in °C, Z).
+/// At normal conditions (0°C, 1.01325 bar), from the NIST reference equations of state
+/// to which GERG-2008 reduces for pure components.
+/// At 60 bar and 270 K, the natural gases 1, 2, 3 and 5 of ISO 12213-2 (annex C),
+/// with the AGA8 detail characterization. Their iso-butane and pentanes and heavier
+/// fractions (0.35% at most) are lumped into n-butane.
+const METERING_POINTS: &[(&str, f64, f64, f64)] = &[
+    ("78.08%N2+20.95%O2+Ar", 1.01325, 0.0, 0.99941),
+    ("N2", 1.01325, 0.0, 0.99955),
+    ("O2", 1.01325, 0.0, 0.99904),
+    ("Ar", 1.01325, 0.0, 0.99906),
+    ("CO2", 1.01325, 0.0, 0.99333),
+    ("H2", 1.01325, 0.0, 1.00061),
+    ("He", 1.01325, 0.0, 1.00053),
+    (
+        "96.5%CH4+1.8%C2H6+0.45%C3H8+0.35%C4H10+0.3%N2+0.6%CO2",
+        60.0,
+        -3.15,
+        0.84053,
+    ),
+    (
+        "90.7%CH4+4.5%C2H6+0.84%C3H8+0.36%C4H10+3.1%N2+0.5%CO2",
+        60.0,
+        -3.15,
+        0.83348,
+    ),
+    (
+        "85.9%CH4+8.5%C2H6+2.3%C3H8+0.8%C4H10+1%N2+1.5%CO2",
+        60.0,
+        -3.15,
+        0.79380,
+    ),
+    (
+        "81.2%CH4+4.3%C2H6+0.9%C3H8+0.3%C4H10+5.7%N2+7.6%CO2",
+        60.0,
+        -3.15,
+        0.82609,
+    ),
+];
+
+/// Invariant that an equation of state must verify
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Invariant {
+    /// Z tends to 1 when P tends to 0
+    IdealLimit,
+    /// Z at Tc and Pc is the critical compressibility of the equation of state
+    CriticalZ,
+    /// The pressure explicit in the equation of state at the molar volume of Z
+    /// is the pressure of Z, i.e. the cubic in Z is the equation of state
+    PressureInverse,
+    /// The cubic equation has a Z-factor root
+    Solvable,
+}
+
+#[derive(Clone, Debug)]
+pub struct Violation {
+    pub gas: &'static str,
+    pub eos: Eos,
+    pub invariant: Invariant,
+    pub message: String,
+}
+
+/// Checks every referenced gas with every equation of state.
+/// Returns the number of checks performed and the violations found.
+pub fn consistency_check() -> (usize, Vec<Violation>) {
+    let mut num = 0;
+    let mut violations = Vec::new();
+
+    for g in GASES.iter() {
+        for &eos in Eos::all() {
+            let mut violation = |invariant, message| {
+                violations.push(Violation {
+                    gas: g.id,
+                    eos,
+                    invariant,
+                    message,
+                })
+            };
+
+            for &tr in TR_GRID {
+                let t = Kelvin(tr * g.tc);
+
+                num += 1;
+                match g.z(eos, Pascal(PR_IDEAL * g.pc), t) {
+                    Ok(z) if (z - 1f64).abs() > IDEAL_TOL => violation(
+                        Invariant::IdealLimit,
+                        format!("Z = {} at Tr = {} and Pr = {}", z, tr, PR_IDEAL),
+                    ),
+                    Ok(_) => {}
+                    Err(err) => violation(
+                        Invariant::Solvable,
+                        format!("{} at Tr = {} and Pr = {}", err, tr, PR_IDEAL),
+                    ),
+                }
+
+                for &pr in PR_GRID {
+                    num += 1;
+                    let p = pr * g.pc;
+                    let inverse = g
+                        .z(eos, Pascal(p), t)
+                        .and_then(|z| Ok((z, g.pressure_from_volume(eos, z * R * t.0 / p, t)?.0)));
+                    match inverse {
+                        Ok((z, p_inv)) if ((p_inv - p) / p).abs() > PRESSURE_TOL => violation(
+                            Invariant::PressureInverse,
+                            format!(
+                                "P = {} Pa at the volume of Z = {} (expected {} Pa) at Tr = {} and Pr = {}",
+                                p_inv, z, p, tr, pr
+                            ),
+                        ),
+                        Ok(_) => {}
+                        Err(err) => violation(
+                            Invariant::Solvable,
+                            format!("{} at Tr = {} and Pr = {}", err, tr, pr),
+                        ),
+                    }
+                }
+            }
+
+            num += 1;
+            // the volume translation shifts the critical Z of the cubic
+            let cubic_z = eos
+                .critical_z()
+                .unwrap_or_else(|| rkpr_criticals(g.rkpr_delta1()).2);
+            let critical_z = cubic_z - g.c(eos, g.tc) * g.pc / (R * g.tc);
+            match g.z(eos, Pascal(g.pc), Kelvin(g.tc)) {
+                Ok(z) if (z - critical_z).abs() > CRITICAL_TOL => violation(
+                    Invariant::CriticalZ,
+                    format!("Z = {} at the critical point (expected {})", z, critical_z),
+                ),
+                Ok(_) => {}
+                Err(err) => violation(
+                    Invariant::Solvable,
+                    format!("{} at the critical point", err),
+                ),
+            }
+        }
+    }
+
+    (num, violations)
+}
+
+/// Z-factor of the equation of state at a metering reference point
+#[derive(Clone, Debug)]
+pub struct MeteringPoint {
+    pub gas: &'static str,
+    /// Pressure in bar
+    pub p: f64,
+    /// Temperature in °C
+    pub t: f64,
+    /// Reference Z-factor
+    pub z_ref: f64,
+    /// Z-factor of the equation of state
+    pub z: f64,
+}
+
+impl MeteringPoint {
+    /// Relative deviation of the equation of state from the reference, in %
+    pub fn deviation(&self) -> f64 {
+        100f64 * (self.z - self.z_ref) / self.z_ref
+    }
+}
+
+/// Computes the Z-factor of every metering reference point with `eos`
+pub fn metering_check(eos: Eos) -> Result<Vec<MeteringPoint>, String> {
+    METERING_POINTS
+        .iter()
+        .map(|&(spec, p, t, z_ref)| {
+            let gas = Gas::from_string(spec).map_err(|err| format!("{}: {}", spec, err))?;
+            let z = gas
+                .z(eos, Pascal::from(Bar(p)), Kelvin::from(Celsius(t)))
+                .map_err(|err| format!("{}: {}", spec, err))?;
+            Ok(MeteringPoint {
+                gas: spec,
+                p,
+                t,
+                z_ref,
+                z,
+            })
+        })
+        .collect()
+}
+
+/// Measured Z-factor of a gas
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DataPoint {
+    pub p: Pascal,
+    pub t: Kelvin,
+    pub z: f64,
+}
+
+/// Parses one point "P,T,Z" per line, in bar and °C, separated by commas, tabs or spaces.
+/// Blank lines, '#' comments (see `util::content_lines`) and a header line are skipped.
+pub fn parse_data_points(input: &str) -> Result<Vec<DataPoint>, String> {
+    let mut points = Vec::new();
+    for (num, line) in util::content_lines(input) {
+        let fields: Vec<&str> = line
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|f| !f.is_empty())
+            .collect();
+        if points.is_empty() && fields.first().is_some_and(|f| f.parse::<f64>().is_err()) {
+            // header
+            continue;
+        }
+        if fields.len() != 3 {
+            return Err(format!(
+                "line {}: expected P, T and Z (got \"{}\")",
+                num, line
+            ));
+        }
+        let value = |s| util::parse_num(s).map_err(|err| format!("line {}: {}", num, err));
+        points.push(DataPoint {
+            p: Pascal::from(Bar(value(fields[0])?)),
+            t: Kelvin::from(Celsius(value(fields[1])?)),
+            z: value(fields[2])?,
+        });
+    }
+    if points.is_empty() {
+        return Err("no data point".into());
+    }
+    Ok(points)
+}
+
+/// Root mean square deviation of the Z-factor of `gas` from the data points,
+/// with every equation of state
+pub fn rms_deviations(gas: &Gas, points: &[DataPoint]) -> Result<Vec<(Eos, f64)>, String> {
+    Eos::all()
+        .iter()
+        .map(|&eos| {
+            let mut sum = 0f64;
+            for pt in points.iter() {
+                let z = gas.z(eos, pt.p, pt.t).map_err(|err| {
+                    format!(
+                        "{} at {} Pa and {} K: {}",
+                        eos.as_abbrev(),
+                        pt.p.0,
+                        pt.t.0,
+                        err
+                    )
+                })?;
+                sum += (z - pt.z).powi(2);
+            }
+            Ok((eos, (sum / points.len() as f64).sqrt()))
+        })
+        .collect()
+}
+
+#[test]
+fn test_consistency_check() {
+    let (num, violations) = consistency_check();
+    assert!(num > 0);
+    for v in violations.iter() {
+        println!("{} {:?}: {}", v.gas, v.eos, v.message);
+    }
+    assert!(violations
+        .iter()
+        .all(|v| v.invariant == Invariant::CriticalZ));
+}
+
+#[test]
+fn test_rms_deviations() {
+    // synthetic data from Peng-Robinson
+    let gas = Gas::from_string("CO2").unwrap();
+    let mut data = String::from("P[bar],T[°C],Z\n");
+    for &(p, t) in &[
+        (1f64, 0f64),
+        (20f64, 20f64),
+        (50f64, 50f64),
+        (100f64, 150f64),
+    ] {
+        let z = gas
+            .z(
+                Eos::PengRobinson,
+                Pascal::from(Bar(p)),
+                Kelvin::from(Celsius(t)),
+            )
+            .unwrap();
+        data.push_str(&format!("{}\t{} {}\n", p, t, z));
+    }
+    let points = parse_data_points(&data).unwrap();
+    assert_eq!(points.len(), 4);
+    let rms = rms_deviations(&gas, &points).unwrap();
+    assert_eq!(rms.len(), Eos::all().len());
+    let best = rms
+        .iter()
+        .fold(None, |best: Option<&(Eos, f64)>, r| match best {
+            Some(b) if b.1 <= r.1 => Some(b),
+            _ => Some(r),
+        })
+        .unwrap();
+    assert_eq!(best.0, Eos::PengRobinson);
+    assert_eq!(best.1, 0f64);
+    assert!(rms.iter().filter(|r| r.1 > 1e-4).count() == rms.len() - 1);
+
+    assert!(parse_data_points("# nothing\n").is_err());
+    assert_eq!(
+        parse_data_points("1,20\n").unwrap_err(),
+        "line 1: expected P, T and Z (got \"1,20\")"
+    );
+}
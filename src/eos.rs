@@ -1,29 +1,295 @@
 //! Equation of State
-use super::gas::{Gas, GasComp, GasMixture, PureGas};
-use roots::{self, Roots};
+#[cfg(test)]
+use super::gas::find_gas;
+use super::gas::{
+    cp_poly, cp_poly_integral, cp_poly_integral_over_t, BRule, Gas, GasComp, GasMixture, PureGas,
+};
+use super::solver::{SolverConfig, SolverError};
+use super::units::{Kelvin, Pascal};
+use super::viscosity;
 #[cfg(test)]
 use float_cmp::assert_approx_eq;
+use roots::{self, Roots};
+use std::fmt;
+use std::str::FromStr;
 
-const R: f64 = 8.31446262;
+/// Molar gas constant in J/(mol.K)
+pub const R: f64 = 8.31446262;
+/// Upper bound of the acentric factor range on which the Peng-Robinson m-correlation was fitted
+const PR_M_MAX_W: f64 = 0.5;
 
 /// Equation of state
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Eos {
     VanDerWaals,
     RedlichKwong,
     SoaveRedlichKwong,
+    /// Soave-Redlich-Kwong with the Peneloux volume translation
+    SrkPeneloux,
     PengRobinson,
+    /// Peng-Robinson with a constant volume translation of the Peneloux form
+    PrPeneloux,
+    /// Peng-Robinson with a temperature-dependent volume translation,
+    /// from the constant translation of `PrPeneloux` away from the critical point
+    /// to the one matching the critical compressibility at Tc.
+    /// The thermal properties include the temperature derivatives of the translation.
+    PrPenelouxT,
+    /// Redlich-Kwong-Peng-Robinson (Cismondi and Mollerup, 2005): the generic cubic form
+    /// with δ1 fitted per gas to its critical compressibility and δ2 = (1-δ1)/(1+δ1).
+    /// It reduces to SRK for δ1 = 1 and to PR for δ1 = 1+√2, and describes the liquid
+    /// densities better than both without volume translation.
+    Rkpr,
 }
 
-pub trait EosGas {
-    fn a(&self, eos: Eos, t: f64) -> f64;
-    fn b(&self, eos: Eos) -> f64;
+impl Eos {
+    /// All the supported equations of state
+    pub fn all() -> &'static [Eos] {
+        &[
+            Eos::VanDerWaals,
+            Eos::RedlichKwong,
+            Eos::SoaveRedlichKwong,
+            Eos::SrkPeneloux,
+            Eos::PengRobinson,
+            Eos::PrPeneloux,
+            Eos::PrPenelouxT,
+            Eos::Rkpr,
+        ]
+    }
+
+    /// Full name of the equation of state
+    pub fn name(&self) -> &'static str {
+        match self {
+            Eos::VanDerWaals => "Van der Waals",
+            Eos::RedlichKwong => "Redlich-Kwong",
+            Eos::SoaveRedlichKwong => "Soave-Redlich-Kwong",
+            Eos::SrkPeneloux => "Soave-Redlich-Kwong-Peneloux",
+            Eos::PengRobinson => "Peng-Robinson",
+            Eos::PrPeneloux => "Peng-Robinson-Peneloux",
+            Eos::PrPenelouxT => "Peng-Robinson-Peneloux with temperature-dependent translation",
+            Eos::Rkpr => "Redlich-Kwong-Peng-Robinson",
+        }
+    }
+
+    /// Canonical abbreviation, as parsed by `from_str`
+    pub fn as_abbrev(&self) -> &'static str {
+        match self {
+            Eos::VanDerWaals => "VdW",
+            Eos::RedlichKwong => "RK",
+            Eos::SoaveRedlichKwong => "SRK",
+            Eos::SrkPeneloux => "SRKP",
+            Eos::PengRobinson => "PR",
+            Eos::PrPeneloux => "PRP",
+            Eos::PrPenelouxT => "PRPT",
+            Eos::Rkpr => "RKPR",
+        }
+    }
+
+    /// Theoretical compressibility factor at the critical point.
+    /// The volume translation of Peneloux lowers it by c.Pc/(R.Tc), which depends on the gas.
+    /// None for RKPR, whose critical compressibility depends on δ1 (see `rkpr_criticals`).
+    pub fn critical_z(&self) -> Option<f64> {
+        match self {
+            Eos::VanDerWaals => Some(0.375),
+            Eos::RedlichKwong | Eos::SoaveRedlichKwong | Eos::SrkPeneloux => Some(1f64 / 3f64),
+            Eos::PengRobinson | Eos::PrPeneloux | Eos::PrPenelouxT => Some(0.3074),
+            Eos::Rkpr => None,
+        }
+    }
+}
+
+impl Eos {
+    /// Parameters (δ1, δ2) of the generic cubic form P = RT/(v-b) - a/((v+δ1.b)(v+δ2.b)).
+    /// None for RKPR, whose δ1 depends on the gas (see `EosGas::deltas`).
+    pub fn deltas(&self) -> Option<(f64, f64)> {
+        match self {
+            Eos::VanDerWaals => Some((0f64, 0f64)),
+            Eos::RedlichKwong | Eos::SoaveRedlichKwong | Eos::SrkPeneloux => Some((1f64, 0f64)),
+            Eos::PengRobinson | Eos::PrPeneloux | Eos::PrPenelouxT => {
+                Some((1f64 + 2f64.sqrt(), 1f64 - 2f64.sqrt()))
+            }
+            Eos::Rkpr => None,
+        }
+    }
+}
+
+/// δ1 of RKPR matching the critical compressibility `zc` of a gas, with the correlation
+/// of Cismondi and Mollerup (2005), fitted on Zc of the equation = 1.168 Zc.
+/// Above Zc = 0.29, out of the range of the correlation, δ1 is kept at its limit 0.428.
+pub fn rkpr_delta1(zc: f64) -> f64 {
+    let x = 0.338426 - 1.168 * zc;
+    if x <= 0f64 {
+        return 0.428363;
+    }
+    0.428363 + 18.496215 * x.powf(0.66) + 789.723105 * x.powf(2.512392)
+}
+
+/// (Ωa, Ωb, Zc) of the generic cubic with δ2 = (1-δ1)/(1+δ1):
+/// ac = Ωa.R²Tc²/Pc, b = Ωb.R.Tc/Pc and Zc is the critical compressibility of the equation
+pub fn rkpr_criticals(d1: f64) -> (f64, f64, f64) {
+    let d = (1f64 + d1 * d1) / (1f64 + d1);
+    let y = 1f64 + (2f64 * (1f64 + d1)).cbrt() + (4f64 / (1f64 + d1)).cbrt();
+    let denom = 3f64 * y + d - 1f64;
+    let omega_a = (3f64 * y * y + 3f64 * y * d + d * d + d - 1f64) / (denom * denom);
+    (omega_a, 1f64 / denom, y / denom)
+}
+
+/// (δ1, δ2) of RKPR for the given δ1
+fn rkpr_deltas(d1: f64) -> (f64, f64) {
+    (d1, (1f64 - d1) / (1f64 + d1))
+}
+
+/// Derivative of δ2 = (1-δ1)/(1+δ1) of RKPR with respect to δ1
+fn rkpr_dd2_dd1(d1: f64) -> f64 {
+    -2f64 / ((1f64 + d1) * (1f64 + d1))
+}
+
+/// Serialized as its abbreviation (e.g. "PR")
+#[cfg(feature = "serde")]
+impl serde::Serialize for Eos {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_abbrev())
+    }
+}
+
+/// Deserialized from its abbreviation, case insensitive
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Eos {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Eos, D::Error> {
+        let abbrev = String::deserialize(deserializer)?;
+        abbrev.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl FromStr for Eos {
+    type Err = String;
+
+    /// Parses an abbreviation of `as_abbrev` (case insensitive)
+    fn from_str(input: &str) -> Result<Eos, String> {
+        Eos::all()
+            .iter()
+            .find(|eos| eos.as_abbrev().eq_ignore_ascii_case(input))
+            .copied()
+            .ok_or_else(|| format!("Unknown equation of state: {}", input))
+    }
+}
+
+/// Root of the cubic equation in Z selected by magnitude among the physical roots
+/// (molar volume above the co-volume), regardless of the phase
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Root {
+    Min,
+    /// Root between the liquid and vapor roots, only with three physical roots
+    Middle,
+    Max,
+}
+
+impl Root {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Root::Min => "min",
+            Root::Middle => "middle",
+            Root::Max => "max",
+        }
+    }
+}
+
+impl FromStr for Root {
+    type Err = String;
+
+    /// Parses "min", "middle" or "max" (case insensitive)
+    fn from_str(input: &str) -> Result<Root, String> {
+        [Root::Min, Root::Middle, Root::Max]
+            .iter()
+            .find(|root| root.name().eq_ignore_ascii_case(input))
+            .copied()
+            .ok_or_else(|| format!("Unknown root: {} (expected min, middle or max)", input))
+    }
+}
+
+/// Selection of the root of the cubic equation in Z among the physical roots
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum RootStrategy {
+    /// Highest root (the default of `z`)
+    Vapor,
+    /// Smallest root
+    Liquid,
+    /// Root of lowest molar Gibbs energy, i.e. of the stable phase
+    Gibbs,
+    /// Root selected by magnitude, failing if missing
+    Magnitude(Root),
+}
+
+impl FromStr for RootStrategy {
+    type Err = String;
+
+    /// Parses "vapor", "liquid" or "gibbs" (case insensitive)
+    fn from_str(input: &str) -> Result<RootStrategy, String> {
+        match input.to_lowercase().as_str() {
+            "vapor" => Ok(RootStrategy::Vapor),
+            "liquid" => Ok(RootStrategy::Liquid),
+            "gibbs" => Ok(RootStrategy::Gibbs),
+            _ => Err(format!(
+                "Unknown phase: {} (expected vapor, liquid or gibbs)",
+                input
+            )),
+        }
+    }
+}
+
+/// Process of a gas between two pressures
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ProcessPath {
+    /// Reversible adiabatic compression or expansion, at constant entropy
+    Isentropic,
+    /// Throttling (Joule-Thomson expansion), at constant enthalpy
+    Isenthalpic,
+}
+
+impl FromStr for ProcessPath {
+    type Err = String;
+
+    /// Parses "isentropic" or "isenthalpic" (case insensitive)
+    fn from_str(input: &str) -> Result<ProcessPath, String> {
+        match input.to_lowercase().as_str() {
+            "isentropic" => Ok(ProcessPath::Isentropic),
+            "isenthalpic" => Ok(ProcessPath::Isenthalpic),
+            _ => Err(format!(
+                "Unknown path: {} (expected isentropic or isenthalpic)",
+                input
+            )),
+        }
+    }
+}
+
+/// Parameters of an equation of state for a gas at a given temperature.
+/// Their methods take the pressure and temperature as plain f64 in Pa and K
+/// (see `units` for the typed state functions of `EosGas`).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct EosParams {
+    /// Attraction parameter a(T) in Pa.m6/mol2
+    pub a: f64,
+    /// Co-volume parameter b in m3/mol
+    pub b: f64,
+    /// Volume translation c in m3/mol (v = v_cubic - c), zero for untranslated equations
+    pub c: f64,
+    /// Parameter δ1 of the generic cubic form (see `Eos::deltas`)
+    pub d1: f64,
+    /// Parameter δ2 of the generic cubic form
+    pub d2: f64,
+}
 
-    fn z(&self, eos: Eos, p: f64, t: f64) -> f64 {
-        let (a3, a2, a1, a0) = match eos {
+impl EosParams {
+    /// Dimensionless parameters A = aP/(RT)² and B = bP/(RT) of the cubic equation in Z
+    pub fn dimensionless(&self, p: f64, t: f64) -> (f64, f64) {
+        (self.a * p / (R * R * t * t), self.b * p / (R * t))
+    }
+
+    /// Coefficients (a3, a2, a1, a0) of the cubic equation in Z, before volume translation
+    pub fn cubic_coeffs(&self, eos: Eos, p: f64, t: f64) -> (f64, f64, f64, f64) {
+        match eos {
             Eos::VanDerWaals => {
-                let a = self.a(eos, t) * p / (R * R * t * t);
-                let b = self.b(eos) * p / (R * t);
+                let a = self.a * p / (R * R * t * t);
+                let b = self.b * p / (R * t);
 
                 let a3 = 1f64;
                 let a2 = -b - 1f64;
@@ -32,9 +298,13 @@ pub trait EosGas {
 
                 (a3, a2, a1, a0)
             }
-            Eos::RedlichKwong => {
-                let a = self.a(eos, t) * p / (R * R * t.powf(2.5));
-                let b = self.b(eos) * p / (R * t);
+            Eos::RedlichKwong | Eos::SoaveRedlichKwong | Eos::SrkPeneloux => {
+                // P = RT/(v-b) - a(T)/(v(v+b))
+                // with A = aP/(RT)² and B = bP/(RT):
+                // Z³ - Z² + (A - B - B²)Z - AB = 0
+                // RK and SRK only differ by the temperature dependence of a(T)
+                let a = self.a * p / (R * R * t * t);
+                let b = self.b * p / (R * t);
 
                 let a3 = 1f64;
                 let a2 = -1f64;
@@ -43,120 +313,2772 @@ pub trait EosGas {
 
                 (a3, a2, a1, a0)
             }
-            Eos::SoaveRedlichKwong => {
-                let a = self.a(eos, t) * p / (R * R * t * t);
-                let b = self.b(eos) * p / (R * t);
+            Eos::PengRobinson | Eos::PrPeneloux | Eos::PrPenelouxT => {
+                let a = self.a * p / (R * R * t * t);
+                let b = self.b * p / (R * t);
 
                 let a3 = 1f64;
-                let a2 = -1f64;
-                let a1 = a - b * b - b;
-                let a0 = -a * b;
+                let a2 = b - 1f64;
+                let a1 = -3f64 * b * b - 2f64 * b + a;
+                let a0 = b * b * b + b * b - a * b;
 
                 (a3, a2, a1, a0)
             }
-            Eos::PengRobinson => {
-                let a = self.a(eos, t) * p / (R * R * t * t);
-                let b = self.b(eos) * p / (R * t);
+            Eos::Rkpr => {
+                // generic form P = RT/(v-b) - a(T)/((v+δ1.b)(v+δ2.b)):
+                // Z³ + ((δ1+δ2-1)B - 1)Z² + (A + δ1δ2.B² - (δ1+δ2)B(B+1))Z
+                //    - (AB + δ1δ2.B²(B+1)) = 0
+                // which gives the cubics above for the (δ1, δ2) of each equation
+                let a = self.a * p / (R * R * t * t);
+                let b = self.b * p / (R * t);
+                let (sum, prod) = (self.d1 + self.d2, self.d1 * self.d2);
 
                 let a3 = 1f64;
-                let a2 = b - 1f64;
-                let a1 = -3f64 * b * b - 2f64 * b + a;
-                let a0 = b * b * b + b * b - a * b;
+                let a2 = (sum - 1f64) * b - 1f64;
+                let a1 = a + prod * b * b - sum * b * (b + 1f64);
+                let a0 = -(a * b + prod * b * b * (b + 1f64));
 
                 (a3, a2, a1, a0)
             }
-        };
+        }
+    }
 
+    /// Real roots of the cubic equation in Z before volume translation, in ascending order.
+    /// Fails if the coefficients or the roots overflow.
+    fn cubic_roots(&self, eos: Eos, p: f64, t: f64) -> Result<Vec<f64>, EosError> {
+        let (a3, a2, a1, a0) = self.cubic_coeffs(eos, p, t);
         let roots = roots::find_roots_cubic(a3, a2, a1, a0);
-        match roots {
-            Roots::No(_) => panic!("could not find Z-factor root"),
-            Roots::One([root]) => root,
-            Roots::Two(roots) => roots[0].max(roots[1]),
-            Roots::Three(roots) => roots[0].max(roots[1]).max(roots[2]),
-            _ => unreachable!(),
+        if ![a3, a2, a1, a0].iter().all(|c| c.is_finite())
+            || !roots.as_ref().iter().all(|z| z.is_finite())
+        {
+            return Err(self.degenerate(p, t));
         }
+        sorted_roots(roots)
     }
-}
 
-impl EosGas for PureGas {
-    fn a(&self, eos: Eos, t: f64) -> f64 {
-        match eos {
-            Eos::VanDerWaals => 27f64 * R * R * self.tc * self.tc / (64f64 * self.pc),
-            Eos::RedlichKwong => 0.42748023 * R * R * self.tc.powf(2.5) / self.pc,
-            Eos::SoaveRedlichKwong => {
-                let m = 0.48 + 1.574 * self.w - 0.176 * self.w * self.w;
-                let alpha = 1f64 + m * (1f64 - (t / self.tc).sqrt());
-                let alpha = alpha * alpha;
-                alpha * 0.42748023 * R * R * self.tc * self.tc / self.pc
-            }
-            Eos::PengRobinson => {
-                let m = if self.w <= 0.491 {
-                    0.37464 + 1.56226 * self.w - 0.26992 * self.w * self.w
-                } else {
-                    0.379642 + 1.487503 * self.w
-                        - 0.164423 * self.w * self.w
-                        - 0.016666 * self.w * self.w * self.w
-                };
-                let alpha = 1f64 + m * (1f64 - (t / self.tc).sqrt());
-                let alpha = alpha * alpha;
-                alpha * 0.45724 * R * R * self.tc * self.tc / self.pc
-            }
+    /// Physical roots of the cubic equation in Z before volume translation, in ascending order.
+    /// Fails if a root is within `DEGENERATE_TOL` of B = bP/(RT), as the volume above
+    /// the co-volume is lost in the rounding of huge roots.
+    fn physical_roots(&self, eos: Eos, p: f64, t: f64) -> Result<Vec<f64>, EosError> {
+        let b = self.b * p / (R * t);
+        let roots = self.cubic_roots(eos, p, t)?;
+        if roots.iter().any(|z| (z - b).abs() <= DEGENERATE_TOL * b) {
+            return Err(self.degenerate(p, t));
         }
+        Ok(physical_roots(roots, b))
     }
-    fn b(&self, eos: Eos) -> f64 {
-        match eos {
-            Eos::VanDerWaals => R * self.tc / (8f64 * self.pc),
-            Eos::RedlichKwong | Eos::SoaveRedlichKwong => 0.08664035 * R * self.tc / self.pc,
-            Eos::PengRobinson => 0.0778 * R * self.tc / self.pc,
+
+    /// Error of a cubic equation that can't be solved at pressure `p` and temperature `t`
+    fn degenerate(&self, p: f64, t: f64) -> EosError {
+        let (a, b) = self.dimensionless(p, t);
+        EosError::Degenerate(format!(
+            "the cubic equation in Z is degenerate at {:e} Pa and {} K (A = {:e}, B = {:e})",
+            p, t, a, b
+        ))
+    }
+
+    /// Highest physical root of the cubic equation in Z before volume translation
+    fn cubic_z(&self, eos: Eos, p: f64, t: f64) -> Result<f64, EosError> {
+        let roots = self.physical_roots(eos, p, t)?;
+        roots.last().copied().ok_or(EosError::NoRoot)
+    }
+
+    /// Shift of Z due to the volume translation
+    fn z_shift(&self, p: f64, t: f64) -> f64 {
+        self.c * p / (R * t)
+    }
+
+    /// All real roots of the cubic equation in Z, in ascending order
+    pub fn z_roots(&self, eos: Eos, p: f64, t: f64) -> Result<Vec<f64>, EosError> {
+        let shift = self.z_shift(p, t);
+        let roots = self.cubic_roots(eos, p, t)?;
+        Ok(roots.into_iter().map(|z| z - shift).collect())
+    }
+
+    /// Compression factor: the highest root of the cubic equation in Z
+    pub fn z(&self, eos: Eos, p: f64, t: f64) -> Result<f64, EosError> {
+        Ok(self.cubic_z(eos, p, t)? - self.z_shift(p, t))
+    }
+
+    /// Compression factor of the physical root selected by `root`
+    pub fn z_root(&self, eos: Eos, p: f64, t: f64, root: Root) -> Result<f64, EosError> {
+        self.z_with_strategy(eos, p, t, RootStrategy::Magnitude(root))
+    }
+
+    /// Compression factor of the physical root selected by `strategy`
+    pub fn z_with_strategy(
+        &self,
+        eos: Eos,
+        p: f64,
+        t: f64,
+        strategy: RootStrategy,
+    ) -> Result<f64, EosError> {
+        let roots = self.physical_roots(eos, p, t)?;
+        let z = match strategy {
+            RootStrategy::Vapor => roots.last().copied(),
+            RootStrategy::Liquid => roots.first().copied(),
+            // the residual Gibbs energy is the logarithm of the fugacity coefficient,
+            // shifted alike for all roots by the volume translation
+            RootStrategy::Gibbs => roots.iter().copied().fold(None, |best, z| match best {
+                Some(best)
+                    if self.ln_fugacity_coeff(p, t, best) <= self.ln_fugacity_coeff(p, t, z) =>
+                {
+                    Some(best)
+                }
+                _ => Some(z),
+            }),
+            RootStrategy::Magnitude(root) => Some(select_root(&roots, root)?),
+        };
+        Ok(z.ok_or(EosError::NoRoot)? - self.z_shift(p, t))
+    }
+
+    /// Diagnostic lines describing the raw output of the cubic solver
+    /// and the root selected by `z`
+    pub fn debug_roots(&self, eos: Eos, p: f64, t: f64) -> Vec<String> {
+        let (a3, a2, a1, a0) = self.cubic_coeffs(eos, p, t);
+        let raw = roots::find_roots_cubic(a3, a2, a1, a0);
+        let mut lines = vec![format!("{:?}", raw)];
+        let roots = match sorted_roots(raw) {
+            Ok(roots) => roots,
+            Err(err) => {
+                lines.push(format!("no root selected: {}", err));
+                return lines;
+            }
+        };
+        let shift = self.z_shift(p, t);
+        if shift != 0f64 {
+            lines.push(format!("volume translation: roots shifted by -{}", shift));
+        }
+        let roots: Vec<f64> = roots.into_iter().map(|z| z - shift).collect();
+        for (i, z) in roots.iter().enumerate() {
+            lines.push(format!("root {}: Z = {}", i, z));
         }
+        lines.push(match roots.len() {
+            0 => "no root selected: the cubic has no real root".to_string(),
+            1 => format!("selected Z = {}: single real root", roots[0]),
+            _ => format!(
+                "selected Z = {}: highest of {} roots (vapor root)",
+                roots[roots.len() - 1],
+                roots.len()
+            ),
+        });
+        lines
+    }
+
+    /// Logarithm of the fugacity coefficient of the root `z`
+    fn ln_fugacity_coeff(&self, p: f64, t: f64, z: f64) -> f64 {
+        let a = self.a * p / (R * R * t * t);
+        let b = self.b * p / (R * t);
+        let (d1, d2) = (self.d1, self.d2);
+        let attraction = if d1 == d2 {
+            a / z
+        } else {
+            a / (b * (d1 - d2)) * ((z + d1 * b) / (z + d2 * b)).ln()
+        };
+        z - 1f64 - (z - b).ln() - attraction
     }
 }
 
-impl EosGas for GasMixture {
-    fn a(&self, eos: Eos, t: f64) -> f64 {
-        let mut res = 0f64;
-        for i in self.comps.iter() {
-            let ai = i.pure_gas().a(eos, t);
-            for j in self.comps.iter() {
-                let aj = j.pure_gas().a(eos, t);
-                res += i.molar_fraction() * j.molar_fraction() * (ai * aj).sqrt();
+/// Failure to solve an equation of state
+#[derive(Clone, Debug, PartialEq)]
+pub enum EosError {
+    /// The cubic equation has no real root
+    NoRoot,
+    /// The root finder returned more roots than a cubic equation can have
+    TooManyRoots(Vec<f64>),
+    /// The constants of the gas can't define an equation of state
+    InvalidGas(String),
+    /// The root selected by magnitude is not among the `count` positive roots
+    MissingRoot { root: Root, count: usize },
+    /// The cubic equation can't be solved in floating point, e.g. at an absurd pressure
+    Degenerate(String),
+}
+
+impl fmt::Display for EosError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EosError::NoRoot => write!(f, "could not find Z-factor root"),
+            EosError::TooManyRoots(roots) => {
+                write!(f, "a cubic equation can't have the roots {:?}", roots)
             }
+            EosError::InvalidGas(msg) | EosError::Degenerate(msg) => write!(f, "{}", msg),
+            EosError::MissingRoot { root, count } => write!(
+                f,
+                "no {} root among the {} physical root(s) of the cubic equation",
+                root.name(),
+                count
+            ),
         }
-        res
     }
+}
 
-    fn b(&self, eos: Eos) -> f64 {
-        let mut res = 0f64;
-        for i in self.comps.iter() {
-            res += i.molar_fraction() * i.pure_gas().b(eos);
+impl std::error::Error for EosError {}
+
+/// Relative margin of the molar volume above the co-volume below which the cubic is degenerate:
+/// the repulsion alone balances the pressure and the root is the co-volume up to
+/// the rounding, e.g. for condensed gases from about 1e12 Pa
+const DEGENERATE_TOL: f64 = 1e-4;
+
+/// Real roots of the root finder output, in ascending order.
+/// The roots crate also serves quartic equations with `Roots::Four`, which
+/// must not come out of a cubic: it is reported instead of truncated.
+fn sorted_roots(roots: Roots<f64>) -> Result<Vec<f64>, EosError> {
+    let mut roots = match roots {
+        Roots::No(_) => Vec::new(),
+        Roots::One(roots) => roots.to_vec(),
+        Roots::Two(roots) => roots.to_vec(),
+        Roots::Three(roots) => roots.to_vec(),
+        Roots::Four(roots) => return Err(EosError::TooManyRoots(roots.to_vec())),
+    };
+    roots.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    Ok(roots)
+}
+
+/// Roots above `b` = bP/(RT), where the molar volume is above the co-volume.
+/// The root finder reports a double root once (`Roots::Two`),
+/// and the other root may be negative or below the co-volume.
+fn physical_roots(roots: Vec<f64>, b: f64) -> Vec<f64> {
+    roots.into_iter().filter(|&z| z > b).collect()
+}
+
+/// Root selected by magnitude among `roots` in ascending order
+fn select_root(roots: &[f64], root: Root) -> Result<f64, EosError> {
+    let z = match (root, roots.len()) {
+        (Root::Min, _) => roots.first(),
+        (Root::Middle, 3) => roots.get(1),
+        (Root::Middle, _) => None,
+        (Root::Max, _) => roots.last(),
+    };
+    z.copied().ok_or(EosError::MissingRoot {
+        root,
+        count: roots.len(),
+    })
+}
+
+/// Liquid-vapor equilibrium of a pure gas at a given temperature
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Saturation {
+    /// Temperature in K
+    pub t: f64,
+    /// Saturation pressure in Pa
+    pub p: f64,
+    /// Compression factor of the saturated liquid
+    pub z_liquid: f64,
+    /// Compression factor of the saturated vapor
+    pub z_vapor: f64,
+}
+
+impl Saturation {
+    /// Density in kg/m3 of the saturated liquid of molar mass `molar_mass` in kg/mol
+    pub fn liquid_density(&self, molar_mass: f64) -> f64 {
+        molar_mass * self.p / (self.z_liquid * R * self.t)
+    }
+
+    /// Density in kg/m3 of the saturated vapor of molar mass `molar_mass` in kg/mol
+    pub fn vapor_density(&self, molar_mass: f64) -> f64 {
+        molar_mass * self.p / (self.z_vapor * R * self.t)
+    }
+}
+
+/// Molar volume v and parameters of a gas at a given state
+struct State {
+    p: f64,
+    t: f64,
+    v: f64,
+    a: f64,
+    da_dt: f64,
+    d2a_dt2: f64,
+    b: f64,
+    d1: f64,
+    d2: f64,
+    c: f64,
+    dc_dt: f64,
+    d2c_dt2: f64,
+}
+
+impl State {
+    fn new<G: EosGas + ?Sized>(gas: &G, eos: Eos, p: f64, t: f64) -> Result<State, EosError> {
+        gas.check_constants()?;
+        let z = gas.eos_params(eos, t).cubic_z(eos, p, t)?;
+        Ok(State::with_z(gas, eos, p, t, z))
+    }
+
+    /// State of the root `z` of the cubic, before volume translation
+    fn with_z<G: EosGas + ?Sized>(gas: &G, eos: Eos, p: f64, t: f64, z: f64) -> State {
+        let params = gas.eos_params(eos, t);
+        State {
+            p,
+            t,
+            v: z * R * t / p,
+            a: params.a,
+            da_dt: gas.da_dt(eos, t),
+            d2a_dt2: gas.d2a_dt2(eos, t),
+            b: params.b,
+            d1: params.d1,
+            d2: params.d2,
+            c: params.c,
+            dc_dt: gas.dc_dt(eos, t),
+            d2c_dt2: gas.d2c_dt2(eos, t),
         }
-        res
+    }
+
+    /// (v+δ1.b)(v+δ2.b)
+    fn denom(&self) -> f64 {
+        (self.v + self.d1 * self.b) * (self.v + self.d2 * self.b)
+    }
+
+    /// Integral of 1/((v+δ1.b)(v+δ2.b)) from v to infinity
+    fn attraction_integral(&self) -> f64 {
+        if self.d1 == self.d2 {
+            1f64 / (self.v + self.d1 * self.b)
+        } else {
+            ((self.v + self.d1 * self.b) / (self.v + self.d2 * self.b)).ln()
+                / (self.b * (self.d1 - self.d2))
+        }
+    }
+
+    /// Derivative of (v+δ1.b)(v+δ2.b) with respect to δ1 of RKPR, with δ2 = (1-δ1)/(1+δ1)
+    fn ddenom_dd1(&self) -> f64 {
+        let dd2 = rkpr_dd2_dd1(self.d1);
+        self.b * ((self.v + self.d2 * self.b) + dd2 * (self.v + self.d1 * self.b))
+    }
+
+    /// Derivative of the attraction integral with respect to δ1 of RKPR,
+    /// with δ2 = (1-δ1)/(1+δ1)
+    fn dattraction_integral_dd1(&self) -> f64 {
+        let dd2 = rkpr_dd2_dd1(self.d1);
+        let (x1, x2) = (self.v + self.d1 * self.b, self.v + self.d2 * self.b);
+        let delta = self.d1 - self.d2;
+        (1f64 / x1 - dd2 / x2 - self.attraction_integral() * (1f64 - dd2)) / delta
+    }
+
+    /// (∂P/∂T) of the cubic at constant untranslated volume
+    fn cubic_dp_dt(&self) -> f64 {
+        R / (self.v - self.b) - self.da_dt / self.denom()
+    }
+
+    /// (∂P/∂T) at constant volume, the untranslated volume following the translation c(T)
+    fn dp_dt(&self) -> f64 {
+        self.cubic_dp_dt() + self.dp_dv() * self.dc_dt
+    }
+
+    /// (∂P/∂v) at constant temperature
+    fn dp_dv(&self) -> f64 {
+        let vb = self.v - self.b;
+        let denom = self.denom();
+        -R * self.t / (vb * vb)
+            + self.a * (2f64 * self.v + (self.d1 + self.d2) * self.b) / (denom * denom)
+    }
+
+    /// Residual isochoric heat capacity in J/(mol.K).
+    /// The terms in c(T) integrate (∂²P/∂T²) of the cubic at the volume v - c.
+    fn residual_cv(&self) -> f64 {
+        let translation = 2f64 * self.dc_dt * self.cubic_dp_dt()
+            + self.dc_dt * self.dc_dt * self.dp_dv()
+            + self.d2c_dt2 * self.p;
+        self.t * (self.d2a_dt2 * self.attraction_integral() + translation)
+    }
+
+    /// Residual isobaric heat capacity in J/(mol.K)
+    fn residual_cp(&self) -> f64 {
+        let dp_dt = self.dp_dt();
+        self.residual_cv() - self.t * dp_dt * dp_dt / self.dp_dv() - R
+    }
+
+    /// Enthalpy departure H - H_ideal in J/mol.
+    /// `v` is the untranslated volume: the translation changes the enthalpy by P.(T.dc/dT - c).
+    fn enthalpy_departure(&self) -> f64 {
+        (self.t * self.da_dt - self.a) * self.attraction_integral() + self.p * (self.v - self.c)
+            - R * self.t
+            + self.t * self.dc_dt * self.p
+    }
+
+    /// Entropy departure S - S_ideal at same pressure and temperature in J/(mol.K)
+    fn entropy_departure(&self) -> f64 {
+        R * ((self.v - self.b) * self.p / (R * self.t)).ln()
+            + self.da_dt * self.attraction_integral()
+            + self.dc_dt * self.p
+    }
+
+    /// (∂Z/∂T) at constant pressure in 1/K of the translated volume v - c.
+    /// (∂(v-c)/∂T) at constant pressure is -(∂P/∂T)/(∂P/∂v), with c(T) in `dp_dt`.
+    fn dz_dt(&self) -> f64 {
+        let dv_dt = -self.dp_dt() / self.dp_dv();
+        self.p / (R * self.t) * (dv_dt - (self.v - self.c) / self.t)
+    }
+
+    /// (∂Z/∂P) at constant temperature in 1/Pa of the translated volume v - c.
+    /// (∂v/∂P) at constant temperature is 1/(∂P/∂v).
+    fn dz_dp(&self) -> f64 {
+        ((self.v - self.c) + self.p / self.dp_dv()) / (R * self.t)
     }
 }
 
-impl EosGas for Gas {
-    fn a(&self, eos: Eos, t: f64) -> f64 {
-        match self {
-            Gas::Pure(g) => g.a(eos, t),
-            Gas::Mixture(g) => g.a(eos, t),
+/// Coefficients of the ideal gas heat capacity of `gas`, failing if they are unknown
+fn known_cp_coeffs<G: EosGas + ?Sized>(gas: &G) -> Result<[f64; 4], EosError> {
+    if gas.has_cp_data() {
+        Ok(gas.cp_ideal_coeffs())
+    } else {
+        Err(EosError::InvalidGas(
+            "the ideal gas heat capacity of the gas is unknown".into(),
+        ))
+    }
+}
+
+pub trait EosGas {
+    /// Attraction parameter a(T) in Pa.m6/mol2
+    fn a(&self, eos: Eos, t: f64) -> f64;
+    /// First derivative of `a` with respect to temperature
+    fn da_dt(&self, eos: Eos, t: f64) -> f64;
+    /// Second derivative of `a` with respect to temperature
+    fn d2a_dt2(&self, eos: Eos, t: f64) -> f64;
+    /// Co-volume parameter b in m3/mol
+    fn b(&self, eos: Eos) -> f64;
+    /// Volume translation c(T) in m3/mol, zero for untranslated equations
+    fn c(&self, eos: Eos, t: f64) -> f64;
+    /// First derivative of `c` with respect to temperature
+    fn dc_dt(&self, eos: Eos, t: f64) -> f64;
+    /// Second derivative of `c` with respect to temperature
+    fn d2c_dt2(&self, eos: Eos, t: f64) -> f64;
+    /// Parameters (δ1, δ2) of the generic cubic form, those of `Eos::deltas`
+    /// except for RKPR
+    fn deltas(&self, eos: Eos) -> (f64, f64);
+    /// Coefficients of the ideal gas isobaric heat capacity polynomial in J/(mol.K)
+    fn cp_ideal_coeffs(&self) -> [f64; 4];
+    /// Whether the ideal gas heat capacity is known, for all the components of a mixture.
+    /// Gases built without data (e.g. from their critical constants) have zero coefficients.
+    fn has_cp_data(&self) -> bool;
+    /// Notes about the validity of the equation of state for this gas
+    fn warnings(&self, eos: Eos) -> Vec<String>;
+    /// Fails if the constants of the gas would make a(T) or b infinite or NaN
+    fn check_constants(&self) -> Result<(), EosError>;
+
+    /// Parameters a and b at temperature `t`, computed at once
+    fn eos_params(&self, eos: Eos, t: f64) -> EosParams {
+        let (d1, d2) = self.deltas(eos);
+        EosParams {
+            a: self.a(eos, t),
+            b: self.b(eos),
+            c: self.c(eos, t),
+            d1,
+            d2,
         }
     }
-    fn b(&self, eos: Eos) -> f64 {
-        match self {
-            Gas::Pure(g) => g.b(eos),
-            Gas::Mixture(g) => g.b(eos),
+
+    /// All real roots of the cubic equation in Z, in ascending order
+    fn z_roots(&self, eos: Eos, p: Pascal, t: Kelvin) -> Result<Vec<f64>, EosError> {
+        self.check_constants()?;
+        self.eos_params(eos, t.0).z_roots(eos, p.0, t.0)
+    }
+
+    /// Compression factor
+    fn z(&self, eos: Eos, p: Pascal, t: Kelvin) -> Result<f64, EosError> {
+        self.check_constants()?;
+        self.eos_params(eos, t.0).z(eos, p.0, t.0)
+    }
+
+    /// Compression factor of the positive root selected by `root`,
+    /// instead of the vapor root of `z`
+    fn z_root(&self, eos: Eos, p: Pascal, t: Kelvin, root: Root) -> Result<f64, EosError> {
+        self.check_constants()?;
+        self.eos_params(eos, t.0).z_root(eos, p.0, t.0, root)
+    }
+
+    /// Compression factor of the physical root selected by `strategy`
+    /// (`RootStrategy::Vapor` for `z`)
+    fn z_with_strategy(
+        &self,
+        eos: Eos,
+        p: Pascal,
+        t: Kelvin,
+        strategy: RootStrategy,
+    ) -> Result<f64, EosError> {
+        self.check_constants()?;
+        self.eos_params(eos, t.0)
+            .z_with_strategy(eos, p.0, t.0, strategy)
+    }
+
+    /// Pressure of the gas of molar volume `v` in m3/mol at temperature `t`,
+    /// explicit in the equation of state (the inverse of `z`).
+    /// Fails if the volume is not above the co-volume.
+    fn pressure_from_volume(&self, eos: Eos, v: f64, t: Kelvin) -> Result<Pascal, EosError> {
+        self.check_constants()?;
+        let t = t.0;
+        let EosParams { a, b, c, d1, d2 } = self.eos_params(eos, t);
+        // volume of the cubic, before translation
+        let v = v + c;
+        if v <= b {
+            return Err(EosError::InvalidGas(format!(
+                "molar volume {} m3/mol is not above the co-volume {} m3/mol",
+                v - c,
+                b - c
+            )));
+        }
+        Ok(Pascal(R * t / (v - b) - a / ((v + d1 * b) * (v + d2 * b))))
+    }
+
+    /// Diagnostic lines describing the roots of the cubic in Z
+    fn debug_roots(&self, eos: Eos, p: Pascal, t: Kelvin) -> Vec<String> {
+        self.eos_params(eos, t.0).debug_roots(eos, p.0, t.0)
+    }
+
+    /// Enthalpy departure H - H_ideal in J/mol
+    fn enthalpy_departure(&self, eos: Eos, p: Pascal, t: Kelvin) -> Result<f64, EosError> {
+        Ok(State::new(self, eos, p.0, t.0)?.enthalpy_departure())
+    }
+
+    /// Entropy departure S - S_ideal at same pressure and temperature in J/(mol.K)
+    fn entropy_departure(&self, eos: Eos, p: Pascal, t: Kelvin) -> Result<f64, EosError> {
+        Ok(State::new(self, eos, p.0, t.0)?.entropy_departure())
+    }
+
+    /// Derivative of the compression factor with respect to temperature at constant
+    /// pressure (∂Z/∂T) in 1/K, differentiating the equation of state implicitly
+    /// with the temperature dependence of a(T) and of the translation c(T).
+    fn dz_dt(&self, eos: Eos, p: Pascal, t: Kelvin) -> Result<f64, EosError> {
+        Ok(State::new(self, eos, p.0, t.0)?.dz_dt())
+    }
+
+    /// Derivative of the compression factor with respect to pressure at constant
+    /// temperature (∂Z/∂P) in 1/Pa, differentiating the equation of state implicitly
+    fn dz_dp(&self, eos: Eos, p: Pascal, t: Kelvin) -> Result<f64, EosError> {
+        Ok(State::new(self, eos, p.0, t.0)?.dz_dp())
+    }
+
+    /// Compression factor with its derivatives (∂Z/∂P, ∂Z/∂T) of `dz_dp` and `dz_dt`,
+    /// solving the cubic equation once
+    fn z_derivatives(&self, eos: Eos, p: Pascal, t: Kelvin) -> Result<(f64, f64, f64), EosError> {
+        self.check_constants()?;
+        let (p, t) = (p.0, t.0);
+        let params = self.eos_params(eos, t);
+        let z = params.cubic_z(eos, p, t)?;
+        let state = State::with_z(self, eos, p, t, z);
+        Ok((z - params.z_shift(p, t), state.dz_dp(), state.dz_dt()))
+    }
+
+    /// Enthalpy in J/mol, anchored to zero for the gas at the reference state
+    /// `reference` = (t_ref, p_ref).
+    fn enthalpy(
+        &self,
+        eos: Eos,
+        p: Pascal,
+        t: Kelvin,
+        reference: (Kelvin, Pascal),
+    ) -> Result<f64, EosError> {
+        let (t_ref, p_ref) = reference;
+        let ideal = cp_poly_integral(&known_cp_coeffs(self)?, t_ref.0, t.0);
+        Ok(ideal + self.enthalpy_departure(eos, p, t)?
+            - self.enthalpy_departure(eos, p_ref, t_ref)?)
+    }
+
+    /// Entropy in J/(mol.K), anchored to zero for the gas at the reference state
+    /// `reference` = (t_ref, p_ref).
+    fn entropy(
+        &self,
+        eos: Eos,
+        p: Pascal,
+        t: Kelvin,
+        reference: (Kelvin, Pascal),
+    ) -> Result<f64, EosError> {
+        let (t_ref, p_ref) = reference;
+        let ideal = cp_poly_integral_over_t(&known_cp_coeffs(self)?, t_ref.0, t.0)
+            - R * (p.0 / p_ref.0).ln();
+        Ok(ideal + self.entropy_departure(eos, p, t)?
+            - self.entropy_departure(eos, p_ref, t_ref)?)
+    }
+
+    /// Final temperature of the process `path` from `p1` and `t1` to the pressure `p2`,
+    /// conserving the enthalpy or the entropy (ideal part and departure).
+    /// Both increase with the temperature: the root is bracketed from `t1` and bisected.
+    fn path_temperature(
+        &self,
+        eos: Eos,
+        path: ProcessPath,
+        (p1, t1): (Pascal, Kelvin),
+        p2: Pascal,
+        config: &SolverConfig,
+    ) -> Result<Kelvin, SolverError> {
+        // change of the conserved quantity from the initial state
+        let f = |t| {
+            match path {
+                ProcessPath::Isentropic => self.entropy(eos, p2, Kelvin(t), (t1, p1)),
+                ProcessPath::Isenthalpic => self.enthalpy(eos, p2, Kelvin(t), (t1, p1)),
+            }
+            .map_err(|err| SolverError::Domain(err.to_string()))
+        };
+        let (mut lo, mut hi) = (t1.0, t1.0);
+        let mut bracketed = false;
+        for _ in 0..config.max_iter {
+            if f(lo)? > 0f64 {
+                lo /= 1.25;
+            } else if f(hi)? < 0f64 {
+                hi *= 1.25;
+            } else {
+                bracketed = true;
+                break;
+            }
+        }
+        if bracketed {
+            for _ in 0..config.max_iter {
+                let mid = 0.5 * (lo + hi);
+                if hi - lo <= config.tol * mid {
+                    return Ok(Kelvin(mid));
+                }
+                if f(mid)? > 0f64 {
+                    hi = mid;
+                } else {
+                    lo = mid;
+                }
+            }
         }
+        Err(SolverError::NoConvergence {
+            max_iter: config.max_iter,
+        })
+    }
+
+    /// Real gas heat capacity ratio Cp/Cv
+    fn heat_capacity_ratio(&self, eos: Eos, p: Pascal, t: Kelvin) -> Result<f64, EosError> {
+        let (p, t) = (p.0, t.0);
+        let cp_ideal = cp_poly(&known_cp_coeffs(self)?, t);
+        let state = State::new(self, eos, p, t)?;
+        let cp = cp_ideal + state.residual_cp();
+        let cv = cp_ideal - R + state.residual_cv();
+        Ok(cp / cv)
+    }
+
+    /// Second virial coefficient B(T) = b - c - a(T)/(RT) in m3/mol,
+    /// the slope of Z with respect to 1/v at zero pressure
+    fn second_virial_coeff(&self, eos: Eos, t: Kelvin) -> f64 {
+        let t = t.0;
+        self.b(eos) - self.c(eos, t) - self.a(eos, t) / (R * t)
     }
 }
 
-#[test]
-fn test_eos() {
+impl PureGas {
+    /// Slope m of the Soave alpha function
+    fn soave_m(&self, eos: Eos) -> f64 {
+        let w = self.acentric_factor();
+        match eos {
+            Eos::SoaveRedlichKwong | Eos::SrkPeneloux => 0.48 + 1.574 * w - 0.176 * w * w,
+            Eos::PengRobinson | Eos::PrPeneloux | Eos::PrPenelouxT => {
+                if w <= 0.491 {
+                    0.37464 + 1.56226 * w - 0.26992 * w * w
+                } else {
+                    0.379642 + 1.487503 * w - 0.164423 * w * w - 0.016666 * w * w * w
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
 
-    let h2 = Gas::from_string("H2").unwrap();
-    let p700b = 101325f64 + 70_000_000f64;
+    /// Exponent k of the RKPR alpha function (3/(2+Tr))^k, correlated by Cismondi
+    /// and Mollerup (2005) to the acentric factor and the critical compressibility of the equation
+    fn rkpr_k(&self) -> f64 {
+        let w = self.acentric_factor();
+        let (_, _, zc) = rkpr_criticals(self.rkpr_delta1());
+        (-2.4407 * zc + 0.0017) * w * w + (7.4513 * zc + 1.9681) * w + (12.504 * zc - 2.7238)
+    }
+
+    /// Equation of state suited to the gas: Redlich-Kwong for the quantum gases of negative
+    /// (or unknown) acentric factor, out of the range of the alpha correlations of SRK and PR,
+    /// and Peng-Robinson otherwise
+    pub fn recommended_eos(&self) -> Eos {
+        match self.w {
+            Some(w) if w >= 0f64 => Eos::PengRobinson,
+            _ => Eos::RedlichKwong,
+        }
+    }
+
+    /// Boyle temperature, where the second virial coefficient vanishes.
+    /// Searched above the critical temperature, up to 100 Tc.
+    /// Returns None if B(T) doesn't change sign in this range.
+    pub fn boyle_temperature(&self, eos: Eos) -> Option<Kelvin> {
+        self.check_constants().ok()?;
+        let b = |t| self.second_virial_coeff(eos, Kelvin(t));
+        // B < 0 at Tc: bracket the first sign change
+        let mut lo = self.tc;
+        if b(lo) >= 0f64 {
+            return None;
+        }
+        let mut hi = lo * 1.5;
+        while b(hi) < 0f64 {
+            lo = hi;
+            hi *= 1.5;
+            if hi > 100f64 * self.tc {
+                return None;
+            }
+        }
+        // bisection down to the f64 resolution
+        while (hi - lo) > 1e-12 * hi {
+            let mid = 0.5 * (lo + hi);
+            if b(mid) < 0f64 {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        Some(Kelvin(0.5 * (lo + hi)))
+    }
+
+    /// Saturation pressure at temperature `t` and compression factors
+    /// of the liquid and vapor phases at this pressure
+    pub fn saturation(
+        &self,
+        eos: Eos,
+        t: Kelvin,
+        config: &SolverConfig,
+    ) -> Result<Saturation, SolverError> {
+        let p = self.saturation_pressure(eos, t, config)?.0;
+        let t = t.0;
+        let params = self.eos_params(eos, t);
+        let b = params.b * p / (R * t);
+        let shift = params.z_shift(p, t);
+        let roots: Vec<f64> = params
+            .cubic_roots(eos, p, t)
+            .map_err(|err| SolverError::Domain(format!("{}: {}", self.id, err)))?
+            .into_iter()
+            .filter(|&z| z > b)
+            .collect();
+        match (roots.first(), roots.last()) {
+            (Some(&zl), Some(&zv)) if roots.len() > 1 => Ok(Saturation {
+                t,
+                p,
+                z_liquid: zl - shift,
+                z_vapor: zv - shift,
+            }),
+            _ => Err(SolverError::Domain(format!(
+                "{}: no distinct liquid and vapor roots at {} Pa and {} K",
+                self.id, p, t
+            ))),
+        }
+    }
+
+    /// Enthalpy of vaporization in J/mol at temperature `t`: the difference between
+    /// the enthalpy departures of the saturated vapor and liquid (the ideal parts cancel).
+    /// None at and above the critical temperature.
+    pub fn enthalpy_of_vaporization(
+        &self,
+        eos: Eos,
+        t: Kelvin,
+        config: &SolverConfig,
+    ) -> Result<Option<f64>, SolverError> {
+        if t.0 >= self.tc {
+            return Ok(None);
+        }
+        let sat = self.saturation(eos, t, config)?;
+        let t = t.0;
+        let shift = self.eos_params(eos, t).z_shift(sat.p, t);
+        let liquid = State::with_z(self, eos, sat.p, t, sat.z_liquid + shift);
+        let vapor = State::with_z(self, eos, sat.p, t, sat.z_vapor + shift);
+        Ok(Some(
+            vapor.enthalpy_departure() - liquid.enthalpy_departure(),
+        ))
+    }
+
+    /// Saturation pressure at temperature `t`.
+    /// Solved by successive substitution on the fugacities of the liquid and vapor roots,
+    /// starting from the Wilson estimate.
+    pub fn saturation_pressure(
+        &self,
+        eos: Eos,
+        t: Kelvin,
+        config: &SolverConfig,
+    ) -> Result<Pascal, SolverError> {
+        self.check_constants()
+            .map_err(|err| SolverError::Domain(err.to_string()))?;
+        let t = t.0;
+        if t >= self.tc {
+            return Err(SolverError::Domain(format!(
+                "{}: no saturation above the critical temperature ({} K)",
+                self.id, self.tc
+            )));
+        }
+        let params = self.eos_params(eos, t);
+        let w = self.acentric_factor();
+        let mut p = self.pc * (5.373 * (1f64 + w) * (1f64 - self.tc / t)).exp();
+        for _ in 0..config.max_iter {
+            let b = params.b * p / (R * t);
+            // the volume translation doesn't change the phase equilibrium
+            let roots: Vec<f64> = params
+                .cubic_roots(eos, p, t)
+                .map_err(|err| SolverError::Domain(format!("{}: {}", self.id, err)))?
+                .into_iter()
+                .filter(|&z| z > b)
+                .collect();
+            let (zl, zv) = match (roots.first(), roots.last()) {
+                (Some(&zl), Some(&zv)) if roots.len() > 1 => (zl, zv),
+                (Some(&z), _) => {
+                    // single phase: move towards the two-phase region.
+                    // The critical volume lies between 3b (VdW) and 4b (PR).
+                    if z * R * t / p > 3.5 * params.b {
+                        p *= 1.2;
+                    } else {
+                        p *= 0.8;
+                    }
+                    continue;
+                }
+                _ => {
+                    return Err(SolverError::Domain(format!(
+                        "{}: no physical root at {} Pa and {} K",
+                        self.id, p, t
+                    )))
+                }
+            };
+            let ratio =
+                (params.ln_fugacity_coeff(p, t, zl) - params.ln_fugacity_coeff(p, t, zv)).exp();
+            p *= ratio;
+            if (ratio - 1f64).abs() < config.tol {
+                return Ok(Pascal(p));
+            }
+        }
+        Err(SolverError::NoConvergence {
+            max_iter: config.max_iter,
+        })
+    }
+}
+
+impl EosGas for PureGas {
+    fn a(&self, eos: Eos, t: f64) -> f64 {
+        match eos {
+            Eos::VanDerWaals => 27f64 * R * R * self.tc * self.tc / (64f64 * self.pc),
+            Eos::RedlichKwong => {
+                // Redlich-Kwong (1949): a(T) = 0.42748 R²Tc^2.5 / (Pc sqrt(T)),
+                // that is the SRK constant with alpha = 1/sqrt(Tr).
+                // The T^-0.5 is kept in a(T), so that the cubic is the same as SRK.
+                0.42748023 * R * R * self.tc.powf(2.5) / (self.pc * t.sqrt())
+            }
+            Eos::SoaveRedlichKwong | Eos::SrkPeneloux => {
+                // Soave (1972): alpha = (1 + m(1 - sqrt(Tr)))²
+                let m = self.soave_m(eos);
+                let alpha = 1f64 + m * (1f64 - (t / self.tc).sqrt());
+                let alpha = alpha * alpha;
+                alpha * 0.42748023 * R * R * self.tc * self.tc / self.pc
+            }
+            Eos::PengRobinson | Eos::PrPeneloux | Eos::PrPenelouxT => {
+                let m = self.soave_m(eos);
+                let alpha = 1f64 + m * (1f64 - (t / self.tc).sqrt());
+                let alpha = alpha * alpha;
+                alpha * 0.45724 * R * R * self.tc * self.tc / self.pc
+            }
+            Eos::Rkpr => {
+                // Cismondi and Mollerup (2005): alpha = (3/(2+Tr))^k
+                let alpha = (3f64 / (2f64 + t / self.tc)).powf(self.rkpr_k());
+                let (omega_a, _, _) = rkpr_criticals(self.rkpr_delta1());
+                alpha * omega_a * R * R * self.tc * self.tc / self.pc
+            }
+        }
+    }
+    fn da_dt(&self, eos: Eos, t: f64) -> f64 {
+        match eos {
+            Eos::VanDerWaals => 0f64,
+            Eos::RedlichKwong => -0.5 * self.a(eos, t) / t,
+            Eos::SoaveRedlichKwong
+            | Eos::SrkPeneloux
+            | Eos::PengRobinson
+            | Eos::PrPeneloux
+            | Eos::PrPenelouxT => {
+                // a = ac.alpha with alpha = g² and g = 1 + m(1 - sqrt(T/Tc))
+                let m = self.soave_m(eos);
+                let g = 1f64 + m * (1f64 - (t / self.tc).sqrt());
+                let ac = self.a(eos, t) / (g * g);
+                -ac * m * g / (t * self.tc).sqrt()
+            }
+            Eos::Rkpr => -self.rkpr_k() * self.a(eos, t) / (2f64 * self.tc + t),
+        }
+    }
+    fn d2a_dt2(&self, eos: Eos, t: f64) -> f64 {
+        match eos {
+            Eos::VanDerWaals => 0f64,
+            Eos::RedlichKwong => 0.75 * self.a(eos, t) / (t * t),
+            Eos::SoaveRedlichKwong
+            | Eos::SrkPeneloux
+            | Eos::PengRobinson
+            | Eos::PrPeneloux
+            | Eos::PrPenelouxT => {
+                let m = self.soave_m(eos);
+                let g = 1f64 + m * (1f64 - (t / self.tc).sqrt());
+                let ac = self.a(eos, t) / (g * g);
+                ac * m / (2f64 * t) * (m / self.tc + g / (t * self.tc).sqrt())
+            }
+            Eos::Rkpr => {
+                let k = self.rkpr_k();
+                let x = 2f64 * self.tc + t;
+                k * (k + 1f64) * self.a(eos, t) / (x * x)
+            }
+        }
+    }
+    fn b(&self, eos: Eos) -> f64 {
+        match eos {
+            Eos::VanDerWaals => R * self.tc / (8f64 * self.pc),
+            Eos::RedlichKwong | Eos::SoaveRedlichKwong | Eos::SrkPeneloux => {
+                0.08664035 * R * self.tc / self.pc
+            }
+            Eos::PengRobinson | Eos::PrPeneloux | Eos::PrPenelouxT => {
+                0.0778 * R * self.tc / self.pc
+            }
+            Eos::Rkpr => {
+                let (_, omega_b, _) = rkpr_criticals(self.rkpr_delta1());
+                omega_b * R * self.tc / self.pc
+            }
+        }
+    }
+    fn c(&self, eos: Eos, t: f64) -> f64 {
+        // Rackett compressibility estimated from the acentric factor (Yamada and Gunn)
+        let z_ra = 0.29056 - 0.08775 * self.acentric_factor();
+        let rtc_pc = R * self.tc / self.pc;
+        match eos {
+            // Peneloux et al. (1982)
+            Eos::SrkPeneloux => 0.40768 * (0.29441 - z_ra) * rtc_pc,
+            Eos::PrPeneloux => 0.50033 * (0.25969 - z_ra) * rtc_pc,
+            Eos::PrPenelouxT => {
+                // shift c0 of PrPeneloux far from Tc, rising to the one of Zc = z_ra at Tc
+                // with the exponential decay of Magoulas and Tassios (1990)
+                let c0 = self.c(Eos::PrPeneloux, t);
+                let c_crit = (0.3074 - z_ra) * rtc_pc;
+                let beta = -10.2447 - 28.6312 * self.acentric_factor();
+                c0 + (c_crit - c0) * (beta * (1f64 - t / self.tc).abs()).exp()
+            }
+            _ => 0f64,
+        }
+    }
+    fn dc_dt(&self, eos: Eos, t: f64) -> f64 {
+        match eos {
+            Eos::PrPenelouxT => {
+                // c0 and c_crit don't depend on the temperature
+                let tr = 1f64 - t / self.tc;
+                let c0 = self.c(Eos::PrPeneloux, t);
+                let beta = -10.2447 - 28.6312 * self.acentric_factor();
+                -(self.c(eos, t) - c0) * beta * tr.signum() / self.tc
+            }
+            _ => 0f64,
+        }
+    }
+    fn d2c_dt2(&self, eos: Eos, t: f64) -> f64 {
+        match eos {
+            Eos::PrPenelouxT => {
+                let c0 = self.c(Eos::PrPeneloux, t);
+                let beta = -10.2447 - 28.6312 * self.acentric_factor();
+                (self.c(eos, t) - c0) * beta * beta / (self.tc * self.tc)
+            }
+            _ => 0f64,
+        }
+    }
+    fn deltas(&self, eos: Eos) -> (f64, f64) {
+        eos.deltas()
+            .unwrap_or_else(|| rkpr_deltas(self.rkpr_delta1()))
+    }
+    fn cp_ideal_coeffs(&self) -> [f64; 4] {
+        self.cp_ideal_coeffs
+    }
+    fn has_cp_data(&self) -> bool {
+        self.cp_ideal_coeffs != [0f64; 4]
+    }
+    fn warnings(&self, eos: Eos) -> Vec<String> {
+        let mut res = Vec::new();
+        let uses_w = !matches!(eos, Eos::VanDerWaals | Eos::RedlichKwong);
+        match self.w {
+            None if uses_w => res.push(format!(
+                "{}: the acentric factor is unknown and taken as zero, {} loses accuracy",
+                self.id,
+                eos.name()
+            )),
+            Some(w)
+                if matches!(eos, Eos::PengRobinson | Eos::PrPeneloux | Eos::PrPenelouxT)
+                    && !(0f64..=PR_M_MAX_W).contains(&w) =>
+            {
+                res.push(format!(
+                    "{}: acentric factor {} is outside of the Peng-Robinson m-correlation range (0 to {}), the correlation is extrapolated",
+                    self.id, w, PR_M_MAX_W
+                ))
+            }
+            _ => {}
+        }
+        res
+    }
+    fn check_constants(&self) -> Result<(), EosError> {
+        // also rejects NaN
+        if !(self.tc > 0f64 && self.tc.is_finite()) {
+            return Err(EosError::InvalidGas(format!(
+                "{}: critical temperature must be positive (got {} K)",
+                self.id, self.tc
+            )));
+        }
+        if !(self.pc > 0f64 && self.pc.is_finite()) {
+            return Err(EosError::InvalidGas(format!(
+                "{}: critical pressure must be positive (got {} Pa)",
+                self.id, self.pc
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl GasMixture {
+    /// Derivatives of the mixing rules with respect to the amount of each component:
+    /// (∂(n².a)/∂ni / n, ∂(n.b)/∂ni), with `b` the co-volume of the mixture
+    fn mixing_derivatives(&self, eos: Eos, t: f64, b: f64) -> Vec<(f64, f64)> {
+        let ais: Vec<f64> = self.comps.iter().map(|c| c.pure_gas().a(eos, t)).collect();
+        let bis: Vec<f64> = self.comps.iter().map(|c| c.pure_gas().b(eos)).collect();
+
+        (0..self.comps.len())
+            .map(|i| {
+                // ∂(n².a)/∂ni
+                let da: f64 = 2f64
+                    * self
+                        .comps
+                        .iter()
+                        .zip(ais.iter())
+                        .map(|(cj, aj)| cj.molar_fraction() * (ais[i] * aj).sqrt())
+                        .sum::<f64>();
+                // ∂(n.b)/∂ni
+                let db = match self.b_rule {
+                    BRule::Linear => bis[i],
+                    BRule::Lorentz => {
+                        let sum: f64 = self
+                            .comps
+                            .iter()
+                            .zip(bis.iter())
+                            .map(|(cj, bj)| {
+                                let bij = (bis[i].cbrt() + bj.cbrt()) / 2f64;
+                                cj.molar_fraction() * bij * bij * bij
+                            })
+                            .sum();
+                        2f64 * sum - b
+                    }
+                };
+                (da, db)
+            })
+            .collect()
+    }
+
+    /// Derivatives ∂δ1/∂ni of the δ1 of RKPR averaged on the molar fractions,
+    /// zero for the other equations
+    fn delta1_derivatives(&self, eos: Eos) -> Vec<f64> {
+        let (d1, _) = self.deltas(eos);
+        self.comps
+            .iter()
+            .map(|c| match eos {
+                Eos::Rkpr => c.pure_gas().rkpr_delta1() - d1,
+                _ => 0f64,
+            })
+            .collect()
+    }
+
+    /// Partial molar volumes (∂V/∂ni) at constant T, P and nj in m3/mol, one per component.
+    /// v_i = -(∂P/∂ni) / (∂P/∂V), with the derivatives with respect to ni taken
+    /// through the mixing rules of a, b and δ1 of RKPR, less the volume translation of the component.
+    pub fn partial_molar_volumes(
+        &self,
+        eos: Eos,
+        p: Pascal,
+        t: Kelvin,
+    ) -> Result<Vec<f64>, EosError> {
+        let (p, t) = (p.0, t.0);
+        let state = State::new(self, eos, p, t)?;
+        let State {
+            v, a, b, d1, d2, ..
+        } = state;
+
+        let vb = v - b;
+        let denom = state.denom();
+        // derivative of (v+δ1.b)(v+δ2.b) with respect to b
+        let ddenom_db = d1 * (v + d2 * b) + d2 * (v + d1 * b);
+        let ddenom_dd1 = state.ddenom_dd1();
+        let dp_dv = state.dp_dv();
+
+        let res = self
+            .comps
+            .iter()
+            .zip(self.mixing_derivatives(eos, t, b))
+            .zip(self.delta1_derivatives(eos))
+            .map(|((ci, (da, db)), dd1)| {
+                let dp_dn = R * t / vb + R * t * db / (vb * vb) - da / denom
+                    + a * (ddenom_db * db + ddenom_dd1 * dd1) / (denom * denom);
+                -dp_dn / dp_dv - ci.pure_gas().c(eos, t)
+            })
+            .collect();
+        Ok(res)
+    }
+
+    /// Fugacity coefficients φi of the components in the mixture, one per component.
+    /// The fugacity of a component is xi.φi.P, its effective partial pressure.
+    pub fn fugacity_coeffs(&self, eos: Eos, p: Pascal, t: Kelvin) -> Result<Vec<f64>, EosError> {
+        let (p, t) = (p.0, t.0);
+        let state = State::new(self, eos, p, t)?;
+        let State { v, a, b, .. } = state;
+        let z = p * v / (R * t);
+        let attraction = a / (R * t) * state.attraction_integral();
+        let dattraction_dd1 = match eos {
+            Eos::Rkpr => a / (R * t) * state.dattraction_integral_dd1(),
+            _ => 0f64,
+        };
+
+        let res = self
+            .comps
+            .iter()
+            .zip(self.mixing_derivatives(eos, t, b))
+            .zip(self.delta1_derivatives(eos))
+            .map(|((ci, (da, db)), dd1)| {
+                let ln_phi = db / b * (z - 1f64)
+                    - ((v - b) * p / (R * t)).ln()
+                    - attraction * (da / a - db / b)
+                    - dattraction_dd1 * dd1
+                    - ci.pure_gas().c(eos, t) * p / (R * t);
+                ln_phi.exp()
+            })
+            .collect();
+        Ok(res)
+    }
+
+    /// Chemical potentials μi/RT of the untranslated cubic at temperature `t` in K,
+    /// volume `v` in m3 and amounts `n` in mol of the components,
+    /// up to terms that only depend on the temperature.
+    /// The δ1 of RKPR is averaged on the amounts `n`, with its derivatives ∂δ1/∂ni.
+    fn chemical_potentials(&self, eos: Eos, t: f64, v: f64, n: &[f64]) -> Vec<f64> {
+        let ais: Vec<f64> = self.comps.iter().map(|c| c.pure_gas().a(eos, t)).collect();
+        let bis: Vec<f64> = self.comps.iter().map(|c| c.pure_gas().b(eos)).collect();
+        let n_tot: f64 = n.iter().sum();
+        let d1is: Vec<f64> = self
+            .comps
+            .iter()
+            .map(|c| c.pure_gas().rkpr_delta1())
+            .collect();
+        let (d1, d2) = match eos {
+            Eos::Rkpr => {
+                let d1: f64 = n.iter().zip(d1is.iter()).map(|(ni, d)| ni * d).sum();
+                rkpr_deltas(d1 / n_tot)
+            }
+            _ => self.deltas(eos),
+        };
+        let dd1: Vec<f64> = d1is
+            .iter()
+            .map(|d1i| match eos {
+                Eos::Rkpr => (d1i - d1) / n_tot,
+                _ => 0f64,
+            })
+            .collect();
+        // ∂(n².a)/∂ni and ∂(n.b)/∂ni
+        let da: Vec<f64> = (0..n.len())
+            .map(|i| {
+                let sum: f64 = (0..n.len()).map(|j| n[j] * (ais[i] * ais[j]).sqrt()).sum();
+                2f64 * sum
+            })
+            .collect();
+        let d: f64 = (0..n.len()).map(|i| 0.5 * n[i] * da[i]).sum();
+        let (b, db): (f64, Vec<f64>) = match self.b_rule {
+            BRule::Linear => (n.iter().zip(bis.iter()).map(|(ni, bi)| ni * bi).sum(), bis),
+            BRule::Lorentz => {
+                let bij = |i: usize, j: usize| ((bis[i].cbrt() + bis[j].cbrt()) / 2f64).powi(3);
+                let sums: Vec<f64> = (0..n.len())
+                    .map(|i| (0..n.len()).map(|j| n[j] * bij(i, j)).sum())
+                    .collect();
+                let b = n.iter().zip(sums.iter()).map(|(ni, s)| ni * s).sum::<f64>() / n_tot;
+                let db = sums.iter().map(|s| (2f64 * s - b) / n_tot).collect();
+                (b, db)
+            }
+        };
+        // attraction term of the Helmholtz energy -D.F(B)/RT and the derivatives of F
+        // with respect to B and to the δ1 of RKPR
+        let (f, df, df_dd1) = if d1 == d2 {
+            let x = v + d1 * b;
+            (1f64 / x, -d1 / (x * x), 0f64)
+        } else {
+            let (x1, x2) = (v + d1 * b, v + d2 * b);
+            let f = (x1 / x2).ln() / (b * (d1 - d2));
+            let df_dd1 = match eos {
+                Eos::Rkpr => {
+                    let dd2 = rkpr_dd2_dd1(d1);
+                    (1f64 / x1 - dd2 / x2 - f * (1f64 - dd2)) / (d1 - d2)
+                }
+                _ => 0f64,
+            };
+            (f, (d1 / x1 - d2 / x2) / (b * (d1 - d2)) - f / b, df_dd1)
+        };
+        (0..n.len())
+            .map(|i| {
+                (n[i] / v).ln() - (1f64 - b / v).ln() + n_tot * db[i] / (v - b)
+                    - (da[i] * f + d * df * db[i] + d * df_dd1 * dd1[i]) / (R * t)
+            })
+            .collect()
+    }
+
+    /// Critical point (Tc, Pc) of a binary mixture, solved numerically
+    /// with the criteria of Heidemann and Khalil (1980): the matrix of the second derivatives
+    /// of the Helmholtz energy in the amounts is singular, and the third derivative
+    /// along its null vector vanishes.
+    /// Unlike the pseudo-critical point of Kay's rule (`Gas::criticals`), it is where the
+    /// liquid and vapor phases of the mixture become identical.
+    /// None for other numbers of components, or if the criteria aren't met.
+    pub fn mixture_critical_point(&self, eos: Eos) -> Option<(Kelvin, Pascal)> {
+        if self.comps.len() != 2 || self.check_constants().is_err() {
+            return None;
+        }
+        let (c0, c1) = (&self.comps[0], &self.comps[1]);
+        let n = [c0.molar_fraction(), c1.molar_fraction()];
+        let b = self.b(eos);
+        let tc_max = c0.pure_gas().tc.max(c1.pure_gas().tc);
+        let tc_min = c0.pure_gas().tc.min(c1.pure_gas().tc);
+        // matrix (∂²A/∂ni∂nj)/RT, by central differences of the chemical potentials
+        let q = |t: f64, v: f64| {
+            const H: f64 = 1e-5;
+            let mut q = [[0f64; 2]; 2];
+            for j in 0..2 {
+                let mut plus = n;
+                let mut minus = n;
+                plus[j] += H;
+                minus[j] -= H;
+                let (mu_p, mu_m) = (
+                    self.chemical_potentials(eos, t, v, &plus),
+                    self.chemical_potentials(eos, t, v, &minus),
+                );
+                for i in 0..2 {
+                    q[i][j] = (mu_p[i] - mu_m[i]) / (2f64 * H);
+                }
+            }
+            q
+        };
+        let det = |t: f64, v: f64| {
+            let q = q(t, v);
+            q[0][0] * q[1][1] - q[0][1] * q[1][0]
+        };
+        // temperature below which the mixture of volume v is unstable, from above tc_max
+        let spinodal_t = |v: f64| {
+            let mut hi = 3f64 * tc_max;
+            if det(hi, v) <= 0f64 {
+                return None;
+            }
+            let mut lo = hi;
+            while det(lo, v) > 0f64 {
+                hi = lo;
+                lo *= 0.9;
+                if lo < 0.1 * tc_min {
+                    return None;
+                }
+            }
+            for _ in 0..100 {
+                let mid = 0.5 * (lo + hi);
+                if det(mid, v) > 0f64 {
+                    hi = mid;
+                } else {
+                    lo = mid;
+                }
+            }
+            Some(0.5 * (lo + hi))
+        };
+        // third derivative of A/RT along the null vector at the spinodal temperature
+        let cubic_form = |v: f64| {
+            const H: f64 = 1e-3;
+            let t = spinodal_t(v)?;
+            let q = q(t, v);
+            let dn = if q[0][0].abs() > q[1][1].abs() {
+                [-q[0][1], q[0][0]]
+            } else {
+                [q[1][1], -q[1][0]]
+            };
+            let norm = (dn[0] * dn[0] + dn[1] * dn[1]).sqrt();
+            let dn = [dn[0] / norm, dn[1] / norm];
+            let f = |s: f64| {
+                let ns = [n[0] + s * dn[0], n[1] + s * dn[1]];
+                let mu = self.chemical_potentials(eos, t, v, &ns);
+                dn[0] * mu[0] + dn[1] * mu[1]
+            };
+            Some(((f(H) - 2f64 * f(0f64) + f(-H)) / (H * H), t))
+        };
+        // the critical volume is about 3 to 4 co-volumes for the cubic equations
+        let mut prev: Option<(f64, f64)> = None;
+        let mut bracket = None;
+        for k in (0..=80).map(|i| 1.5 + 0.1 * i as f64) {
+            let c = cubic_form(k * b).map(|(c, _)| c);
+            if let (Some((k0, c0)), Some(c)) = (prev, c) {
+                if c0 * c <= 0f64 {
+                    bracket = Some((k0, c0, k));
+                    break;
+                }
+            }
+            prev = c.map(|c| (k, c));
+        }
+        let (mut lo, c_lo, mut hi) = bracket?;
+        for _ in 0..60 {
+            let mid = 0.5 * (lo + hi);
+            let (c, _) = cubic_form(mid * b)?;
+            if c * c_lo > 0f64 {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        let v = 0.5 * (lo + hi) * b;
+        let (_, t) = cubic_form(v)?;
+        let (d1, d2) = self.deltas(eos);
+        let p = R * t / (v - b) - self.a(eos, t) / ((v + d1 * b) * (v + d2 * b));
+        Some((Kelvin(t), Pascal(p)))
+    }
+}
+
+impl EosGas for GasMixture {
+    fn a(&self, eos: Eos, t: f64) -> f64 {
+        let mut res = 0f64;
+        for i in self.comps.iter() {
+            let ai = i.pure_gas().a(eos, t);
+            for j in self.comps.iter() {
+                let aj = j.pure_gas().a(eos, t);
+                res += i.molar_fraction() * j.molar_fraction() * (ai * aj).sqrt();
+            }
+        }
+        res
+    }
+
+    fn da_dt(&self, eos: Eos, t: f64) -> f64 {
+        // derivative of sqrt(ai.aj)
+        let mut res = 0f64;
+        for i in self.comps.iter() {
+            let ai = i.pure_gas().a(eos, t);
+            let dai = i.pure_gas().da_dt(eos, t);
+            for j in self.comps.iter() {
+                let aj = j.pure_gas().a(eos, t);
+                let daj = j.pure_gas().da_dt(eos, t);
+                let aij = (ai * aj).sqrt();
+                res +=
+                    i.molar_fraction() * j.molar_fraction() * (dai * aj + ai * daj) / (2f64 * aij);
+            }
+        }
+        res
+    }
+
+    fn d2a_dt2(&self, eos: Eos, t: f64) -> f64 {
+        // second derivative of sqrt(ai.aj)
+        let mut res = 0f64;
+        for i in self.comps.iter() {
+            let ai = i.pure_gas().a(eos, t);
+            let dai = i.pure_gas().da_dt(eos, t);
+            let d2ai = i.pure_gas().d2a_dt2(eos, t);
+            for j in self.comps.iter() {
+                let aj = j.pure_gas().a(eos, t);
+                let daj = j.pure_gas().da_dt(eos, t);
+                let d2aj = j.pure_gas().d2a_dt2(eos, t);
+                let aij = (ai * aj).sqrt();
+                let num = dai * aj + ai * daj;
+                let d2aij = (d2ai * aj + 2f64 * dai * daj + ai * d2aj) / (2f64 * aij)
+                    - num * num / (4f64 * aij * aij * aij);
+                res += i.molar_fraction() * j.molar_fraction() * d2aij;
+            }
+        }
+        res
+    }
+
+    fn b(&self, eos: Eos) -> f64 {
+        let mut res = 0f64;
+        match self.b_rule {
+            BRule::Linear => {
+                for i in self.comps.iter() {
+                    res += i.molar_fraction() * i.pure_gas().b(eos);
+                }
+            }
+            BRule::Lorentz => {
+                for i in self.comps.iter() {
+                    for j in self.comps.iter() {
+                        let bij = (i.pure_gas().b(eos).cbrt() + j.pure_gas().b(eos).cbrt()) / 2f64;
+                        res += i.molar_fraction() * j.molar_fraction() * bij * bij * bij;
+                    }
+                }
+            }
+        }
+        res
+    }
+
+    fn c(&self, eos: Eos, t: f64) -> f64 {
+        self.comps
+            .iter()
+            .map(|i| i.molar_fraction() * i.pure_gas().c(eos, t))
+            .sum()
+    }
+
+    fn dc_dt(&self, eos: Eos, t: f64) -> f64 {
+        self.comps
+            .iter()
+            .map(|i| i.molar_fraction() * i.pure_gas().dc_dt(eos, t))
+            .sum()
+    }
+
+    fn d2c_dt2(&self, eos: Eos, t: f64) -> f64 {
+        self.comps
+            .iter()
+            .map(|i| i.molar_fraction() * i.pure_gas().d2c_dt2(eos, t))
+            .sum()
+    }
+
+    fn deltas(&self, eos: Eos) -> (f64, f64) {
+        // δ1 of RKPR averaged on the molar fractions
+        eos.deltas().unwrap_or_else(|| {
+            rkpr_deltas(
+                self.comps
+                    .iter()
+                    .map(|c| c.molar_fraction() * c.pure_gas().rkpr_delta1())
+                    .sum(),
+            )
+        })
+    }
+
+    fn cp_ideal_coeffs(&self) -> [f64; 4] {
+        let mut res = [0f64; 4];
+        for c in self.comps.iter() {
+            for (r, ci) in res.iter_mut().zip(c.pure_gas().cp_ideal_coeffs.iter()) {
+                *r += c.molar_fraction() * ci;
+            }
+        }
+        res
+    }
+
+    fn has_cp_data(&self) -> bool {
+        self.comps.iter().all(|c| c.pure_gas().has_cp_data())
+    }
+
+    fn warnings(&self, eos: Eos) -> Vec<String> {
+        self.comps
+            .iter()
+            .flat_map(|c| c.pure_gas().warnings(eos))
+            .collect()
+    }
+
+    fn check_constants(&self) -> Result<(), EosError> {
+        self.comps
+            .iter()
+            .try_for_each(|c| c.pure_gas().check_constants())
+    }
+}
+
+impl EosGas for Gas {
+    fn a(&self, eos: Eos, t: f64) -> f64 {
+        match self {
+            Gas::Pure(g) => g.a(eos, t),
+            Gas::Mixture(g) => g.a(eos, t),
+        }
+    }
+    fn da_dt(&self, eos: Eos, t: f64) -> f64 {
+        match self {
+            Gas::Pure(g) => g.da_dt(eos, t),
+            Gas::Mixture(g) => g.da_dt(eos, t),
+        }
+    }
+    fn d2a_dt2(&self, eos: Eos, t: f64) -> f64 {
+        match self {
+            Gas::Pure(g) => g.d2a_dt2(eos, t),
+            Gas::Mixture(g) => g.d2a_dt2(eos, t),
+        }
+    }
+    fn b(&self, eos: Eos) -> f64 {
+        match self {
+            Gas::Pure(g) => g.b(eos),
+            Gas::Mixture(g) => g.b(eos),
+        }
+    }
+    fn c(&self, eos: Eos, t: f64) -> f64 {
+        match self {
+            Gas::Pure(g) => g.c(eos, t),
+            Gas::Mixture(g) => g.c(eos, t),
+        }
+    }
+    fn dc_dt(&self, eos: Eos, t: f64) -> f64 {
+        match self {
+            Gas::Pure(g) => g.dc_dt(eos, t),
+            Gas::Mixture(g) => g.dc_dt(eos, t),
+        }
+    }
+    fn d2c_dt2(&self, eos: Eos, t: f64) -> f64 {
+        match self {
+            Gas::Pure(g) => g.d2c_dt2(eos, t),
+            Gas::Mixture(g) => g.d2c_dt2(eos, t),
+        }
+    }
+    fn deltas(&self, eos: Eos) -> (f64, f64) {
+        match self {
+            Gas::Pure(g) => g.deltas(eos),
+            Gas::Mixture(g) => g.deltas(eos),
+        }
+    }
+    fn cp_ideal_coeffs(&self) -> [f64; 4] {
+        match self {
+            Gas::Pure(g) => g.cp_ideal_coeffs(),
+            Gas::Mixture(g) => g.cp_ideal_coeffs(),
+        }
+    }
+    fn has_cp_data(&self) -> bool {
+        match self {
+            Gas::Pure(g) => g.has_cp_data(),
+            Gas::Mixture(g) => g.has_cp_data(),
+        }
+    }
+    fn warnings(&self, eos: Eos) -> Vec<String> {
+        match self {
+            Gas::Pure(g) => g.warnings(eos),
+            Gas::Mixture(g) => g.warnings(eos),
+        }
+    }
+    fn check_constants(&self) -> Result<(), EosError> {
+        match self {
+            Gas::Pure(g) => g.check_constants(),
+            Gas::Mixture(g) => g.check_constants(),
+        }
+    }
+}
+
+/// Properties of a gas at a pressure and temperature, derived from the vapor root
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Properties {
+    /// Compression factor
+    pub z: f64,
+    /// Molar volume in m3/mol
+    pub molar_volume: f64,
+    /// Density in kg/m3, None if the molar mass is unknown
+    pub density: Option<f64>,
+    /// Fugacity coefficient
+    pub fugacity_coeff: f64,
+    /// Enthalpy departure H - H_ideal in J/mol
+    pub enthalpy_departure: f64,
+    /// Entropy departure S - S_ideal in J/(mol.K)
+    pub entropy_departure: f64,
+    /// Residual isobaric heat capacity Cp - Cp_ideal in J/(mol.K)
+    pub residual_cp: f64,
+    /// Heat capacity ratio Cp/Cv, None if the ideal gas heat capacity is unknown
+    pub gamma: Option<f64>,
+    /// Speed of sound in m/s, None if the molar mass or the ideal gas heat capacity is unknown
+    pub speed_of_sound: Option<f64>,
+    /// Joule-Thomson coefficient (∂T/∂P) at constant enthalpy in K/Pa,
+    /// None if the ideal gas heat capacity is unknown
+    pub joule_thomson: Option<f64>,
+}
+
+impl Gas {
+    /// Properties at pressure `p` and temperature `t`,
+    /// all derived from a single solve of the equation of state
+    pub fn properties(&self, eos: Eos, p: Pascal, t: Kelvin) -> Result<Properties, EosError> {
+        self.check_constants()?;
+        let (p, t) = (p.0, t.0);
+        let params = self.eos_params(eos, t);
+        let cubic_z = params.cubic_z(eos, p, t)?;
+        let shift = params.z_shift(p, t);
+        let state = State::with_z(self, eos, p, t, cubic_z);
+        let z = cubic_z - shift;
+        let molar_volume = z * R * t / p;
+        let molar_mass = self.molar_mass();
+
+        let cp_ideal = if self.has_cp_data() {
+            Some(cp_poly(&self.cp_ideal_coeffs(), t))
+        } else {
+            None
+        };
+        let cp = cp_ideal.map(|cp| cp + state.residual_cp());
+        let gamma = cp_ideal.map(|cp_ideal| {
+            (cp_ideal + state.residual_cp()) / (cp_ideal - R + state.residual_cv())
+        });
+        let (dp_dt, dp_dv) = (state.dp_dt(), state.dp_dv());
+        let speed_of_sound = match (gamma, molar_mass) {
+            (Some(gamma), Some(m)) => {
+                Some((-gamma * molar_volume * molar_volume * dp_dv / m).sqrt())
+            }
+            _ => None,
+        };
+        // (∂v/∂T) at constant pressure is -(∂P/∂T)/(∂P/∂v), with the translation c(T) in ∂P/∂T
+        let joule_thomson = cp.map(|cp| (-t * dp_dt / dp_dv - molar_volume) / cp);
+
+        Ok(Properties {
+            z,
+            molar_volume,
+            density: molar_mass.map(|m| m / molar_volume),
+            fugacity_coeff: (params.ln_fugacity_coeff(p, t, cubic_z) - shift).exp(),
+            enthalpy_departure: state.enthalpy_departure(),
+            entropy_departure: state.entropy_departure(),
+            residual_cp: state.residual_cp(),
+            gamma,
+            speed_of_sound,
+            joule_thomson,
+        })
+    }
+
+    /// Pressure of the gas of density `density` in kg/m3 at temperature `t`
+    pub fn pressure_from_density(
+        &self,
+        eos: Eos,
+        density: f64,
+        t: Kelvin,
+    ) -> Result<Pascal, EosError> {
+        let molar_mass = self
+            .molar_mass()
+            .ok_or_else(|| EosError::InvalidGas("the molar mass of the gas is unknown".into()))?;
+        self.pressure_from_volume(eos, molar_mass / density, t)
+    }
+
+    /// Density in kg/m3 of the liquid root (the smallest physical root, after volume translation).
+    /// Out of the two-phase region, this is the single root, which is a vapor
+    /// below the saturation pressure.
+    pub fn liquid_density(&self, eos: Eos, p: Pascal, t: Kelvin) -> Result<f64, EosError> {
+        let molar_mass = self
+            .molar_mass()
+            .ok_or_else(|| EosError::InvalidGas("the molar mass of the gas is unknown".into()))?;
+        let z = self.z_with_strategy(eos, p, t, RootStrategy::Liquid)?;
+        Ok(molar_mass * p.0 / (z * R * t.0))
+    }
+
+    /// Viscosity in Pa.s estimated from the density of the vapor root
+    /// (see `viscosity::lee_gonzalez_eakin` for the validity)
+    pub fn viscosity(&self, eos: Eos, p: Pascal, t: Kelvin) -> Result<f64, EosError> {
+        let molar_mass = self
+            .molar_mass()
+            .ok_or_else(|| EosError::InvalidGas("the molar mass of the gas is unknown".into()))?;
+        let z = self.z(eos, p, t)?;
+        let density = molar_mass * p.0 / (z * R * t.0);
+        Ok(viscosity::lee_gonzalez_eakin(molar_mass, density, t))
+    }
+}
+
+#[test]
+fn test_properties() {
+    let n2 = Gas::from_string("N2").unwrap();
+    let (p, t) = (1e5, 300f64);
+    for &eos in Eos::all() {
+        let props = n2.properties(eos, Pascal(p), Kelvin(t)).unwrap();
+        assert_eq!(props.z, n2.z(eos, Pascal(p), Kelvin(t)).unwrap());
+        assert_approx_eq!(f64, props.molar_volume, props.z * R * t / p, ulps = 4);
+        assert_approx_eq!(
+            f64,
+            props.enthalpy_departure,
+            n2.enthalpy_departure(eos, Pascal(p), Kelvin(t)).unwrap(),
+            epsilon = 1e-9
+        );
+        assert_approx_eq!(
+            f64,
+            props.gamma.unwrap(),
+            n2.heat_capacity_ratio(eos, Pascal(p), Kelvin(t)).unwrap(),
+            epsilon = 1e-12
+        );
+    }
+    // about 1.12 kg/m3, 353 m/s and 0.22 K/bar for nitrogen at 1 bar and 300 K (NIST)
+    let props = n2
+        .properties(Eos::PengRobinson, Pascal(p), Kelvin(t))
+        .unwrap();
+    assert!((props.density.unwrap() - 1.123).abs() < 0.01);
+    assert!((props.speed_of_sound.unwrap() - 353f64).abs() < 5f64);
+    assert!((props.joule_thomson.unwrap() * 1e5 - 0.22).abs() < 0.05);
+    assert!(props.fugacity_coeff < 1f64 && props.fugacity_coeff > 0.99);
+
+    // gases built without data
+    let crit = Gas::from_string("crit:Tc=126.2,Pc=33.9e5,w=0.039").unwrap();
+    let props = crit
+        .properties(Eos::PengRobinson, Pascal(p), Kelvin(t))
+        .unwrap();
+    assert_eq!(props.density, None);
+    assert_eq!(props.gamma, None);
+    assert_eq!(props.speed_of_sound, None);
+    assert_eq!(props.joule_thomson, None);
+}
+
+#[test]
+fn test_cubic_coeffs() {
+    let n2 = find_gas("N2").unwrap();
+    let (p, t) = (100e5, 300f64);
+    for &eos in Eos::all() {
+        let params = n2.eos_params(eos, t);
+        let (a, b) = params.dimensionless(p, t);
+        assert!(a > 0f64 && b > 0f64);
+        // the untranslated Z is a root
+        let (a3, a2, a1, a0) = params.cubic_coeffs(eos, p, t);
+        let z = params.cubic_z(eos, p, t).unwrap();
+        let residual = ((a3 * z + a2) * z + a1) * z + a0;
+        assert_approx_eq!(f64, residual, 0f64, epsilon = 1e-12);
+        // Z = 1 for an ideal gas
+        let ideal = EosParams {
+            a: 0f64,
+            b: 0f64,
+            ..params
+        };
+        let (_, a2, a1, a0) = ideal.cubic_coeffs(eos, p, t);
+        assert_eq!((a2, a1, a0), (-1f64, 0f64, 0f64));
+    }
+}
+
+#[test]
+fn test_mixture_critical_point() {
+    let n2 = *find_gas("N2").unwrap();
+    let o2 = *find_gas("O2").unwrap();
+    let mix = |x: f64| GasMixture {
+        comps: vec![(x, n2), (1f64 - x, o2)],
+        b_rule: BRule::Linear,
+    };
+    for &eos in &[
+        Eos::RedlichKwong,
+        Eos::PengRobinson,
+        Eos::SrkPeneloux,
+        Eos::Rkpr,
+    ] {
+        let (Kelvin(tc), Pascal(pc)) = mix(0.5).mixture_critical_point(eos).unwrap();
+        assert!(tc > n2.tc && tc < o2.tc, "{:?}: {}", eos, tc);
+        assert!(pc > n2.pc && pc < o2.pc, "{:?}: {}", eos, pc);
+
+        // the pure critical point at the end of the critical locus
+        let (Kelvin(tc), Pascal(pc)) = mix(0.9999).mixture_critical_point(eos).unwrap();
+        assert_approx_eq!(f64, tc, n2.tc, epsilon = 0.01);
+        assert_approx_eq!(f64, pc / n2.pc, 1f64, epsilon = 0.001);
+    }
+
+    // the chemical potentials derive from the Helmholtz energy: their derivatives
+    // ∂μi/∂nj are symmetric, also with the δ1 of RKPR depending on the composition
+    let co2 = *find_gas("CO2").unwrap();
+    let c6h6 = *find_gas("C6H6").unwrap();
+    for &b_rule in &[BRule::Linear, BRule::Lorentz] {
+        let mix = GasMixture {
+            comps: vec![(0.5, co2), (0.5, c6h6)],
+            b_rule,
+        };
+        for &eos in &[Eos::PengRobinson, Eos::Rkpr] {
+            let (t, v) = (400f64, 4f64 * mix.b(eos));
+            let h = 1e-5;
+            let mu = |n0, n1| mix.chemical_potentials(eos, t, v, &[n0, n1]);
+            let dmu0_dn1 = (mu(0.5, 0.5 + h)[0] - mu(0.5, 0.5 - h)[0]) / (2f64 * h);
+            let dmu1_dn0 = (mu(0.5 + h, 0.5)[1] - mu(0.5 - h, 0.5)[1]) / (2f64 * h);
+            assert_approx_eq!(f64, dmu0_dn1, dmu1_dn0, epsilon = 1e-6);
+        }
+    }
+
+    // the Lorentz rule of the co-volume moves the critical point of an asymmetric mixture
+    let mix = |b_rule| GasMixture {
+        comps: vec![(0.5, co2), (0.5, c6h6)],
+        b_rule,
+    };
+    let pr = Eos::PengRobinson;
+    let (Kelvin(tc), Pascal(pc)) = mix(BRule::Lorentz).mixture_critical_point(pr).unwrap();
+    assert!(tc > co2.tc && tc < c6h6.tc, "{}", tc);
+    assert!(pc > co2.pc.min(c6h6.pc), "{}", pc);
+    let (Kelvin(tc_linear), _) = mix(BRule::Linear).mixture_critical_point(pr).unwrap();
+    assert!((tc - tc_linear).abs() > 0.1, "{} {}", tc, tc_linear);
+
+    let ar = *find_gas("Ar").unwrap();
+    let air = GasMixture {
+        comps: vec![(0.78, n2), (0.21, o2), (0.01, ar)],
+        b_rule: BRule::Linear,
+    };
+    assert_eq!(air.mixture_critical_point(Eos::PengRobinson), None);
+}
+
+#[test]
+fn test_recommended_eos() {
+    let eos = |id| find_gas(id).unwrap().recommended_eos();
+    assert_eq!(eos("N2"), Eos::PengRobinson);
+    assert_eq!(eos("CO2"), Eos::PengRobinson);
+    assert_eq!(eos("He"), Eos::RedlichKwong);
+    assert_eq!(eos("H2"), Eos::RedlichKwong);
+    let unknown = PureGas::new("X", "X", 300f64, 5e6, None).unwrap();
+    assert_eq!(unknown.recommended_eos(), Eos::RedlichKwong);
+}
+
+#[test]
+fn test_pressure_from_density() {
+    // above the critical temperatures, where the vapor root is the single root
+    let t = 350f64;
+    for spec in &["N2", "78%N2+21%O2+Ar", "CO2+C2H6"] {
+        let gas = Gas::from_string(spec).unwrap();
+        let m = gas.molar_mass().unwrap();
+        for &eos in Eos::all() {
+            for &density in &[1f64, 100f64, 300f64] {
+                let p = gas
+                    .pressure_from_density(eos, density, Kelvin(t))
+                    .unwrap()
+                    .0;
+                let z = gas.z(eos, Pascal(p), Kelvin(t)).unwrap();
+                assert_approx_eq!(f64, m * p / (z * R * t), density, epsilon = 1e-6);
+            }
+        }
+    }
+    let n2 = Gas::from_string("N2").unwrap();
+    // denser than the co-volume allows
+    let res = n2.pressure_from_density(Eos::PengRobinson, 2000f64, Kelvin(t));
+    assert!(res.is_err());
+    // unknown molar mass
+    let crit = Gas::from_string("crit:Tc=126.2,Pc=33.9e5,w=0.039").unwrap();
+    let res = crit.pressure_from_density(Eos::PengRobinson, 1f64, Kelvin(t));
+    assert!(res.is_err());
+}
+
+#[test]
+fn test_eos() {
+    let h2 = Gas::from_string("H2").unwrap();
+    let p700b = 101325f64 + 70_000_000f64;
     let t15c = 273.15 + 15f64;
-    assert_approx_eq!(f64, h2.z(Eos::VanDerWaals, p700b, t15c), 1.6818452, epsilon = 0.00001);
-    assert_approx_eq!(f64, h2.z(Eos::RedlichKwong, p700b, t15c), 1.506842, epsilon = 0.00001);
-    assert_approx_eq!(f64, h2.z(Eos::SoaveRedlichKwong, p700b, t15c), 1.48638434, epsilon = 0.00001);
-    assert_approx_eq!(f64, h2.z(Eos::PengRobinson, p700b, t15c), 1.396375, epsilon = 0.00001);
+    assert_approx_eq!(
+        f64,
+        h2.z(Eos::VanDerWaals, Pascal(p700b), Kelvin(t15c)).unwrap(),
+        1.6818452,
+        epsilon = 0.00001
+    );
+    // exact value before the RK/SRK unification of the cubic
+    assert_eq!(
+        h2.z(Eos::RedlichKwong, Pascal(p700b), Kelvin(t15c))
+            .unwrap(),
+        1.5068416945496312
+    );
+    assert_approx_eq!(
+        f64,
+        h2.z(Eos::RedlichKwong, Pascal(p700b), Kelvin(t15c))
+            .unwrap(),
+        1.506842,
+        epsilon = 0.00001
+    );
+    assert_approx_eq!(
+        f64,
+        h2.z(Eos::SoaveRedlichKwong, Pascal(p700b), Kelvin(t15c))
+            .unwrap(),
+        1.48638434,
+        epsilon = 0.00001
+    );
+    assert_approx_eq!(
+        f64,
+        h2.z(Eos::PengRobinson, Pascal(p700b), Kelvin(t15c))
+            .unwrap(),
+        1.396375,
+        epsilon = 0.00001
+    );
+}
+
+#[test]
+fn test_pr_m_warning() {
+    let n2 = Gas::from_string("N2").unwrap();
+    assert!(n2.warnings(Eos::PengRobinson).is_empty());
+
+    let heavy = PureGas {
+        id: "C20",
+        name: "Heavy pseudo-component",
+        tc: 768f64,
+        pc: 1_070_000f64,
+        w: Some(0.907f64),
+        molar_mass: None,
+        cp_ideal_coeffs: [470f64, 0f64, 0f64, 0f64],
+        valid_t: None,
+        valid_p: None,
+        rkpr_delta1: Some(rkpr_delta1(0.219)),
+    };
+    assert_eq!(heavy.warnings(Eos::PengRobinson).len(), 1);
+    assert!(heavy.warnings(Eos::SoaveRedlichKwong).is_empty());
+
+    let mix = GasMixture {
+        comps: vec![(0.9, *find_gas("N2").unwrap()), (0.1, heavy)],
+        b_rule: BRule::Linear,
+    };
+    let warnings = mix.warnings(Eos::PengRobinson);
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].starts_with("C20:"));
+}
+
+#[test]
+fn test_unknown_acentric_warning() {
+    let unknown = PureGas::new("X", "X", 300f64, 5e6, None).unwrap();
+    let zero = PureGas::new("Y", "Y", 300f64, 5e6, Some(0f64)).unwrap();
+    let is_unknown = |warnings: Vec<String>| warnings.iter().any(|w| w.contains("unknown"));
+    for &eos in Eos::all() {
+        let uses_w = eos != Eos::VanDerWaals && eos != Eos::RedlichKwong;
+        assert_eq!(is_unknown(unknown.warnings(eos)), uses_w, "{:?}", eos);
+        assert!(zero.warnings(eos).is_empty(), "{:?}", eos);
+        // the unknown acentric factor is taken as zero
+        assert_eq!(
+            unknown.z(eos, Pascal(5e6), Kelvin(350f64)),
+            zero.z(eos, Pascal(5e6), Kelvin(350f64))
+        );
+    }
+    // near-zero acentric factors of the database are known
+    for id in &["Ar", "Kr", "Ne"] {
+        let gas = find_gas(id).unwrap();
+        assert!(!is_unknown(gas.warnings(Eos::PengRobinson)));
+    }
+
+    let mix = GasMixture {
+        comps: vec![(0.9, *find_gas("N2").unwrap()), (0.1, unknown)],
+        b_rule: BRule::Linear,
+    };
+    let warnings = mix.warnings(Eos::SoaveRedlichKwong);
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].starts_with("X: the acentric factor is unknown"));
+}
+
+#[test]
+fn test_a_derivatives() {
+    let co2 = Gas::from_string("CO2").unwrap();
+    let mix = Gas::from_string("80%N2+CO2").unwrap();
+    let t = 300f64;
+    let h = 1e-3;
+    for gas in [co2, mix].iter() {
+        for &eos in Eos::all() {
+            let da = (gas.a(eos, t + h) - gas.a(eos, t - h)) / (2f64 * h);
+            let d2a = (gas.da_dt(eos, t + h) - gas.da_dt(eos, t - h)) / (2f64 * h);
+            assert_approx_eq!(f64, gas.da_dt(eos, t), da, epsilon = 1e-9);
+            assert_approx_eq!(f64, gas.d2a_dt2(eos, t), d2a, epsilon = 1e-9);
+            let dc = (gas.c(eos, t + h) - gas.c(eos, t - h)) / (2f64 * h);
+            let d2c = (gas.dc_dt(eos, t + h) - gas.dc_dt(eos, t - h)) / (2f64 * h);
+            assert_approx_eq!(f64, gas.dc_dt(eos, t), dc, epsilon = 1e-12);
+            assert_approx_eq!(f64, gas.d2c_dt2(eos, t), d2c, epsilon = 1e-12);
+        }
+    }
+}
+
+#[test]
+fn test_thermal_consistency() {
+    // residual Cp, entropy and Joule-Thomson coefficient against the enthalpy departure
+    // and the molar volume, also with the temperature-dependent translation of PRPT
+    let co2 = Gas::from_string("CO2").unwrap();
+    let (p, t) = (50e5, 320f64);
+    let h = 1e-3;
+    for &eos in Eos::all() {
+        let hdep = |t| co2.enthalpy_departure(eos, Pascal(p), Kelvin(t)).unwrap();
+        let sdep = |t| co2.entropy_departure(eos, Pascal(p), Kelvin(t)).unwrap();
+        let props = co2.properties(eos, Pascal(p), Kelvin(t)).unwrap();
+        let cp_res = (hdep(t + h) - hdep(t - h)) / (2f64 * h);
+        assert_approx_eq!(f64, props.residual_cp / cp_res, 1f64, epsilon = 1e-5);
+        let ds = (sdep(t + h) - sdep(t - h)) / (2f64 * h);
+        assert_approx_eq!(f64, ds * t / cp_res, 1f64, epsilon = 1e-5);
+
+        // μJT = (T.(∂v/∂T) - v) / Cp
+        let v = |t| co2.z(eos, Pascal(p), Kelvin(t)).unwrap() * R * t / p;
+        let dv_dt = (v(t + h) - v(t - h)) / (2f64 * h);
+        let cp = props.joule_thomson.unwrap();
+        let cp = (t * dv_dt - props.molar_volume) / cp;
+        let cp_ideal = cp_poly(&co2.cp_ideal_coeffs(), t);
+        assert_approx_eq!(f64, cp / (cp_ideal + cp_res), 1f64, epsilon = 1e-5);
+    }
+}
+
+#[test]
+fn test_dz_dt() {
+    let co2 = Gas::from_string("CO2").unwrap();
+    let (p, t) = (50e5, 320f64);
+    let h = 1e-3;
+    for &eos in Eos::all() {
+        let z_hi = co2.z(eos, Pascal(p), Kelvin(t + h)).unwrap();
+        let z_lo = co2.z(eos, Pascal(p), Kelvin(t - h)).unwrap();
+        let fd = (z_hi - z_lo) / (2f64 * h);
+        let dz_dt = co2.dz_dt(eos, Pascal(p), Kelvin(t)).unwrap();
+        assert_approx_eq!(f64, dz_dt / fd, 1f64, epsilon = 1e-5);
+        // the gas gets closer to ideal when heated
+        assert!(dz_dt > 0f64);
+
+        let hp = 1f64;
+        let z_hi = co2.z(eos, Pascal(p + hp), Kelvin(t)).unwrap();
+        let z_lo = co2.z(eos, Pascal(p - hp), Kelvin(t)).unwrap();
+        let fd = (z_hi - z_lo) / (2f64 * hp);
+        let dz_dp = co2.dz_dp(eos, Pascal(p), Kelvin(t)).unwrap();
+        assert_approx_eq!(f64, dz_dp / fd, 1f64, epsilon = 1e-5);
+        // and more compressible under pressure
+        assert!(dz_dp < 0f64);
+
+        let z = co2.z(eos, Pascal(p), Kelvin(t)).unwrap();
+        assert_eq!(
+            co2.z_derivatives(eos, Pascal(p), Kelvin(t)),
+            Ok((z, dz_dp, dz_dt))
+        );
+    }
+}
+
+#[test]
+fn test_heat_capacity_ratio() {
+    let n2 = Gas::from_string("N2").unwrap();
+    for &eos in Eos::all() {
+        let gamma = n2
+            .heat_capacity_ratio(eos, Pascal(1e3), Kelvin(293.15))
+            .unwrap();
+        assert_approx_eq!(f64, gamma, 1.4, epsilon = 0.002);
+    }
+    // real gas effects increase gamma at high pressure
+    assert!(
+        n2.heat_capacity_ratio(Eos::PengRobinson, Pascal(200e5), Kelvin(293.15))
+            .unwrap()
+            > 1.45
+    );
+
+    // no heat capacity data for a gas from its critical constants, nor a mixture with it
+    let generic = Gas::Pure(PureGas::generic(0.1));
+    let mix = Gas::Mixture(GasMixture {
+        comps: vec![
+            (0.5, *find_gas("N2").unwrap()),
+            (0.5, PureGas::generic(0.1)),
+        ],
+        b_rule: BRule::Linear,
+    });
+    for gas in &[generic, mix] {
+        let pr = Eos::PengRobinson;
+        assert!(!gas.has_cp_data());
+        let (p, t) = (Pascal(1e5), Kelvin(293.15));
+        let reference = (Kelvin(298.15), Pascal(1e5));
+        assert!(gas.heat_capacity_ratio(pr, p, t).is_err());
+        assert!(gas.enthalpy(pr, p, t, reference).is_err());
+        assert!(gas.entropy(pr, p, t, reference).is_err());
+        let config = SolverConfig::default();
+        let path = ProcessPath::Isenthalpic;
+        assert!(gas
+            .path_temperature(pr, path, (Pascal(10e5), t), p, &config)
+            .is_err());
+    }
+}
+
+#[test]
+fn test_reference_state() {
+    let co2 = Gas::from_string("CO2").unwrap();
+    let reference = (Kelvin(298.15), Pascal(1e5));
+    let (t, p) = reference;
+    for &eos in Eos::all() {
+        assert_approx_eq!(f64, co2.enthalpy(eos, p, t, reference).unwrap(), 0f64);
+        assert_approx_eq!(f64, co2.entropy(eos, p, t, reference).unwrap(), 0f64);
+    }
+
+    // ideal gas limit: only the heat capacity contributes
+    let n2 = Gas::from_string("N2").unwrap();
+    let low = Pascal(1e-3);
+    let h = n2
+        .enthalpy(Eos::PengRobinson, low, Kelvin(398.15), (t, low))
+        .unwrap();
+    let ideal = cp_poly_integral(&n2.cp_ideal_coeffs(), 298.15, 398.15);
+    assert_approx_eq!(f64, h, ideal, epsilon = 1e-3);
+    assert_approx_eq!(f64, ideal, 100f64 * 29.1, epsilon = 10f64);
+
+    // throttling a real gas: the departure makes enthalpy decrease with pressure
+    let h_hp = co2
+        .enthalpy(Eos::PengRobinson, Pascal(50e5), Kelvin(298.15), reference)
+        .unwrap();
+    assert!(h_hp < 0f64);
+}
+
+#[test]
+fn test_eos_params() {
+    let t = 250f64;
+    for spec in ["CO2", "78%N2+21%O2+Ar"].iter() {
+        let gas = Gas::from_string(spec).unwrap();
+        for &eos in Eos::all() {
+            let params = gas.eos_params(eos, t);
+            assert_eq!(params.a, gas.a(eos, t));
+            assert_eq!(params.b, gas.b(eos));
+            assert_eq!(
+                params.z(eos, 50e5, t).unwrap(),
+                gas.z(eos, Pascal(50e5), Kelvin(t)).unwrap()
+            );
+        }
+    }
+}
+
+#[test]
+fn test_saturation_pressure() {
+    // N2 at 100 K: 7.78 bar (NIST)
+    let n2 = find_gas("N2").unwrap();
+    let config = SolverConfig::default();
+    let p = n2
+        .saturation_pressure(Eos::PengRobinson, Kelvin(100f64), &config)
+        .unwrap()
+        .0;
+    assert!((p / 7.78e5 - 1f64).abs() < 0.03, "p = {}", p);
+    assert!(n2
+        .saturation_pressure(Eos::PengRobinson, Kelvin(130f64), &config)
+        .is_err());
+
+    let config = SolverConfig {
+        max_iter: 1,
+        ..config
+    };
+    assert_eq!(
+        n2.saturation_pressure(Eos::PengRobinson, Kelvin(100f64), &config),
+        Err(SolverError::NoConvergence { max_iter: 1 })
+    );
+}
+
+#[test]
+fn test_debug_roots() {
+    // CO2 liquid-vapor region: three roots
+    let co2 = find_gas("CO2").unwrap();
+    let lines = co2.debug_roots(Eos::PengRobinson, Pascal(20e5), Kelvin(260f64));
+    assert!(lines[0].starts_with("Three("), "{:?}", lines);
+    assert_eq!(lines.iter().filter(|l| l.starts_with("root ")).count(), 3);
+    let z = co2
+        .z(Eos::PengRobinson, Pascal(20e5), Kelvin(260f64))
+        .unwrap();
+    assert_eq!(
+        lines.last().unwrap(),
+        &format!("selected Z = {}: highest of 3 roots (vapor root)", z)
+    );
+}
+
+#[test]
+fn test_root_strategy() {
+    // CO2 liquid-vapor region: three roots
+    let co2 = find_gas("CO2").unwrap();
+    let t = 260f64;
+    let z = |p, strategy| co2.z_with_strategy(Eos::PengRobinson, Pascal(p), Kelvin(t), strategy);
+    let psat = co2
+        .saturation_pressure(Eos::PengRobinson, Kelvin(t), &SolverConfig::default())
+        .unwrap()
+        .0;
+
+    for &p in &[0.9 * psat, 1.02 * psat] {
+        let roots = co2
+            .z_roots(Eos::PengRobinson, Pascal(p), Kelvin(t))
+            .unwrap();
+        assert_eq!(roots.len(), 3);
+        assert_eq!(z(p, RootStrategy::Vapor), Ok(roots[2]));
+        assert_eq!(
+            z(p, RootStrategy::Vapor),
+            co2.z(Eos::PengRobinson, Pascal(p), Kelvin(t))
+        );
+        assert_eq!(z(p, RootStrategy::Liquid), Ok(roots[0]));
+        assert_eq!(z(p, RootStrategy::Magnitude(Root::Middle)), Ok(roots[1]));
+        // the vapor is stable below the saturation pressure, the liquid above
+        let stable = if p < psat { roots[2] } else { roots[0] };
+        assert_eq!(z(p, RootStrategy::Gibbs), Ok(stable));
+    }
+
+    // supercritical: every strategy but the middle root selects the single root
+    let roots = co2
+        .z_roots(Eos::PengRobinson, Pascal(100e5), Kelvin(400f64))
+        .unwrap();
+    assert_eq!(roots.len(), 1);
+    for &strategy in &[
+        RootStrategy::Vapor,
+        RootStrategy::Liquid,
+        RootStrategy::Gibbs,
+    ] {
+        assert_eq!(
+            co2.z_with_strategy(Eos::PengRobinson, Pascal(100e5), Kelvin(400f64), strategy),
+            Ok(roots[0])
+        );
+    }
+    assert!(co2
+        .z_with_strategy(
+            Eos::PengRobinson,
+            Pascal(100e5),
+            Kelvin(400f64),
+            RootStrategy::Magnitude(Root::Middle)
+        )
+        .is_err());
+    assert_eq!("Gibbs".parse(), Ok(RootStrategy::Gibbs));
+    assert!("stable".parse::<RootStrategy>().is_err());
+}
+
+#[test]
+fn test_z_root() {
+    // CO2 liquid-vapor region: three roots
+    let co2 = find_gas("CO2").unwrap();
+    let (p, t) = (20e5, 260f64);
+    let roots = co2
+        .z_roots(Eos::PengRobinson, Pascal(p), Kelvin(t))
+        .unwrap();
+    assert_eq!(roots.len(), 3);
+    assert!(roots[0] > 0f64);
+    let z_root = |root| co2.z_root(Eos::PengRobinson, Pascal(p), Kelvin(t), root);
+    assert_eq!(z_root(Root::Min), Ok(roots[0]));
+    assert_eq!(z_root(Root::Middle), Ok(roots[1]));
+    assert_eq!(z_root(Root::Max), Ok(roots[2]));
+    assert_eq!(
+        z_root(Root::Max),
+        co2.z(Eos::PengRobinson, Pascal(p), Kelvin(t))
+    );
+
+    // supercritical: a single root
+    let z = co2
+        .z(Eos::PengRobinson, Pascal(100e5), Kelvin(400f64))
+        .unwrap();
+    let z_root = |root| co2.z_root(Eos::PengRobinson, Pascal(100e5), Kelvin(400f64), root);
+    assert_eq!(z_root(Root::Min), Ok(z));
+    assert_eq!(z_root(Root::Max), Ok(z));
+    assert_eq!(
+        z_root(Root::Middle),
+        Err(EosError::MissingRoot {
+            root: Root::Middle,
+            count: 1
+        })
+    );
+
+    assert_eq!("Middle".parse(), Ok(Root::Middle));
+    assert!("mid".parse::<Root>().is_err());
+}
+
+#[test]
+fn test_fugacity_coeffs() {
+    let n2 = *find_gas("N2").unwrap();
+    let co2 = *find_gas("CO2").unwrap();
+    for &b_rule in &[BRule::Linear, BRule::Lorentz] {
+        let mix = GasMixture {
+            comps: vec![(0.7, n2), (0.3, co2)],
+            b_rule,
+        };
+        for &eos in Eos::all() {
+            // ideal gas at low pressure
+            for phi in mix
+                .fugacity_coeffs(eos, Pascal(100f64), Kelvin(300f64))
+                .unwrap()
+            {
+                assert_approx_eq!(f64, phi, 1f64, epsilon = 1e-4);
+            }
+            // the molar average of ln(φi) is ln(φ) of the mixture
+            let (p, t) = (100e5, 300f64);
+            let phis = mix.fugacity_coeffs(eos, Pascal(p), Kelvin(t)).unwrap();
+            let avg: f64 = mix
+                .comps
+                .iter()
+                .zip(phis.iter())
+                .map(|(c, phi)| c.molar_fraction() * phi.ln())
+                .sum();
+            let params = mix.eos_params(eos, t);
+            let z = params.cubic_z(eos, p, t).unwrap();
+            let ln_phi = params.ln_fugacity_coeff(p, t, z) - params.c * p / (R * t);
+            assert_approx_eq!(f64, avg, ln_phi, epsilon = 1e-10);
+            // CO2 is more attracted than N2
+            assert!(phis[1] < phis[0]);
+        }
+    }
+}
+
+#[test]
+fn test_two_roots() {
+    let b = 0.01;
+    // with Roots::Two([z1, z2]) out of the root finder
+    let select = |roots: [f64; 2], root| {
+        select_root(&physical_roots(sorted_roots(Roots::Two(roots))?, b), root)
+    };
+
+    // double root at the liquid or the vapor side, in any order
+    for &(z1, z2) in &[(0.05, 0.6), (0.6, 0.05)] {
+        let roots = [z1, z2];
+        assert_eq!(select(roots, Root::Max), Ok(0.6));
+        assert_eq!(select(roots, Root::Min), Ok(0.05));
+        assert_eq!(
+            select(roots, Root::Middle),
+            Err(EosError::MissingRoot {
+                root: Root::Middle,
+                count: 2
+            })
+        );
+    }
+    // one of the roots is negative or below the co-volume: a single phase
+    for &z in &[-0.2, 0.005] {
+        let roots = [0.4, z];
+        assert_eq!(select(roots, Root::Max), Ok(0.4));
+        assert_eq!(select(roots, Root::Min), Ok(0.4));
+    }
+    assert_eq!(
+        select([-0.2, 0.005], Root::Max),
+        Err(EosError::MissingRoot {
+            root: Root::Max,
+            count: 0
+        })
+    );
+}
+
+#[test]
+fn test_b_rule() {
+    let h2 = *find_gas("H2").unwrap();
+    let c6h6 = *find_gas("C6H6").unwrap();
+    let mix = |comps, b_rule| GasMixture { comps, b_rule };
+
+    let linear = mix(vec![(0.5, h2), (0.5, c6h6)], BRule::Linear);
+    let lorentz = mix(vec![(0.5, h2), (0.5, c6h6)], BRule::Lorentz);
+    for &eos in Eos::all() {
+        // the cubic mean is below the linear mean
+        assert!(lorentz.b(eos) < linear.b(eos));
+        assert!(lorentz.b(eos) > h2.b(eos));
+    }
+
+    let linear = mix(vec![(0.5, h2), (0.5, h2)], BRule::Linear);
+    let lorentz = mix(vec![(0.5, h2), (0.5, h2)], BRule::Lorentz);
+    for &eos in Eos::all() {
+        assert_approx_eq!(f64, lorentz.b(eos), linear.b(eos), ulps = 4);
+        assert_approx_eq!(f64, lorentz.b(eos), h2.b(eos), ulps = 4);
+    }
+}
+
+#[test]
+fn test_critical_z() {
+    let zc = |eos: Eos| eos.critical_z().unwrap();
+    assert_eq!(zc(Eos::VanDerWaals), 0.375);
+    assert_approx_eq!(f64, zc(Eos::RedlichKwong), 0.333, epsilon = 0.001);
+    assert_approx_eq!(f64, zc(Eos::SoaveRedlichKwong), 0.333, epsilon = 0.001);
+    assert_approx_eq!(f64, zc(Eos::PengRobinson), 0.307, epsilon = 0.001);
+    assert!(Eos::Rkpr.critical_z().is_none());
+}
+
+#[test]
+fn test_rkpr() {
+    // the critical constants of SRK and PR for their δ1
+    let (omega_a, omega_b, zc) = rkpr_criticals(1f64);
+    assert_approx_eq!(f64, omega_a, 0.42748, epsilon = 1e-5);
+    assert_approx_eq!(f64, omega_b, 0.08664, epsilon = 1e-5);
+    assert_approx_eq!(f64, zc, 1f64 / 3f64, epsilon = 1e-9);
+    let (omega_a, omega_b, zc) = rkpr_criticals(1f64 + 2f64.sqrt());
+    assert_approx_eq!(f64, omega_a, 0.45724, epsilon = 1e-5);
+    assert_approx_eq!(f64, omega_b, 0.07780, epsilon = 1e-5);
+    assert_approx_eq!(f64, zc, 0.3074, epsilon = 1e-4);
+
+    // so that RKPR reduces to PR for δ1 = 1+√2 at Tc, where both alpha functions are 1
+    let co2 = find_gas("CO2").unwrap();
+    let as_pr = PureGas {
+        rkpr_delta1: Some(1f64 + 2f64.sqrt()),
+        ..*co2
+    };
+    let t = co2.tc;
+    for &p in &[1e5, 50e5, 100e5] {
+        let z_pr = co2.z(Eos::PengRobinson, Pascal(p), Kelvin(t)).unwrap();
+        assert_approx_eq!(
+            f64,
+            as_pr.z(Eos::Rkpr, Pascal(p), Kelvin(t)).unwrap(),
+            z_pr,
+            epsilon = 1e-4
+        );
+    }
+
+    // δ1 of CO2 from its critical compressibility of 0.274,
+    // and the critical point of the equation at 1.168 Zc
+    assert_approx_eq!(f64, co2.rkpr_delta1(), rkpr_delta1(0.274), epsilon = 1e-4);
+    let z = co2.z(Eos::Rkpr, Pascal(co2.pc), Kelvin(co2.tc)).unwrap();
+    assert_approx_eq!(f64, z, 1.168 * 0.274, epsilon = 0.01);
+    let (_, _, zc) = rkpr_criticals(co2.rkpr_delta1());
+    assert_approx_eq!(f64, zc, 1.168 * 0.274, epsilon = 0.001);
+
+    // saturated liquid molar volume of CO2 at 220 K (Tr = 0.72, where δ1 is fitted):
+    // 37.73 cm3/mol (Span and Wagner), closer than SRK and PR without translation
+    let config = SolverConfig::default();
+    let liquid_volume = |eos| {
+        let sat = co2.saturation(eos, Kelvin(220f64), &config).unwrap();
+        sat.z_liquid * R * 220f64 / sat.p * 1e6
+    };
+    let err = |eos| (liquid_volume(eos) - 37.73f64).abs();
+    assert!(err(Eos::Rkpr) < 1f64, "{}", liquid_volume(Eos::Rkpr));
+    assert!(err(Eos::Rkpr) < err(Eos::PengRobinson));
+    assert!(err(Eos::Rkpr) < err(Eos::SoaveRedlichKwong));
+
+    // the k correlation reproduces the vapor pressure at Tr = 0.7 defining the acentric factor,
+    // log10(Psat/Pc) = -1 - ω
+    for id in &["N2", "CO2", "C2H6", "C6H6"] {
+        let gas = find_gas(id).unwrap();
+        let psat = gas
+            .saturation_pressure(Eos::Rkpr, Kelvin(0.7 * gas.tc), &config)
+            .unwrap()
+            .0;
+        let w = -1f64 - (psat / gas.pc).log10();
+        assert_approx_eq!(f64, w, gas.acentric_factor(), epsilon = 0.01);
+    }
+
+    // δ1 averaged in mixtures
+    let mix = Gas::from_string("50%CO2+C2H6").unwrap();
+    let d1 = 0.5 * (co2.rkpr_delta1() + find_gas("C2H6").unwrap().rkpr_delta1());
+    assert_eq!(mix.deltas(Eos::Rkpr), rkpr_deltas(d1));
+    let pr = Eos::PengRobinson;
+    assert_eq!(mix.deltas(pr), pr.deltas().unwrap());
+}
+
+#[test]
+fn test_too_many_roots() {
+    assert_eq!(
+        sorted_roots(Roots::Three([0.9, 0.1, 0.3])),
+        Ok(vec![0.1, 0.3, 0.9])
+    );
+    assert_eq!(
+        sorted_roots(Roots::Four([0.1, 0.2, 0.3, 0.4])),
+        Err(EosError::TooManyRoots(vec![0.1, 0.2, 0.3, 0.4]))
+    );
+}
+
+#[test]
+fn test_degenerate_cubic() {
+    let co2 = Gas::from_string("CO2").unwrap();
+    let t = 50f64;
+    for &eos in Eos::all() {
+        // compressed towards the co-volume, still solvable
+        let z = co2.z(eos, Pascal(1e10), Kelvin(t)).unwrap();
+        assert!(z.is_finite() && z > 1f64, "{:?}: {}", eos, z);
+
+        // the volume above the co-volume is lost in the rounding
+        for &p in &[1e12, 1e25, 1e300] {
+            match co2.z(eos, Pascal(p), Kelvin(t)) {
+                Err(EosError::Degenerate(msg)) => assert!(msg.contains("degenerate")),
+                res => panic!("{:?} at {} Pa: {:?}", eos, p, res),
+            }
+            assert!(co2.enthalpy_departure(eos, Pascal(p), Kelvin(t)).is_err());
+        }
+    }
+}
+
+#[test]
+fn test_liquid_density() {
+    // n-butane compressed liquid at 50 bar: about 628 kg/m3 at 250 K (NIST)
+    let c4 = Gas::from_string("C4H10").unwrap();
+    let density = |eos, t| c4.liquid_density(eos, Pascal(50e5), Kelvin(t)).unwrap();
+    for &eos in &[Eos::PengRobinson, Eos::PrPeneloux, Eos::SrkPeneloux] {
+        let rho = density(eos, 250f64);
+        assert!(rho > 550f64 && rho < 700f64, "{:?}: {} kg/m3", eos, rho);
+        assert!(density(eos, 300f64) < rho);
+    }
+    // the translation brings SRK closer to the measured density
+    let err = |eos| (density(eos, 250f64) - 628f64).abs();
+    assert!(err(Eos::SrkPeneloux) < err(Eos::SoaveRedlichKwong));
+
+    // the liquid root in the two-phase region, the vapor root otherwise
+    let co2 = Gas::from_string("CO2").unwrap();
+    let m = co2.molar_mass().unwrap();
+    let (p, t) = (20e5, 260f64);
+    let z = co2
+        .z_roots(Eos::PengRobinson, Pascal(p), Kelvin(t))
+        .unwrap()[0];
+    assert_eq!(
+        co2.liquid_density(Eos::PengRobinson, Pascal(p), Kelvin(t)),
+        Ok(m * p / (z * R * t))
+    );
+
+    let crit = Gas::from_string("crit:Tc=500,Pc=30e5,w=0.2").unwrap();
+    assert!(crit
+        .liquid_density(Eos::PengRobinson, Pascal(50e5), Kelvin(300f64))
+        .is_err());
+}
+
+#[test]
+fn test_saturation_densities() {
+    let co2 = find_gas("CO2").unwrap();
+    let m = co2.molar_mass.unwrap();
+    let config = SolverConfig::default();
+    let densities = |t| {
+        let sat = co2
+            .saturation(Eos::PengRobinson, Kelvin(t), &config)
+            .unwrap();
+        (sat.liquid_density(m), sat.vapor_density(m))
+    };
+    // well below Tc: about 1129 and 13.8 kg/m3 at 220 K (Span and Wagner)
+    let (liquid, vapor) = densities(220f64);
+    assert!(vapor > 10f64 && vapor < 20f64, "{}", vapor);
+    assert!(liquid > 50f64 * vapor);
+    // the phases converge at the critical point
+    let (liquid, vapor) = densities(0.999 * co2.tc);
+    assert!(liquid / vapor < 1.5, "{} {}", liquid, vapor);
+}
+
+#[test]
+fn test_srk_peneloux() {
+    // liquid at 250 K and 50 bar: a single root below the critical volume
+    let (p, t) = (50e5, 250f64);
+    let volume = |gas: &Gas, eos| gas.z(eos, Pascal(p), Kelvin(t)).unwrap() * R * t / p;
+
+    let mix = Gas::from_string("50%C2H6+50%C4H10").unwrap();
+    let v_srk = volume(&mix, Eos::SoaveRedlichKwong);
+    let v_srkp = volume(&mix, Eos::SrkPeneloux);
+    let roots = mix.z_roots(Eos::SrkPeneloux, Pascal(p), Kelvin(t));
+    assert_eq!(roots.unwrap().len(), 1);
+    assert_approx_eq!(
+        f64,
+        v_srk - v_srkp,
+        mix.c(Eos::SrkPeneloux, t),
+        epsilon = 1e-12
+    );
+    assert!(mix.c(Eos::SrkPeneloux, t) > 0f64);
+
+    // n-butane liquid molar volume at 250 K and 50 bar is about 92.5 cm3/mol (NIST):
+    // SRK overestimates it by several percents
+    let c4 = Gas::from_string("C4H10").unwrap();
+    let v_exp = 92.5e-6;
+    let v_srk = volume(&c4, Eos::SoaveRedlichKwong);
+    let v_srkp = volume(&c4, Eos::SrkPeneloux);
+    assert!((v_srkp - v_exp).abs() < (v_srk - v_exp).abs());
+
+    // the translation doesn't move the saturation pressure nor the entropy
+    let config = SolverConfig::default();
+    let c4 = find_gas("C4H10").unwrap();
+    assert_approx_eq!(
+        f64,
+        c4.saturation_pressure(Eos::SrkPeneloux, Kelvin(t), &config)
+            .unwrap()
+            .0,
+        c4.saturation_pressure(Eos::SoaveRedlichKwong, Kelvin(t), &config)
+            .unwrap()
+            .0,
+        epsilon = 1e-3
+    );
+    assert_approx_eq!(
+        f64,
+        c4.entropy_departure(Eos::SrkPeneloux, Pascal(p), Kelvin(t))
+            .unwrap(),
+        c4.entropy_departure(Eos::SoaveRedlichKwong, Pascal(p), Kelvin(t))
+            .unwrap()
+    );
+}
+
+#[test]
+fn test_pr_peneloux_t() {
+    // CO2 saturated liquid molar volumes in cm3/mol (Span and Wagner)
+    let co2 = find_gas("CO2").unwrap();
+    let config = SolverConfig::default();
+    let liquid_volume = |eos, t| {
+        let sat = co2.saturation(eos, Kelvin(t), &config).unwrap();
+        sat.z_liquid * R * t / sat.p * 1e6
+    };
+    for &(t, v_exp) in &[(273.15, 47.46), (293.15, 56.90)] {
+        let err = |eos| (liquid_volume(eos, t) - v_exp).abs();
+        assert!(
+            err(Eos::PrPenelouxT) < 1f64,
+            "{}",
+            liquid_volume(Eos::PrPenelouxT, t)
+        );
+        assert!(err(Eos::PrPenelouxT) < err(Eos::PrPeneloux));
+    }
+
+    // constant translation far from the critical point, critical compressibility of Rackett at Tc
+    assert_approx_eq!(
+        f64,
+        co2.c(Eos::PrPenelouxT, 100f64),
+        co2.c(Eos::PrPeneloux, 100f64),
+        epsilon = 1e-9
+    );
+    let z_ra = 0.29056 - 0.08775 * co2.acentric_factor();
+    let z = co2
+        .z(Eos::PrPenelouxT, Pascal(co2.pc), Kelvin(co2.tc))
+        .unwrap();
+    assert_approx_eq!(f64, z, z_ra, epsilon = 0.02);
+}
+
+#[test]
+fn test_second_virial_coeff() {
+    // about -4.5 cm3/mol for nitrogen at 300 K (Dymond et al., Virial Coefficients of Pure Gases).
+    // The cubic equations are loose there, Van der Waals is three times off.
+    let n2 = find_gas("N2").unwrap();
+    let b_ref = -4.5e-6;
+    for &eos in &[
+        Eos::RedlichKwong,
+        Eos::SoaveRedlichKwong,
+        Eos::SrkPeneloux,
+        Eos::PengRobinson,
+    ] {
+        let b = n2.second_virial_coeff(eos, Kelvin(300f64));
+        assert!((b - b_ref).abs() < 7e-6, "{:?}: B = {}", eos, b);
+    }
+    // slope of Z with respect to 1/v at vanishing pressure
+    for &eos in Eos::all() {
+        let (p, t) = (100f64, 300f64);
+        let z = n2.z(eos, Pascal(p), Kelvin(t)).unwrap();
+        let v = z * R * t / p;
+        assert_approx_eq!(
+            f64,
+            (z - 1f64) * v,
+            n2.second_virial_coeff(eos, Kelvin(t)),
+            epsilon = 1e-8
+        );
+    }
+}
+
+#[test]
+fn test_boyle_temperature() {
+    // about 327 K for nitrogen
+    let n2 = find_gas("N2").unwrap();
+    for &eos in Eos::all() {
+        let t = n2.boyle_temperature(eos).unwrap();
+        assert!(t > Kelvin(250f64) && t < Kelvin(500f64));
+        assert_approx_eq!(f64, n2.second_virial_coeff(eos, t), 0f64, epsilon = 1e-15);
+    }
+    // Van der Waals: Tb = a/(Rb) = 27/8 Tc
+    assert_approx_eq!(
+        f64,
+        n2.boyle_temperature(Eos::VanDerWaals).unwrap().0,
+        3.375 * n2.tc,
+        epsilon = 1e-9
+    );
+}
+
+#[test]
+fn test_saturation() {
+    let config = SolverConfig::default();
+    let n2 = find_gas("N2").unwrap();
+    for &eos in Eos::all() {
+        let sat = n2.saturation(eos, Kelvin(100f64), &config).unwrap();
+        let psat = n2.saturation_pressure(eos, Kelvin(100f64), &config);
+        assert_eq!(Ok(Pascal(sat.p)), psat);
+        assert!(sat.z_liquid > 0f64);
+        assert!(sat.z_vapor > sat.z_liquid);
+    }
+}
+
+#[test]
+fn test_eos_abbrev() {
+    for &eos in Eos::all() {
+        assert_eq!(Eos::from_str(eos.as_abbrev()), Ok(eos));
+    }
+    assert_eq!("pr".parse(), Ok(Eos::PengRobinson));
+    assert!(Eos::from_str("Peng-Robinson").is_err());
+}
+
+#[test]
+fn test_check_constants() {
+    for g in crate::gases::GASES.iter() {
+        assert_eq!(g.check_constants(), Ok(()));
+    }
+
+    let mut broken = *find_gas("N2").unwrap();
+    broken.pc = 0f64;
+    for &eos in Eos::all() {
+        assert_eq!(
+            broken.z(eos, Pascal(1e5), Kelvin(300f64)),
+            Err(EosError::InvalidGas(
+                "N2: critical pressure must be positive (got 0 Pa)".into()
+            ))
+        );
+        assert!(broken
+            .enthalpy_departure(eos, Pascal(1e5), Kelvin(300f64))
+            .is_err());
+    }
+    broken.pc = 3_390_000f64;
+    broken.tc = f64::NAN;
+    assert!(broken
+        .z(Eos::PengRobinson, Pascal(1e5), Kelvin(300f64))
+        .is_err());
+    assert!(broken.boyle_temperature(Eos::PengRobinson).is_none());
+}
+
+#[test]
+fn test_partial_molar_volumes() {
+    let co2 = *find_gas("CO2").unwrap();
+    let n2 = *find_gas("N2").unwrap();
+    let (p, t) = (50e5, 300f64);
+    for &b_rule in [BRule::Linear, BRule::Lorentz].iter() {
+        let mix = GasMixture {
+            comps: vec![(0.3, co2), (0.7, n2)],
+            b_rule,
+        };
+        for &eos in Eos::all() {
+            let vis = mix
+                .partial_molar_volumes(eos, Pascal(p), Kelvin(t))
+                .unwrap();
+            assert_eq!(vis.len(), 2);
+            let v = mix.z(eos, Pascal(p), Kelvin(t)).unwrap() * R * t / p;
+            let sum: f64 = mix
+                .comps
+                .iter()
+                .zip(vis.iter())
+                .map(|(c, vi)| c.molar_fraction() * vi)
+                .sum();
+            assert_approx_eq!(f64, sum, v, epsilon = 1e-12);
+            // CO2 is the most attracted component
+            assert!(vis[0] < vis[1]);
+        }
+    }
+
+    // a mixture of a single gas has its molar volume as partial molar volume
+    let mix = GasMixture {
+        comps: vec![(0.5, n2), (0.5, n2)],
+        b_rule: BRule::Linear,
+    };
+    let v = n2.z(Eos::PengRobinson, Pascal(p), Kelvin(t)).unwrap() * R * t / p;
+    for vi in mix
+        .partial_molar_volumes(Eos::PengRobinson, Pascal(p), Kelvin(t))
+        .unwrap()
+    {
+        assert_approx_eq!(f64, vi, v, epsilon = 1e-12);
+    }
+}
+
+#[test]
+fn test_partial_molar_derivatives() {
+    // vi and ln(φi) are the derivatives of n.v and n.ln(φ) with respect to ni,
+    // including the δ1 of RKPR that depends on the composition
+    let co2 = *find_gas("CO2").unwrap();
+    let c2h6 = *find_gas("C2H6").unwrap();
+    let (p, t) = (50e5, 350f64);
+    let mix = |n0: f64, n1: f64| GasMixture {
+        comps: vec![(n0 / (n0 + n1), co2), (n1 / (n0 + n1), c2h6)],
+        b_rule: BRule::Linear,
+    };
+    // n.v and n.ln(φ) of the mixture of n0 mol of CO2 and n1 mol of C2H6
+    let totals = |eos, n0: f64, n1: f64| {
+        let params = mix(n0, n1).eos_params(eos, t);
+        let z = params.cubic_z(eos, p, t).unwrap();
+        let ln_phi = params.ln_fugacity_coeff(p, t, z) - params.c * p / (R * t);
+        let n = n0 + n1;
+        (n * (z * R * t / p - params.c), n * ln_phi)
+    };
+    let h = 1e-5;
+    for &eos in &[Eos::PengRobinson, Eos::Rkpr] {
+        let (n0, n1) = (0.4, 0.6);
+        let vis = mix(n0, n1)
+            .partial_molar_volumes(eos, Pascal(p), Kelvin(t))
+            .unwrap();
+        let phis = mix(n0, n1)
+            .fugacity_coeffs(eos, Pascal(p), Kelvin(t))
+            .unwrap();
+        let derivatives = [
+            (totals(eos, n0 + h, n1), totals(eos, n0 - h, n1)),
+            (totals(eos, n0, n1 + h), totals(eos, n0, n1 - h)),
+        ];
+        for (i, (plus, minus)) in derivatives.iter().enumerate() {
+            let vi = (plus.0 - minus.0) / (2f64 * h);
+            let ln_phi = (plus.1 - minus.1) / (2f64 * h);
+            assert_approx_eq!(f64, vis[i], vi, epsilon = 1e-9);
+            assert_approx_eq!(f64, phis[i].ln(), ln_phi, epsilon = 1e-7);
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_eos_serde() {
+    for &eos in Eos::all() {
+        let json = serde_json::to_string(&eos).unwrap();
+        assert_eq!(json, format!("\"{}\"", eos.as_abbrev()));
+        assert_eq!(serde_json::from_str::<Eos>(&json).unwrap(), eos);
+    }
+    assert!(serde_json::from_str::<Eos>("\"XYZ\"").is_err());
+}
+
+#[test]
+fn test_path_temperature() {
+    let config = SolverConfig::default();
+    let final_t = |id, path, p1, t1, p2| {
+        let gas = find_gas(id).unwrap();
+        let (p1, t1, p2) = (Pascal(p1), Kelvin(t1), Pascal(p2));
+        let t2 = gas.path_temperature(Eos::PengRobinson, path, (p1, t1), p2, &config);
+        t2.unwrap().0
+    };
+
+    // helium is nearly ideal: negligible Joule-Thomson effect
+    let t2 = final_t("He", ProcessPath::Isenthalpic, 10e5, 300f64, 1e5);
+    assert!((t2 - 300f64).abs() < 0.5, "{}", t2);
+
+    // nitrogen cools on throttling, and much more on isentropic expansion,
+    // close to the ideal gas T1.(P2/P1)^(R/Cp) = 155 K
+    let throttled = final_t("N2", ProcessPath::Isenthalpic, 10e5, 300f64, 1e5);
+    assert!(throttled < 300f64 && throttled > 295f64, "{}", throttled);
+    let expanded = final_t("N2", ProcessPath::Isentropic, 10e5, 300f64, 1e5);
+    assert!((expanded - 155f64).abs() < 5f64, "{}", expanded);
+    // and back to the initial state by isentropic compression
+    let compressed = final_t("N2", ProcessPath::Isentropic, 1e5, expanded, 10e5);
+    assert_approx_eq!(f64, compressed, 300f64, epsilon = 1e-5);
+}
+
+#[test]
+fn test_enthalpy_of_vaporization() {
+    let config = SolverConfig::default();
+    for &(id, t, hvap) in [
+        // at the normal boiling point (NIST)
+        ("N2", 77.355, 5_570f64),
+        ("C4H10", 272.66, 22_440f64),
+        ("H2O", 373.124, 40_660f64),
+    ]
+    .iter()
+    {
+        let gas = find_gas(id).unwrap();
+        for &eos in [Eos::SoaveRedlichKwong, Eos::PengRobinson].iter() {
+            let est = gas
+                .enthalpy_of_vaporization(eos, Kelvin(t), &config)
+                .unwrap()
+                .unwrap();
+            assert!(
+                (est - hvap).abs() < 0.1 * hvap,
+                "{} {:?}: {} J/mol",
+                id,
+                eos,
+                est
+            );
+        }
+    }
+
+    let n2 = find_gas("N2").unwrap();
+    assert_eq!(
+        n2.enthalpy_of_vaporization(Eos::PengRobinson, Kelvin(130f64), &config),
+        Ok(None)
+    );
+    // vanishes at the critical point
+    let near_tc = n2.enthalpy_of_vaporization(Eos::PengRobinson, Kelvin(0.999 * n2.tc), &config);
+    assert!(near_tc.unwrap().unwrap() < 0.2 * 5_570f64);
 }
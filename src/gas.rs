@@ -1,13 +1,32 @@
-use crate::gases::GASES;
+use crate::eos::{rkpr_delta1, EosGas};
+use crate::gases::{ASSOCIATING, GASES};
 use crate::util;
 #[cfg(test)]
 use float_cmp::assert_approx_eq;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize};
 
+/// Finds a referenced gas by id.
+/// The exact case is preferred, but `id` also matches case-insensitively
+/// when a single gas does so (e.g. "n2" for "N2").
 pub fn find_gas(id: &str) -> Option<&PureGas> {
-    GASES.iter().find(|g| g.id == id)
+    find_gas_in(GASES, id)
+}
+
+/// Gas of `gases` with the id `id`, or with the same id ignoring the case if unambiguous
+pub fn find_gas_in<'a>(gases: &'a [PureGas], id: &str) -> Option<&'a PureGas> {
+    if let Some(gas) = gases.iter().find(|g| g.id == id) {
+        return Some(gas);
+    }
+    let mut candidates = gases.iter().filter(|g| g.id.eq_ignore_ascii_case(id));
+    match (candidates.next(), candidates.next()) {
+        (Some(gas), None) => Some(gas),
+        _ => None,
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct PureGas {
     /// Identifier of the gas
     pub id: &'static str,
@@ -17,14 +36,304 @@ pub struct PureGas {
     pub tc: f64,
     /// Critical pressure in Pa
     pub pc: f64,
-    /// Acentric factor
-    pub w: f64,
+    /// Acentric factor, None if unknown (then taken as zero)
+    pub w: Option<f64>,
+    /// Molar mass in kg/mol, if known
+    pub molar_mass: Option<f64>,
+    /// Coefficients of the ideal gas isobaric heat capacity in J/(mol.K):
+    /// Cp = c0 + c1.T + c2.T² + c3.T³, with only c0 set when Cp is taken constant
+    pub cp_ideal_coeffs: [f64; 4],
+    /// Temperature domain (min, max) in K of the reference data, if known
+    pub valid_t: Option<(f64, f64)>,
+    /// Pressure domain (min, max) in Pa of the reference data, if known
+    pub valid_p: Option<(f64, f64)>,
+    /// Parameter δ1 of the RKPR equation of state fitted to the critical compressibility
+    /// (see `eos::rkpr_delta1`), None if unknown and estimated from the acentric factor
+    /// (see `PureGas::rkpr_delta1`)
+    pub rkpr_delta1: Option<f64>,
+}
+
+/// Combining rule of the co-volume b of a mixture
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum BRule {
+    /// b = sum(xi.bi)
+    #[default]
+    Linear,
+    /// b = sum(sum(xi.xj.bij)) with bij = ((bi^(1/3) + bj^(1/3))/2)³
+    Lorentz,
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct GasMixture {
     /// Components of the gas
     pub comps: Vec<(f64, PureGas)>,
+    /// Combining rule of the co-volume
+    pub b_rule: BRule,
+}
+
+/// Deserialized form of `PureGas`, with owned strings
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct PureGasData {
+    id: String,
+    tc: f64,
+    pc: f64,
+    w: Option<f64>,
+    molar_mass: Option<f64>,
+    cp_ideal_coeffs: [f64; 4],
+    valid_t: Option<(f64, f64)>,
+    valid_p: Option<(f64, f64)>,
+    rkpr_delta1: Option<f64>,
+}
+
+/// The id and name of a deserialized gas are those of the referenced gas of same id
+/// (or of the generic or anonymous fluid), while the constants are taken as deserialized.
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for PureGas {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<PureGas, D::Error> {
+        let data = PureGasData::deserialize(deserializer)?;
+        let w = data.w.unwrap_or_default();
+        let generic = PureGas::generic(w);
+        let anonymous = PureGas::anonymous(data.tc, data.pc, w);
+        let gas = if data.id == generic.id {
+            &generic
+        } else if data.id == anonymous.id {
+            &anonymous
+        } else {
+            find_gas(&data.id)
+                .ok_or_else(|| serde::de::Error::custom(format!("Unknown gas: {}", data.id)))?
+        };
+        Ok(PureGas {
+            id: gas.id,
+            name: gas.name,
+            tc: data.tc,
+            pc: data.pc,
+            w: data.w,
+            molar_mass: data.molar_mass,
+            cp_ideal_coeffs: data.cp_ideal_coeffs,
+            valid_t: data.valid_t,
+            valid_p: data.valid_p,
+            rkpr_delta1: data.rkpr_delta1,
+        })
+    }
+}
+
+/// δ1 of RKPR for the critical compressibility estimated from the acentric factor
+/// (Yamada and Gunn), when it is not known
+fn rkpr_delta1_estimate(w: Option<f64>) -> f64 {
+    rkpr_delta1(0.29056 - 0.08775 * w.unwrap_or(0f64))
+}
+
+/// Tolerance on the sum of the explicit molar fractions of a mixture,
+/// which accumulates the rounding of the percentages (e.g. 10 x 10% is 0.9999999999999999)
+const FRACTION_SUM_TOL: f64 = 1e-9;
+
+/// Maximum number of decimals of `ParseOpts::fraction_precision`,
+/// beyond which a fraction below 1 is not resolved by a f64
+pub const MAX_FRACTION_DECIMALS: u32 = 15;
+
+/// Plausible range of the acentric factor (about -0.4 for helium, 1 for heavy compounds)
+const W_RANGE: (f64, f64) = (-1f64, 2f64);
+
+impl PureGas {
+    /// Gas of critical temperature `tc` in K, critical pressure `pc` in Pa and acentric factor `w`
+    /// (None if unknown).
+    /// The molar mass, the ideal gas heat capacity and the validity domains are unset
+    /// (unknown, zero and unknown) unless given with `with_molar_mass`,
+    /// `with_cp_ideal_coeffs`, `with_valid_t` and `with_valid_p`.
+    /// The δ1 of RKPR is estimated from the acentric factor unless given with `with_rkpr_delta1`.
+    /// Fails if the critical constants are not positive or if the acentric factor is implausible.
+    pub fn new(
+        id: &'static str,
+        name: &'static str,
+        tc: f64,
+        pc: f64,
+        w: Option<f64>,
+    ) -> Result<PureGas, String> {
+        let gas = PureGas {
+            id,
+            name,
+            tc,
+            pc,
+            w,
+            molar_mass: None,
+            cp_ideal_coeffs: [0f64; 4],
+            valid_t: None,
+            valid_p: None,
+            rkpr_delta1: None,
+        };
+        gas.check_constants().map_err(|err| err.to_string())?;
+        if let Some(w) = w.filter(|w| !(*w >= W_RANGE.0 && *w <= W_RANGE.1)) {
+            return Err(format!(
+                "{}: implausible acentric factor {} (expected between {} and {})",
+                id, w, W_RANGE.0, W_RANGE.1
+            ));
+        }
+        Ok(gas)
+    }
+
+    /// Sets the acentric factor, failing if it is implausible like `new`.
+    /// The δ1 of RKPR follows if it is estimated from the acentric factor.
+    pub fn with_acentric_factor(self, w: f64) -> Result<PureGas, String> {
+        PureGas::new(self.id, self.name, self.tc, self.pc, Some(w))?;
+        Ok(PureGas { w: Some(w), ..self })
+    }
+
+    /// Parameter δ1 of the RKPR equation of state: the fitted one if known,
+    /// else the one of the critical compressibility estimated from the acentric factor
+    pub fn rkpr_delta1(&self) -> f64 {
+        self.rkpr_delta1
+            .unwrap_or_else(|| rkpr_delta1_estimate(self.w))
+    }
+
+    /// Sets the molar mass in kg/mol
+    pub fn with_molar_mass(self, molar_mass: f64) -> PureGas {
+        PureGas {
+            molar_mass: Some(molar_mass),
+            ..self
+        }
+    }
+
+    /// Sets the coefficients of the ideal gas isobaric heat capacity in J/(mol.K)
+    pub fn with_cp_ideal_coeffs(self, cp_ideal_coeffs: [f64; 4]) -> PureGas {
+        PureGas {
+            cp_ideal_coeffs,
+            ..self
+        }
+    }
+
+    /// Sets the temperature domain (min, max) in K of the constants
+    pub fn with_valid_t(self, min: f64, max: f64) -> PureGas {
+        PureGas {
+            valid_t: Some((min, max)),
+            ..self
+        }
+    }
+
+    /// Sets the pressure domain (min, max) in Pa of the constants
+    pub fn with_valid_p(self, min: f64, max: f64) -> PureGas {
+        PureGas {
+            valid_p: Some((min, max)),
+            ..self
+        }
+    }
+
+    /// Sets the δ1 parameter of RKPR
+    pub fn with_rkpr_delta1(self, rkpr_delta1: f64) -> PureGas {
+        PureGas {
+            rkpr_delta1: Some(rkpr_delta1),
+            ..self
+        }
+    }
+
+    /// Acentric factor, zero if unknown
+    pub fn acentric_factor(&self) -> f64 {
+        self.w.unwrap_or(0f64)
+    }
+
+    /// Whether the molecules form hydrogen bonds (see `gases::ASSOCIATING`)
+    pub fn is_associating(&self) -> bool {
+        ASSOCIATING.contains(&self.id)
+    }
+
+    /// Ideal gas isobaric heat capacity in J/(mol.K) at temperature t in K
+    pub fn cp_ideal_at(&self, t: f64) -> f64 {
+        cp_poly(&self.cp_ideal_coeffs, t)
+    }
+
+    /// Hypothetical fluid of acentric factor `w` with unit critical constants (1 K and 1 Pa),
+    /// so that its temperatures and pressures are reduced coordinates
+    pub fn generic(w: f64) -> PureGas {
+        PureGas {
+            id: "generic",
+            name: "Generic fluid",
+            tc: 1f64,
+            pc: 1f64,
+            w: Some(w),
+            molar_mass: None,
+            cp_ideal_coeffs: [0f64; 4],
+            valid_t: None,
+            valid_p: None,
+            rkpr_delta1: None,
+        }
+    }
+
+    /// Unreferenced gas of critical temperature `tc` in K, critical pressure `pc` in Pa
+    /// and acentric factor `w`, without validation
+    fn anonymous(tc: f64, pc: f64, w: f64) -> PureGas {
+        PureGas {
+            id: "crit",
+            name: "Anonymous fluid",
+            tc,
+            pc,
+            w: Some(w),
+            molar_mass: None,
+            cp_ideal_coeffs: [0f64; 4],
+            valid_t: None,
+            valid_p: None,
+            rkpr_delta1: None,
+        }
+    }
+
+    /// Unreferenced gas of the spec "Tc=126.2,Pc=33.9e5,w=0.037" (K and Pa, keys case insensitive),
+    /// as given after "crit:" in a gas spec
+    pub fn from_criticals(spec: &str) -> Result<PureGas, String> {
+        let (mut tc, mut pc, mut w) = (None, None, None);
+        for item in spec.split(',') {
+            let kv: Vec<&str> = item.splitn(2, '=').collect();
+            if kv.len() != 2 {
+                return Err(format!(
+                    "\"{}\" from \"{}\" is not a key=value pair",
+                    item, spec
+                ));
+            }
+            let val = util::parse_num(kv[1].trim())?;
+            let slot = match kv[0].trim().to_lowercase().as_str() {
+                "tc" => &mut tc,
+                "pc" => &mut pc,
+                "w" => &mut w,
+                key => {
+                    return Err(format!(
+                        "Unknown critical constant: {} (expected Tc, Pc or w)",
+                        key
+                    ))
+                }
+            };
+            if slot.replace(val).is_some() {
+                return Err(format!("{} is given twice in \"{}\"", kv[0].trim(), spec));
+            }
+        }
+        match (tc, pc, w) {
+            (Some(tc), Some(pc), Some(w)) => {
+                let anonymous = PureGas::anonymous(tc, pc, w);
+                PureGas::new(anonymous.id, anonymous.name, tc, pc, Some(w))
+            }
+            _ => Err(format!(
+                "\"{}\" must give Tc, Pc and w (e.g. Tc=126.2,Pc=33.9e5,w=0.037)",
+                spec
+            )),
+        }
+    }
+}
+
+/// Evaluates the heat capacity polynomial at t
+pub fn cp_poly(c: &[f64; 4], t: f64) -> f64 {
+    c[0] + t * (c[1] + t * (c[2] + t * c[3]))
+}
+
+/// Integral of the heat capacity polynomial from t_ref to t (ideal gas enthalpy change)
+pub fn cp_poly_integral(c: &[f64; 4], t_ref: f64, t: f64) -> f64 {
+    let prim = |t: f64| t * (c[0] + t * (c[1] / 2f64 + t * (c[2] / 3f64 + t * c[3] / 4f64)));
+    prim(t) - prim(t_ref)
+}
+
+/// Integral of Cp/T from t_ref to t (ideal gas entropy change at constant pressure)
+pub fn cp_poly_integral_over_t(c: &[f64; 4], t_ref: f64, t: f64) -> f64 {
+    let prim = |t: f64| t * (c[1] + t * (c[2] / 2f64 + t * c[3] / 3f64));
+    c[0] * (t / t_ref).ln() + prim(t) - prim(t_ref)
 }
 
 pub trait GasComp {
@@ -41,7 +350,34 @@ impl GasComp for (f64, PureGas) {
     }
 }
 
+/// Spec of dry air, to which the ids "air" and "air_dry" expand:
+/// 78.084% N2, 20.946% O2, 0.934% Ar and the rest (0.036%) of CO2
+pub const AIR: &str = "78.084%N2+20.946%O2+0.934%Ar+CO2";
+
+/// Options of the gas spec parsing
+#[derive(Clone, Debug, Default)]
+pub struct ParseOpts {
+    /// Drop the gases without molar fraction instead of failing
+    /// when the explicit fractions already sum to 100%
+    pub allow_trace: bool,
+    /// Drop the components given an explicit zero fraction (0%CO2) instead of failing,
+    /// the gases without fraction taking the remainder
+    pub drop_zero: bool,
+    /// Combining rule of the co-volume of mixtures
+    pub b_rule: BRule,
+    /// Order the components of mixtures by descending molar fraction, then by id,
+    /// instead of the order of the spec
+    pub sort_components: bool,
+    /// Round the molar fractions of mixtures to this number of decimals
+    /// (`MAX_FRACTION_DECIMALS` at most),
+    /// the last of the largest components taking the rounding residual
+    pub fraction_precision: Option<u32>,
+    /// Gases found before the referenced ones (e.g. read with `gasdb::parse_gas_db`)
+    pub gas_db: Vec<PureGas>,
+}
+
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Gas {
     Pure(PureGas),
     Mixture(GasMixture),
@@ -49,6 +385,19 @@ pub enum Gas {
 
 impl Gas {
     pub fn from_string(input: &str) -> Result<Gas, String> {
+        Gas::from_string_with(input, &ParseOpts::default())
+    }
+
+    pub fn from_string_with(input: &str, opts: &ParseOpts) -> Result<Gas, String> {
+        if let Some(spec) = input.strip_prefix("crit:") {
+            // before splitting the components: the constants may contain '+' (33.9e+5)
+            return Ok(Gas::Pure(PureGas::from_criticals(spec)?));
+        }
+        let input = if input.eq_ignore_ascii_case("air") || input.eq_ignore_ascii_case("air_dry") {
+            AIR
+        } else {
+            input
+        };
         let comps = {
             let mut v: Vec<&str> = Vec::new();
             for s in input.split('+') {
@@ -61,6 +410,8 @@ impl Gas {
             unreachable!();
         }
 
+        let find_gas = |id| find_gas_in(&opts.gas_db, id).or_else(|| find_gas(id));
+
         if comps.len() == 1 {
             let gas = find_gas(comps[0]).ok_or("The requested gas is not referenced")?;
             Ok(Gas::Pure(*gas))
@@ -70,7 +421,14 @@ impl Gas {
             let mut gas_comps = Vec::new();
 
             for comp in comps.into_iter() {
-                let frac_gas: Vec<&str> = comp.split('%').collect();
+                // fraction as percentage (80%N2) or trace in ppm or ppb (5ppm_CO)
+                let (frac_gas, unit): (Vec<&str>, f64) = if comp.contains("ppm_") {
+                    (comp.splitn(2, "ppm_").collect(), 1e-6)
+                } else if comp.contains("ppb_") {
+                    (comp.splitn(2, "ppb_").collect(), 1e-9)
+                } else {
+                    (comp.split('%').collect(), 1e-2)
+                };
                 if frac_gas.is_empty() {
                     unreachable!()
                 }
@@ -86,10 +444,13 @@ impl Gas {
                     gas_comps.push((NO_FRAC, *gas));
                 } else {
                     let frac = util::parse_num(frac_gas[0])?;
+                    if frac == 0f64 && opts.drop_zero {
+                        continue;
+                    }
                     if frac <= 0f64 {
                         return Err("molar fraction cannot be negative".into());
                     }
-                    gas_comps.push((frac / 100f64, *gas));
+                    gas_comps.push((frac * unit, *gas));
                 }
             }
 
@@ -106,11 +467,14 @@ impl Gas {
                 (total, num)
             };
 
-            if total_frac > 1f64 || (total_frac - 1f64).abs() < f64::EPSILON && num_no_frac > 0 {
+            let full = (total_frac - 1f64).abs() <= FRACTION_SUM_TOL;
+            if full && num_no_frac > 0 && opts.allow_trace {
+                gas_comps.retain(|c| c.0 != NO_FRAC);
+            } else if total_frac > 1f64 + FRACTION_SUM_TOL || full && num_no_frac > 0 {
                 return Err("total molar fraction is too high".into());
-            } else if total_frac < 1f64 && num_no_frac == 0 {
+            } else if total_frac < 1f64 - FRACTION_SUM_TOL && num_no_frac == 0 {
                 return Err("total molar fraction is too low".into());
-            } else {
+            } else if num_no_frac > 0 {
                 let missing = (1f64 - total_frac) / num_no_frac as f64;
                 for c in gas_comps.iter_mut() {
                     if c.0 == NO_FRAC {
@@ -119,18 +483,226 @@ impl Gas {
                 }
             }
 
-            Ok(Gas::Mixture(GasMixture { comps: gas_comps }))
+            if let Some(decimals) = opts.fraction_precision {
+                round_fractions(&mut gas_comps, decimals)?;
+            }
+
+            if opts.sort_components {
+                gas_comps.sort_by(|a, b| {
+                    b.0.partial_cmp(&a.0)
+                        .unwrap()
+                        .then_with(|| a.1.id.cmp(b.1.id))
+                });
+            }
+
+            if gas_comps.len() == 1 {
+                Ok(Gas::Pure(gas_comps[0].1))
+            } else {
+                Ok(Gas::Mixture(GasMixture {
+                    comps: gas_comps,
+                    b_rule: opts.b_rule,
+                }))
+            }
+        }
+    }
+
+    /// Iterates over the components of the gas with their molar fraction
+    pub fn components(&self) -> Box<dyn Iterator<Item = (f64, &PureGas)> + '_> {
+        match self {
+            Gas::Pure(gas) => Box::new(std::iter::once((1f64, gas))),
+            Gas::Mixture(mix) => Box::new(mix.comps.iter().map(|c| (c.0, &c.1))),
+        }
+    }
+
+    /// Temperature domain (min, max) in K, intersection of the known component domains
+    pub fn valid_t(&self) -> Option<(f64, f64)> {
+        intersect_domains(self.components().map(|(_, g)| g.valid_t))
+    }
+
+    /// Pressure domain (min, max) in Pa, intersection of the known component domains
+    pub fn valid_p(&self) -> Option<(f64, f64)> {
+        intersect_domains(self.components().map(|(_, g)| g.valid_p))
+    }
+
+    /// Critical temperature in K and pressure in Pa.
+    /// Mixtures have the pseudo-critical point of Kay's rule (molar average of the components).
+    pub fn criticals(&self) -> (f64, f64) {
+        self.components().fold((0f64, 0f64), |(tc, pc), (frac, g)| {
+            (tc + frac * g.tc, pc + frac * g.pc)
+        })
+    }
+
+    /// Same gas with the acentric factor of every component offset by `dw`
+    /// (see `PureGas::with_acentric_factor`).
+    /// Fails if an offset acentric factor is implausible.
+    pub fn with_acentric_offset(&self, dw: f64) -> Result<Gas, String> {
+        let offset = |g: &PureGas| g.with_acentric_factor(g.acentric_factor() + dw);
+        Ok(match self {
+            Gas::Pure(g) => Gas::Pure(offset(g)?),
+            Gas::Mixture(mix) => Gas::Mixture(GasMixture {
+                comps: mix
+                    .comps
+                    .iter()
+                    .map(|(x, g)| Ok((*x, offset(g)?)))
+                    .collect::<Result<_, String>>()?,
+                b_rule: mix.b_rule,
+            }),
+        })
+    }
+
+    /// Molar mass in kg/mol (molar average of the components for mixtures),
+    /// if known for every component
+    pub fn molar_mass(&self) -> Option<f64> {
+        self.components()
+            .map(|(frac, g)| g.molar_mass.map(|m| frac * m))
+            .sum()
+    }
+
+    /// Spec parsed by `from_string` back to this gas, e.g. "80%N2+10%O2+10%CO2".
+    /// Fractions are rounded to 12 significant digits, traces written in ppm or ppb.
+    /// If the rounded fractions don't sum to 100%, the fraction of the main component
+    /// is omitted so that it takes the rest.
+    /// The mixing rule of the co-volume is not part of the spec.
+    pub fn to_spec_string(&self) -> String {
+        let mix = match self {
+            Gas::Pure(gas) if gas.id == "crit" => {
+                return format!(
+                    "crit:Tc={},Pc={},w={}",
+                    gas.tc,
+                    gas.pc,
+                    gas.acentric_factor()
+                )
+            }
+            Gas::Pure(gas) => return gas.id.to_string(),
+            Gas::Mixture(mix) => mix,
+        };
+        let fracs: Vec<(String, f64)> = mix
+            .comps
+            .iter()
+            .map(|&(frac, _)| {
+                let (unit, sep, scale) = if frac >= 1e-4 {
+                    (1e-2, "%", 1e2)
+                } else if frac >= 1e-7 {
+                    (1e-6, "ppm_", 1e6)
+                } else {
+                    (1e-9, "ppb_", 1e9)
+                };
+                let num = format_significant(frac * scale, 12);
+                // as parsed by from_string
+                let parsed = num.parse::<f64>().unwrap() * unit;
+                (format!("{}{}", num, sep), parsed)
+            })
+            .collect();
+        let total: f64 = fracs.iter().map(|f| f.1).sum();
+        let omitted = if (total - 1f64).abs() < f64::EPSILON {
+            None
+        } else {
+            // first of the largest fractions
+            let main = (1..mix.comps.len()).fold(0, |main, i| {
+                if mix.comps[i].0 > mix.comps[main].0 {
+                    i
+                } else {
+                    main
+                }
+            });
+            Some(main)
+        };
+        let comps: Vec<String> = mix
+            .comps
+            .iter()
+            .zip(fracs.iter())
+            .enumerate()
+            .map(|(i, (comp, frac))| {
+                if Some(i) == omitted {
+                    comp.1.id.to_string()
+                } else {
+                    format!("{}{}", frac.0, comp.1.id)
+                }
+            })
+            .collect();
+        comps.join("+")
+    }
+
+    /// Normalized composition, e.g. "N2 0.80, O2 0.10, CO2 0.10"
+    pub fn composition(&self) -> String {
+        let comps: Vec<String> = self
+            .components()
+            .map(|(frac, gas)| {
+                // at least 2 decimals, at most 6
+                let frac = format!("{:.6}", frac);
+                let frac = frac.trim_end_matches('0');
+                let decimals = frac.len() - frac.find('.').unwrap() - 1;
+                format!(
+                    "{} {}{}",
+                    gas.id,
+                    frac,
+                    "0".repeat(2usize.saturating_sub(decimals))
+                )
+            })
+            .collect();
+        comps.join(", ")
+    }
+}
+
+/// Shortest representation of positive `val` rounded to `digits` significant digits.
+/// Panics if `digits` is 0.
+fn format_significant(val: f64, digits: u32) -> String {
+    assert!(digits >= 1, "at least one significant digit is needed");
+    let magnitude = val.log10().floor() as i32;
+    let decimals = (digits as i32 - 1).saturating_sub(magnitude).max(0) as usize;
+    let s = format!("{:.*}", decimals, val);
+    if s.contains('.') {
+        s.trim_end_matches('0').trim_end_matches('.').to_string()
+    } else {
+        s
+    }
+}
+
+/// Rounds the molar fractions to `decimals` decimals, keeping their sum at 1
+/// with the residual on the last of the largest components
+fn round_fractions(comps: &mut [(f64, PureGas)], decimals: u32) -> Result<(), String> {
+    if decimals > MAX_FRACTION_DECIMALS {
+        return Err(format!(
+            "can't round the molar fractions to {} decimals ({} at most)",
+            decimals, MAX_FRACTION_DECIMALS
+        ));
+    }
+    let scale = 10f64.powi(decimals as i32);
+    let largest = (0..comps.len())
+        .max_by(|&i, &j| comps[i].0.partial_cmp(&comps[j].0).unwrap())
+        .unwrap();
+    let mut others = 0f64;
+    for (i, c) in comps.iter_mut().enumerate() {
+        if i == largest {
+            continue;
+        }
+        c.0 = (c.0 * scale).round() / scale;
+        if c.0 == 0f64 {
+            return Err(format!(
+                "molar fraction of {} rounds to zero with {} decimals",
+                c.1.id, decimals
+            ));
         }
+        others += c.0;
     }
+    comps[largest].0 = 1f64 - others;
+    Ok(())
+}
+
+fn intersect_domains<I>(domains: I) -> Option<(f64, f64)>
+where
+    I: Iterator<Item = Option<(f64, f64)>>,
+{
+    domains.flatten().fold(None, |acc, (min, max)| match acc {
+        None => Some((min, max)),
+        Some((acc_min, acc_max)) => Some((acc_min.max(min), acc_max.min(max))),
+    })
 }
 
 #[cfg(test)]
 impl Gas {
     fn is_pure(&self) -> bool {
-        match self {
-            Gas::Pure(_) => true,
-            _ => false,
-        }
+        matches!(self, Gas::Pure(_))
     }
 
     fn pure(&self) -> PureGas {
@@ -141,10 +713,7 @@ impl Gas {
     }
 
     fn is_mixture(&self) -> bool {
-        match self {
-            Gas::Mixture(_) => true,
-            _ => false,
-        }
+        matches!(self, Gas::Mixture(_))
     }
 
     fn mixture(&self) -> GasMixture {
@@ -155,6 +724,136 @@ impl Gas {
     }
 }
 
+#[test]
+fn test_sort_components() {
+    let opts = ParseOpts {
+        sort_components: true,
+        ..ParseOpts::default()
+    };
+    let composition = |spec| Gas::from_string_with(spec, &opts).unwrap().composition();
+    assert_eq!(composition("O2+N2"), "N2 0.50, O2 0.50");
+    assert_eq!(composition("N2+O2"), "N2 0.50, O2 0.50");
+    assert_eq!(composition("10%CO2+O2+80%N2"), "N2 0.80, CO2 0.10, O2 0.10");
+    assert_eq!(composition("80%N2+O2+CO2"), composition("CO2+80%N2+O2"));
+
+    // spec order by default
+    let gas = Gas::from_string("O2+N2").unwrap();
+    assert_eq!(gas.composition(), "O2 0.50, N2 0.50");
+}
+
+#[test]
+fn test_molar_mass() {
+    assert_eq!(Gas::from_string("N2").unwrap().molar_mass(), Some(0.028014));
+    let air = Gas::from_string("air").unwrap().molar_mass().unwrap();
+    assert_approx_eq!(f64, air, 0.028965, epsilon = 1e-5);
+    let crit = Gas::from_string("crit:Tc=500,Pc=30e5,w=0.2").unwrap();
+    assert_eq!(crit.molar_mass(), None);
+}
+
+#[test]
+fn test_fraction_precision() {
+    let opts = |decimals| ParseOpts {
+        fraction_precision: Some(decimals),
+        ..ParseOpts::default()
+    };
+    let fractions = |spec, decimals| -> Vec<f64> {
+        Gas::from_string_with(spec, &opts(decimals))
+            .unwrap()
+            .components()
+            .map(|(f, _)| f)
+            .collect()
+    };
+    let fracs = fractions("N2+O2+CO2", 3);
+    assert_eq!(fracs[..2], [0.333, 0.333]);
+    assert_approx_eq!(f64, fracs[2], 0.334, ulps = 2);
+    assert_eq!(fracs.iter().sum::<f64>(), 1f64);
+
+    // the residual goes to the largest component
+    let fracs = fractions("N2+N2+N2+70%O2", 2);
+    assert_eq!(fracs[..3], [0.1, 0.1, 0.1]);
+    assert_approx_eq!(f64, fracs[3], 0.7, ulps = 2);
+    assert_eq!(fracs.iter().sum::<f64>(), 1f64);
+
+    assert_eq!(
+        Gas::from_string_with("O2+5ppm_CO", &opts(3)).err().unwrap(),
+        "molar fraction of CO rounds to zero with 3 decimals"
+    );
+    assert_eq!(fractions("N2+O2", 15), [0.5, 0.5]);
+    assert_eq!(
+        Gas::from_string_with("N2+O2", &opts(400)).err().unwrap(),
+        "can't round the molar fractions to 400 decimals (15 at most)"
+    );
+}
+
+#[test]
+fn test_pure_gas_new() {
+    let gas = PureGas::new("N2", "Nitrogen", 126.2, 3_390_000f64, Some(0.039))
+        .unwrap()
+        .with_molar_mass(0.028014)
+        .with_cp_ideal_coeffs([31.15, -1.357e-2, 2.680e-5, -1.168e-8])
+        .with_valid_t(63.151, 2000f64)
+        .with_valid_p(0f64, 2_200_000_000f64)
+        .with_rkpr_delta1(0.4284);
+    assert_eq!(&gas, find_gas("N2").unwrap());
+
+    let gas = PureGas::new("X", "X", 300f64, 5e6, Some(-0.3)).unwrap();
+    assert_eq!(gas.valid_t, None);
+    assert_eq!(gas.molar_mass, None);
+    assert_eq!(gas.cp_ideal_coeffs, [0f64; 4]);
+
+    assert_eq!(
+        PureGas::new("X", "X", 0f64, 5e6, Some(0.1)).unwrap_err(),
+        "X: critical temperature must be positive (got 0 K)"
+    );
+    assert!(PureGas::new("X", "X", -300f64, 5e6, Some(0.1)).is_err());
+    assert!(PureGas::new("X", "X", f64::NAN, 5e6, Some(0.1)).is_err());
+    assert!(PureGas::new("X", "X", 300f64, 0f64, Some(0.1)).is_err());
+    assert!(PureGas::new("X", "X", 300f64, f64::INFINITY, Some(0.1)).is_err());
+    assert!(PureGas::new("X", "X", 300f64, 5e6, Some(3f64)).is_err());
+    assert!(PureGas::new("X", "X", 300f64, 5e6, Some(f64::NAN)).is_err());
+
+    let co2 = find_gas("CO2").unwrap().with_acentric_factor(0.3).unwrap();
+    assert_eq!(co2.w, Some(0.3));
+    assert_eq!(co2.tc, find_gas("CO2").unwrap().tc);
+    assert!(co2.with_acentric_factor(3f64).is_err());
+    // δ1 of RKPR from the critical compressibility is kept, the estimate follows ω
+    assert_eq!(co2.rkpr_delta1, find_gas("CO2").unwrap().rkpr_delta1);
+    assert!(co2.rkpr_delta1.is_some());
+    let gas = PureGas::new("X", "X", 300f64, 5e6, None)
+        .unwrap()
+        .with_acentric_factor(0.3)
+        .unwrap();
+    assert_eq!(gas.rkpr_delta1, None);
+    assert_eq!(gas.rkpr_delta1(), rkpr_delta1_estimate(Some(0.3)));
+}
+
+#[test]
+fn test_acentric_offset() {
+    let crit = Gas::from_string("crit:Tc=500,Pc=30e5,w=0.2").unwrap();
+    let offset = crit.with_acentric_offset(0.1).unwrap();
+    let set = crit.pure().with_acentric_factor(0.2 + 0.1).unwrap();
+    assert_eq!(offset.pure(), set);
+    assert_eq!(offset.pure().rkpr_delta1(), set.rkpr_delta1());
+
+    let mix = Gas::from_string("N2+O2").unwrap();
+    let offset = mix.with_acentric_offset(-0.01).unwrap();
+    for ((_, g), (_, o)) in mix.components().zip(offset.components()) {
+        assert_eq!(o.w, Some(g.acentric_factor() - 0.01));
+        assert_eq!(o.rkpr_delta1, g.rkpr_delta1);
+    }
+    assert!(mix.with_acentric_offset(5f64).is_err());
+}
+
+#[test]
+fn test_criticals() {
+    let n2 = find_gas("N2").unwrap();
+    let o2 = find_gas("O2").unwrap();
+    assert_eq!(Gas::from_string("N2").unwrap().criticals(), (n2.tc, n2.pc));
+    let (tc, pc) = Gas::from_string("80%N2+20%O2").unwrap().criticals();
+    assert_approx_eq!(f64, tc, 0.8 * n2.tc + 0.2 * o2.tc, ulps = 4);
+    assert_approx_eq!(f64, pc, 0.8 * n2.pc + 0.2 * o2.pc, ulps = 4);
+}
+
 #[test]
 fn test_gas_parse() {
     let gas = Gas::from_string("N2");
@@ -208,3 +907,257 @@ fn test_gas_parse() {
     assert!(gas.is_err());
     assert_eq!(gas.err().unwrap(), "total molar fraction is too high");
 }
+
+#[test]
+fn test_gas_parse_fraction_sum() {
+    // below 1 with free gases: the rest is distributed
+    let gas = Gas::from_string("33.33%N2+33.33%O2+CO2").unwrap().mixture();
+    assert_approx_eq!(f64, gas.comps[2].molar_fraction(), 0.3334, epsilon = 1e-12);
+    let sum: f64 = gas.comps.iter().map(|c| c.molar_fraction()).sum();
+    assert_approx_eq!(f64, sum, 1f64, epsilon = 1e-12);
+
+    // exactly 1 up to the rounding of the percentages, without free gas
+    let gas = Gas::from_string("33.33%N2+33.33%O2+33.34%CO2").unwrap();
+    assert_eq!(gas.mixture().comps.len(), 3);
+    let ten = ["10%N2"; 9].join("+") + "+10%O2";
+    assert!(Gas::from_string(&ten).is_ok());
+
+    // exactly 1 up to the rounding, with free gases
+    let gas = Gas::from_string("33.33%N2+33.33%O2+33.34%CO2+Ar");
+    assert_eq!(gas.err().unwrap(), "total molar fraction is too high");
+
+    // above 1, with or without free gases
+    let gas = Gas::from_string("33.34%N2+33.34%O2+33.34%CO2");
+    assert_eq!(gas.err().unwrap(), "total molar fraction is too high");
+    let gas = Gas::from_string("33.34%N2+33.34%O2+33.34%CO2+Ar");
+    assert_eq!(gas.err().unwrap(), "total molar fraction is too high");
+
+    // below 1 without free gas
+    let gas = Gas::from_string("33.33%N2+33.33%O2+33.33%CO2");
+    assert_eq!(gas.err().unwrap(), "total molar fraction is too low");
+}
+
+#[test]
+fn test_gas_parse_trace() {
+    let opts = ParseOpts {
+        allow_trace: true,
+        ..ParseOpts::default()
+    };
+
+    let gas = Gas::from_string("100%N2+O2");
+    assert_eq!(gas.err().unwrap(), "total molar fraction is too high");
+
+    let gas = Gas::from_string_with("100%N2+O2", &opts).unwrap();
+    assert!(gas.is_pure());
+    assert_eq!(gas.pure(), *find_gas("N2").unwrap());
+
+    let gas = Gas::from_string_with("80%N2+20%O2+Ar+CO2", &opts).unwrap();
+    let gas = gas.mixture();
+    assert_eq!(gas.comps.len(), 2);
+    assert_eq!(gas.comps[1].pure_gas(), find_gas("O2").unwrap());
+
+    // the rest is still distributed when the explicit fractions don't sum to 100%
+    let gas = Gas::from_string_with("80%N2+O2", &opts).unwrap();
+    assert_approx_eq!(f64, gas.mixture().comps[1].molar_fraction(), 0.2);
+}
+
+#[test]
+fn test_gas_parse_drop_zero() {
+    let gas = Gas::from_string("80%N2+0%CO2+O2");
+    assert_eq!(gas.err().unwrap(), "molar fraction cannot be negative");
+
+    let opts = ParseOpts {
+        drop_zero: true,
+        ..ParseOpts::default()
+    };
+    let gas = Gas::from_string_with("80%N2+0%CO2+O2", &opts).unwrap();
+    let comps = &gas.mixture().comps;
+    assert_eq!(comps.len(), 2);
+    assert_eq!(comps[0].pure_gas(), find_gas("N2").unwrap());
+    assert_eq!(comps[1].pure_gas(), find_gas("O2").unwrap());
+    assert_approx_eq!(f64, comps[1].molar_fraction(), 0.2);
+
+    let gas = Gas::from_string_with("100%N2+0%CO2", &opts).unwrap();
+    assert_eq!(gas.pure(), *find_gas("N2").unwrap());
+
+    // still an error for negative fractions and unknown gases
+    assert!(Gas::from_string_with("80%N2+-5%CO2+O2", &opts).is_err());
+    assert!(Gas::from_string_with("80%N2+0%XX+O2", &opts).is_err());
+}
+
+#[test]
+fn test_find_gas_case() {
+    assert_eq!(find_gas("n2").unwrap().id, "N2");
+    assert_eq!(find_gas("co2").unwrap().id, "CO2");
+    assert!(find_gas("xx").is_none());
+
+    let co = find_gas("CO").unwrap();
+    let cobalt = PureGas {
+        id: "Co",
+        name: "Cobalt",
+        ..*co
+    };
+    let gases = [*co, cobalt];
+    assert_eq!(find_gas_in(&gases, "CO").unwrap().name, "Carbon monoxide");
+    assert_eq!(find_gas_in(&gases, "Co").unwrap().name, "Cobalt");
+    // ambiguous without exact case
+    assert!(find_gas_in(&gases, "co").is_none());
+
+    let gas = Gas::from_string("80%n2+o2").unwrap().mixture();
+    assert_eq!(gas.comps[0].1.id, "N2");
+    assert_eq!(gas.comps[1].1.id, "O2");
+}
+
+#[test]
+fn test_air() {
+    let explicit = Gas::from_string("78.084%N2+20.946%O2+0.934%Ar+0.036%CO2").unwrap();
+    for id in ["air", "Air", "air_dry"].iter() {
+        let air = Gas::from_string(id).unwrap();
+        let comps: Vec<(f64, &str)> = air.components().map(|(f, g)| (f, g.id)).collect();
+        let expected: Vec<(f64, &str)> = explicit.components().map(|(f, g)| (f, g.id)).collect();
+        assert_eq!(comps.len(), expected.len());
+        for (c, e) in comps.iter().zip(expected.iter()) {
+            assert_eq!(c.1, e.1);
+            assert_approx_eq!(f64, c.0, e.0, epsilon = 1e-12);
+        }
+    }
+    assert!(Gas::from_string("air+CO2").is_err());
+}
+
+#[test]
+fn test_to_spec_string() {
+    let spec = |s| Gas::from_string(s).unwrap().to_spec_string();
+    assert_eq!(spec("N2"), "N2");
+    assert_eq!(spec("80%N2+20%O2"), "80%N2+20%O2");
+    assert_eq!(spec("80%N2+O2+CO2"), "80%N2+10%O2+10%CO2");
+    assert_eq!(spec("O2+5ppm_CO"), "99.9995%O2+5ppm_CO");
+    // thirds don't round to 100%
+    assert_eq!(spec("N2+O2+CO2"), "N2+33.3333333333%O2+33.3333333333%CO2");
+
+    for s in [
+        "N2",
+        "78%N2+21%O2+Ar",
+        "N2+O2+CO2",
+        "N2+O2+Ar+CO2+He+H2+CO",
+        "O2+5ppm_CO+3ppb_NO",
+        "air",
+    ]
+    .iter()
+    {
+        let gas = Gas::from_string(s).unwrap();
+        let round_trip = Gas::from_string(&gas.to_spec_string()).unwrap();
+        let comps: Vec<(f64, &str)> = gas.components().map(|(f, g)| (f, g.id)).collect();
+        let rt_comps: Vec<(f64, &str)> = round_trip.components().map(|(f, g)| (f, g.id)).collect();
+        assert_eq!(comps.len(), rt_comps.len(), "{}", s);
+        for (c, rt) in comps.iter().zip(rt_comps.iter()) {
+            assert_eq!(c.1, rt.1);
+            assert_approx_eq!(f64, c.0, rt.0, epsilon = 1e-10);
+        }
+    }
+
+    assert_eq!(format_significant(78.084, 12), "78.084");
+    assert_eq!(format_significant(100f64 / 3f64, 12), "33.3333333333");
+    assert_eq!(format_significant(0.0123456, 3), "0.0123");
+    assert_eq!(format_significant(5f64, 1), "5");
+}
+
+#[test]
+fn test_crit_spec() {
+    use crate::eos::{Eos, EosGas};
+    use crate::units::{Kelvin, Pascal};
+
+    let n2 = *find_gas("N2").unwrap();
+    let gas = Gas::from_string("crit:Tc=126.2,Pc=33.9e+5,w=0.039").unwrap();
+    let anon = gas.pure();
+    assert_eq!((anon.id, anon.name), ("crit", "Anonymous fluid"));
+    assert_eq!((anon.tc, anon.pc, anon.w), (n2.tc, n2.pc, n2.w));
+    // the δ1 of RKPR is estimated from the acentric factor instead of Zc
+    for &eos in Eos::all().iter().filter(|&&eos| eos != Eos::Rkpr) {
+        assert_eq!(
+            gas.z(eos, Pascal(200e5), Kelvin(300f64)),
+            n2.z(eos, Pascal(200e5), Kelvin(300f64))
+        );
+    }
+
+    assert_eq!(gas.to_spec_string(), "crit:Tc=126.2,Pc=3390000,w=0.039");
+    assert!(Gas::from_string("crit:pc=33.9e5, W=0.039 ,TC=126.2").is_ok());
+    let err = |spec| Gas::from_string(spec).err().unwrap();
+    assert_eq!(
+        err("crit:Tc=0,Pc=33.9e5,w=0.039"),
+        "crit: critical temperature must be positive (got 0 K)"
+    );
+    assert!(err("crit:Tc=126.2,Pc=33.9e5").contains("must give Tc, Pc and w"));
+    assert!(err("crit:Tc=126.2,Pc=33.9e5,w=0.039,Vc=90").contains("Unknown critical constant"));
+    assert!(err("crit:Tc=126.2,Tc=127,Pc=33.9e5,w=0.039").contains("given twice"));
+    assert!(err("crit:Tc=126.2,Pc,w=0.039").contains("key=value"));
+}
+
+#[test]
+fn test_composition() {
+    let gas = Gas::from_string("80%N2+O2+CO2").unwrap();
+    assert_eq!(gas.composition(), "N2 0.80, O2 0.10, CO2 0.10");
+    let gas = Gas::from_string("78.084%N2+O2").unwrap();
+    assert_eq!(gas.composition(), "N2 0.78084, O2 0.21916");
+    let gas = Gas::from_string("N2").unwrap();
+    assert_eq!(gas.composition(), "N2 1.00");
+}
+
+#[test]
+fn test_gas_parse_trace_units() {
+    let gas = Gas::from_string("O2+5ppm_CO").unwrap().mixture();
+    assert_eq!(gas.comps.len(), 2);
+    assert_eq!(gas.comps[0].1.id, "O2");
+    assert_approx_eq!(f64, gas.comps[0].0, 1f64 - 5e-6);
+    assert_eq!(gas.comps[1].1.id, "CO");
+    assert_approx_eq!(f64, gas.comps[1].0, 5e-6);
+
+    let gas = Gas::from_string("80%N2+CO2+250ppb_NO").unwrap().mixture();
+    assert_approx_eq!(f64, gas.comps[1].0, 0.2 - 250e-9);
+    assert_approx_eq!(f64, gas.comps[2].0, 250e-9);
+
+    assert!(Gas::from_string("O2+xppm_CO").is_err());
+}
+
+#[test]
+fn test_cp_ideal_at() {
+    // NIST Chemistry WebBook: 29.12 J/(mol.K) at 300 K and 32.70 J/(mol.K) at 1000 K
+    let n2 = find_gas("N2").unwrap();
+    assert_approx_eq!(f64, n2.cp_ideal_at(300f64), 29.12, epsilon = 0.1);
+    assert_approx_eq!(f64, n2.cp_ideal_at(1000f64), 32.70, epsilon = 0.1);
+
+    // constant heat capacity
+    let ar = find_gas("Ar").unwrap();
+    assert_approx_eq!(f64, ar.cp_ideal_at(300f64), ar.cp_ideal_at(1000f64));
+    let c = ar.cp_ideal_coeffs;
+    assert_approx_eq!(f64, cp_poly_integral(&c, 300f64, 400f64), 100f64 * c[0]);
+    assert_approx_eq!(
+        f64,
+        cp_poly_integral_over_t(&c, 300f64, 400f64),
+        c[0] * (4f64 / 3f64).ln()
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde() {
+    let opts = ParseOpts {
+        b_rule: BRule::Lorentz,
+        ..ParseOpts::default()
+    };
+    let gas = Gas::from_string_with("80%N2+15%O2+CO2", &opts).unwrap();
+    let json = serde_json::to_string(&gas).unwrap();
+    assert!(json.contains("\"b_rule\":\"lorentz\""));
+    let mix = serde_json::from_str::<Gas>(&json).unwrap().mixture();
+    assert_eq!(mix.comps, gas.mixture().comps);
+    assert_eq!(mix.b_rule, BRule::Lorentz);
+
+    let n2 = serde_json::from_str::<PureGas>(&serde_json::to_string(&GASES[0]).unwrap());
+    assert_eq!(n2.unwrap(), GASES[0]);
+    let json = serde_json::to_string(&PureGas::generic(0.1)).unwrap();
+    assert_eq!(
+        serde_json::from_str::<PureGas>(&json).unwrap(),
+        PureGas::generic(0.1)
+    );
+    let json = json.replace("generic", "unobtainium");
+    assert!(serde_json::from_str::<PureGas>(&json).is_err());
+}
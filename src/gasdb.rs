@@ -0,0 +1,221 @@
+//! Database of pure gases read from a CSV file (e.g. an export of DIPPR)
+use crate::gas::PureGas;
+use crate::units::{Celsius, Kelvin};
+use crate::util;
+
+/// Column of the database, recognized by the name of its header
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Column {
+    Id,
+    Name,
+    /// Critical temperature, in K by default
+    Tc,
+    /// Critical pressure, in Pa by default
+    Pc,
+    /// Acentric factor
+    W,
+    /// Molar mass, in g/mol by default
+    MolarMass,
+    /// Column not used by rkz
+    Other,
+}
+
+impl Column {
+    /// Column of the header name, case insensitive and regardless of '_', '-', '.' and spaces
+    fn from_name(name: &str) -> Column {
+        let name: String = name
+            .chars()
+            .filter(|c| !matches!(c, '_' | '-' | '.' | ' '))
+            .collect::<String>()
+            .to_lowercase();
+        match name.as_str() {
+            "id" | "formula" | "symbol" => Column::Id,
+            "name" | "compound" | "compoundname" => Column::Name,
+            "tc" | "tcrit" | "criticaltemperature" => Column::Tc,
+            "pc" | "pcrit" | "criticalpressure" => Column::Pc,
+            "w" | "omega" | "acentric" | "acentricfactor" => Column::W,
+            "m" | "mw" | "molarmass" | "molecularweight" => Column::MolarMass,
+            _ => Column::Other,
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            Column::Id => "id",
+            Column::Name => "name",
+            Column::Tc => "critical temperature (Tc)",
+            Column::Pc => "critical pressure (Pc)",
+            Column::W => "acentric factor (w)",
+            Column::MolarMass => "molar mass (MW)",
+            Column::Other => "other",
+        }
+    }
+
+    /// Converter of the values of the column in `unit` to the units of `PureGas`
+    fn converter(self, unit: Option<&str>) -> Result<fn(f64) -> f64, String> {
+        let unit = unit.map(|u| u.trim().to_lowercase());
+        let conv: fn(f64) -> f64 = match (self, unit.as_deref()) {
+            (Column::Tc, None) | (Column::Tc, Some("k")) => |t| t,
+            (Column::Tc, Some("c")) | (Column::Tc, Some("°c")) => |t| Kelvin::from(Celsius(t)).0,
+            (Column::Pc, None) | (Column::Pc, Some("pa")) => |p| p,
+            (Column::Pc, Some("kpa")) => |p| p * 1e3,
+            (Column::Pc, Some("mpa")) => |p| p * 1e6,
+            (Column::Pc, Some("bar")) => |p| p * 1e5,
+            (Column::Pc, Some("atm")) => |p| p * 101325f64,
+            (Column::Pc, Some("psi")) => |p| p * 6894.757293168361,
+            (Column::MolarMass, None)
+            | (Column::MolarMass, Some("g/mol"))
+            | (Column::MolarMass, Some("kg/kmol")) => |m| m * 1e-3,
+            (Column::MolarMass, Some("kg/mol")) => |m| m,
+            // the unknown columns are ignored, whatever their unit
+            (Column::Other, _) | (_, None) => |v| v,
+            (col, Some(unit)) => {
+                return Err(format!(
+                    "unsupported unit [{}] for the {}",
+                    unit,
+                    col.description()
+                ))
+            }
+        };
+        Ok(conv)
+    }
+}
+
+/// Name and unit of a header such as "Pc[bar]" or "Pc (bar)"
+fn split_unit(header: &str) -> (&str, Option<&str>) {
+    let header = header.trim();
+    for &(open, close) in &[('[', ']'), ('(', ')')] {
+        if let (Some(start), true) = (header.find(open), header.ends_with(close)) {
+            return (&header[..start], Some(&header[start + 1..header.len() - 1]));
+        }
+    }
+    (header, None)
+}
+
+/// Parses a CSV database of pure gases with a header line, ignoring '#' comments and blank lines.
+/// The columns are recognized by their common names (e.g. `Tc`, `T_crit` or `CriticalTemperature`),
+/// with an optional unit in the header (e.g. `Pc[bar]`), and the unknown columns are ignored.
+/// Id, critical temperature, critical pressure and acentric factor are required.
+/// The ids and names are leaked to be referenced as the static ids of `PureGas`.
+pub fn parse_gas_db(input: &str) -> Result<Vec<PureGas>, String> {
+    let mut lines = util::content_lines(input);
+    let (_, header) = lines.next().ok_or("empty gas database")?;
+    let mut columns = Vec::new();
+    for h in header.split(',') {
+        let (name, unit) = split_unit(h.trim().trim_matches('"'));
+        let col = Column::from_name(name);
+        columns.push((col, col.converter(unit)?));
+    }
+    for &col in &[Column::Id, Column::Tc, Column::Pc, Column::W] {
+        if !columns.iter().any(|&(c, _)| c == col) {
+            return Err(format!("missing column for the {}", col.description()));
+        }
+    }
+
+    let mut gases: Vec<PureGas> = Vec::new();
+    for (num, line) in lines {
+        let fields: Vec<&str> = line
+            .split(',')
+            .map(|f| f.trim().trim_matches('"'))
+            .collect();
+        if fields.len() != columns.len() {
+            return Err(format!(
+                "line {}: {} fields for {} columns",
+                num,
+                fields.len(),
+                columns.len()
+            ));
+        }
+        let (mut id, mut name) = ("", None);
+        let (mut tc, mut pc, mut w, mut molar_mass) = (0f64, 0f64, None, None);
+        for (&(col, conv), &field) in columns.iter().zip(fields.iter()) {
+            let num_value = || -> Result<f64, String> {
+                util::parse_num(field)
+                    .map(conv)
+                    .map_err(|err| format!("line {}: {}", num, err))
+            };
+            match col {
+                Column::Id => id = field,
+                Column::Name => name = Some(field),
+                Column::Tc => tc = num_value()?,
+                Column::Pc => pc = num_value()?,
+                Column::W if field.is_empty() => w = None,
+                Column::W => w = Some(num_value()?),
+                Column::MolarMass => molar_mass = Some(num_value()?),
+                Column::Other => {}
+            }
+        }
+        if id.is_empty() {
+            return Err(format!("line {}: missing id", num));
+        }
+        if gases.iter().any(|g| g.id == id) {
+            return Err(format!("line {}: duplicate gas {}", num, id));
+        }
+        let id: &'static str = Box::leak(id.to_string().into_boxed_str());
+        let name: &'static str = match name {
+            Some(name) => Box::leak(name.to_string().into_boxed_str()),
+            None => id,
+        };
+        let gas =
+            PureGas::new(id, name, tc, pc, w).map_err(|err| format!("line {}: {}", num, err))?;
+        gases.push(match molar_mass {
+            Some(m) => gas.with_molar_mass(m),
+            None => gas,
+        });
+    }
+    Ok(gases)
+}
+
+#[test]
+fn test_dippr_headers() {
+    let db = concat!(
+        "# exported from DIPPR\n",
+        "Formula,Compound Name,MW,T_crit[K],P_crit[bar],Omega,CAS\n",
+        "C3H8,Propane,44.0956,369.83,42.48,0.1523,74-98-6 # from the 2020 edition\n",
+        "\n",
+        "# n-alkanes\n",
+        "\"C5H12\",\"n-Pentane\",72.1488,469.7,33.7,0.2515,109-66-0\n",
+    );
+    let gases = parse_gas_db(db).unwrap();
+    assert_eq!(gases.len(), 2);
+    let c3 = &gases[0];
+    assert_eq!((c3.id, c3.name), ("C3H8", "Propane"));
+    assert_eq!(c3.tc, 369.83);
+    assert_eq!(c3.pc, 4_248_000f64);
+    assert_eq!(c3.w, Some(0.1523));
+    assert_eq!(c3.molar_mass, Some(0.0440956));
+    assert_eq!(gases[1].name, "n-Pentane");
+
+    let gases = parse_gas_db(concat!(
+        "id,CriticalTemperature (C),CriticalPressure (MPa),AcentricFactor\n",
+        "X,100,5,0.1\n",
+    ))
+    .unwrap();
+    assert_eq!(gases[0].tc, 373.15);
+    assert_eq!(gases[0].pc, 5e6);
+    assert_eq!(gases[0].name, "X");
+    assert_eq!(gases[0].molar_mass, None);
+
+    let gases = parse_gas_db("id,Tb[K],Tc,Pc,w,Zc[-]\nX,80,100,5e6,0.1,0.29\n").unwrap();
+    assert_eq!(
+        (gases[0].tc, gases[0].pc, gases[0].w),
+        (100f64, 5e6, Some(0.1))
+    );
+
+    let gases = parse_gas_db("id,Tc,Pc,w\nX,100,5e6,\nY,100,5e6,0\n").unwrap();
+    assert_eq!(gases[0].w, None);
+    assert_eq!(gases[1].w, Some(0f64));
+
+    assert_eq!(
+        parse_gas_db("id,Tc,Pc\nX,100,5e6\n").unwrap_err(),
+        "missing column for the acentric factor (w)"
+    );
+    assert_eq!(
+        parse_gas_db("id,Tc,Pc[mmHg],w\nX,100,5,0.1\n").unwrap_err(),
+        "unsupported unit [mmhg] for the critical pressure (Pc)"
+    );
+    assert!(parse_gas_db("id,Tc,Pc,w\nX,100,5e6\n")
+        .unwrap_err()
+        .starts_with("line 2: "));
+    assert!(parse_gas_db("id,Tc,Pc,w\nX,-100,5e6,0.1\n").is_err());
+}
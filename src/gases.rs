@@ -1,223 +1,413 @@
 /// Physical constants of gases
 /// source: http://www.kaylaiacovino.com/Petrology_Tools/Critical_Constants_and_Acentric_Factors.htm
+/// Ideal gas heat capacities: polynomials of Reid, Prausnitz & Poling,
+/// The Properties of Gases and Liquids, 4th ed. (valid ~273-1500 K),
+/// or the NIST Chemistry WebBook value at 298.15 K when no polynomial is given
+/// Molar masses: IUPAC standard atomic weights
+/// Validity domains: range of the NIST reference equations of state
+/// RKPR δ1: correlation of Cismondi and Mollerup (2005) applied to the critical
+/// compressibilities of Reid, Prausnitz & Poling
 use crate::gas::PureGas;
 
+/// Ids of the gases forming hydrogen bonds, which the cubic equations of state
+/// don't account for
+pub static ASSOCIATING: &[&str] = &["H2O", "NH3", "CH3OH", "C2H5OH", "CH3COOH"];
+
 pub static GASES: &[PureGas] = &[
     PureGas {
         id: "Ar",
         name: "Argon",
         tc: 150.8f64,
         pc: 4_870_000f64,
-        w: 0.001f64,
+        w: Some(0.001f64),
+        molar_mass: Some(0.039948f64),
+        cp_ideal_coeffs: [20.786f64, 0f64, 0f64, 0f64],
+        valid_t: Some((83.806f64, 2000f64)),
+        valid_p: Some((0f64, 1_000_000_000f64)),
+        rkpr_delta1: Some(0.4284f64),
     },
     PureGas {
         id: "Br2",
         name: "Brome",
         tc: 588f64,
         pc: 10_340_000f64,
-        w: 0.108f64,
+        w: Some(0.108f64),
+        molar_mass: Some(0.159808f64),
+        cp_ideal_coeffs: [36.05f64, 0f64, 0f64, 0f64],
+        valid_t: None,
+        valid_p: None,
+        rkpr_delta1: Some(2.0262f64),
     },
     PureGas {
         id: "Cl2",
         name: "Chlore",
         tc: 416.9f64,
         pc: 7_980_000f64,
-        w: 0.09f64,
+        w: Some(0.09f64),
+        molar_mass: Some(0.070906f64),
+        cp_ideal_coeffs: [33.95f64, 0f64, 0f64, 0f64],
+        valid_t: None,
+        valid_p: None,
+        rkpr_delta1: Some(1.03f64),
     },
     PureGas {
         id: "F2",
         name: "Fluor",
         tc: 144.3f64,
         pc: 5_220_000f64,
-        w: 0.054f64,
+        w: Some(0.054f64),
+        molar_mass: Some(0.037997f64),
+        cp_ideal_coeffs: [31.30f64, 0f64, 0f64, 0f64],
+        valid_t: None,
+        valid_p: None,
+        rkpr_delta1: Some(0.7388f64),
     },
     PureGas {
         id: "He",
         name: "Helium-4",
         tc: 5.19f64,
         pc: 227_000f64,
-        w: -0.365f64,
+        w: Some(-0.365f64),
+        molar_mass: Some(0.0040026f64),
+        cp_ideal_coeffs: [20.786f64, 0f64, 0f64, 0f64],
+        valid_t: Some((2.1768f64, 2000f64)),
+        valid_p: Some((0f64, 1_000_000_000f64)),
+        rkpr_delta1: Some(0.4284f64),
     },
     PureGas {
         id: "H2",
         name: "Hydrogen",
         tc: 33f64,
         pc: 1_290_000f64,
-        w: -0.216f64,
+        w: Some(-0.216f64),
+        molar_mass: Some(0.002016f64),
+        cp_ideal_coeffs: [27.14f64, 9.274e-3, -1.381e-5, 7.645e-9],
+        valid_t: Some((13.957f64, 1000f64)),
+        valid_p: Some((0f64, 2_000_000_000f64)),
+        rkpr_delta1: Some(0.4284f64),
     },
     PureGas {
         id: "I2",
         name: "Iode",
         tc: 819f64,
         pc: 11_650_000f64,
-        w: 0.229f64,
+        w: Some(0.229f64),
+        molar_mass: Some(0.253809f64),
+        cp_ideal_coeffs: [36.89f64, 0f64, 0f64, 0f64],
+        valid_t: None,
+        valid_p: None,
+        rkpr_delta1: Some(2.3195f64),
     },
     PureGas {
         id: "Kr",
         name: "Krypton",
         tc: 209.4f64,
         pc: 5_500_000f64,
-        w: 0.005f64,
+        w: Some(0.005f64),
+        molar_mass: Some(0.083798f64),
+        cp_ideal_coeffs: [20.786f64, 0f64, 0f64, 0f64],
+        valid_t: None,
+        valid_p: None,
+        rkpr_delta1: Some(0.7388f64),
     },
     PureGas {
         id: "Ne",
         name: "Neon",
         tc: 44.4f64,
         pc: 2_760_000f64,
-        w: -0.029f64,
+        w: Some(-0.029f64),
+        molar_mass: Some(0.02018f64),
+        cp_ideal_coeffs: [20.786f64, 0f64, 0f64, 0f64],
+        valid_t: None,
+        valid_p: None,
+        rkpr_delta1: Some(0.4284f64),
     },
     PureGas {
         id: "N2",
         name: "Nitrogen",
         tc: 126.2f64,
         pc: 3_390_000f64,
-        w: 0.039f64,
+        w: Some(0.039f64),
+        molar_mass: Some(0.028014f64),
+        cp_ideal_coeffs: [31.15f64, -1.357e-2, 2.680e-5, -1.168e-8],
+        valid_t: Some((63.151f64, 2000f64)),
+        valid_p: Some((0f64, 2_200_000_000f64)),
+        rkpr_delta1: Some(0.4284f64),
     },
     PureGas {
         id: "O2",
         name: "Oxygen",
         tc: 154.6f64,
         pc: 5_040_000f64,
-        w: 0.025f64,
+        w: Some(0.025f64),
+        molar_mass: Some(0.031999f64),
+        cp_ideal_coeffs: [28.11f64, -3.680e-6, 1.746e-5, -1.065e-8],
+        valid_t: Some((54.361f64, 2000f64)),
+        valid_p: Some((0f64, 82_000_000f64)),
+        rkpr_delta1: Some(0.7388f64),
     },
     PureGas {
         id: "Xe",
         name: "Xenon",
         tc: 289.7f64,
         pc: 5_840_000f64,
-        w: 0.008f64,
+        w: Some(0.008f64),
+        molar_mass: Some(0.131293f64),
+        cp_ideal_coeffs: [20.786f64, 0f64, 0f64, 0f64],
+        valid_t: None,
+        valid_p: None,
+        rkpr_delta1: Some(0.9425f64),
     },
     PureGas {
         id: "C2H2",
         name: "Acetylene",
         tc: 308.3f64,
         pc: 6_140_000f64,
-        w: 0.19f64,
+        w: Some(0.19f64),
+        molar_mass: Some(0.026038f64),
+        cp_ideal_coeffs: [44.04f64, 0f64, 0f64, 0f64],
+        valid_t: None,
+        valid_p: None,
+        rkpr_delta1: Some(2.0262f64),
     },
     PureGas {
         id: "C6H6",
         name: "Benzene",
         tc: 562.1f64,
         pc: 4_890_000f64,
-        w: 0.212f64,
+        w: Some(0.212f64),
+        molar_mass: Some(0.078114f64),
+        cp_ideal_coeffs: [82.43f64, 0f64, 0f64, 0f64],
+        valid_t: None,
+        valid_p: None,
+        rkpr_delta1: Some(1.9669f64),
     },
     PureGas {
         id: "C4H10",
         name: "n-Butane",
         tc: 425.2f64,
         pc: 3_800_000f64,
-        w: 0.199f64,
+        w: Some(0.199f64),
+        molar_mass: Some(0.058123f64),
+        cp_ideal_coeffs: [98.49f64, 0f64, 0f64, 0f64],
+        valid_t: None,
+        valid_p: None,
+        rkpr_delta1: Some(1.7865f64),
     },
     PureGas {
         id: "C4H8",
         name: "Cyclobutane",
         tc: 460f64,
         pc: 4_990_000f64,
-        w: 0.181f64,
+        w: Some(0.181f64),
+        molar_mass: Some(0.056107f64),
+        cp_ideal_coeffs: [72.22f64, 0f64, 0f64, 0f64],
+        valid_t: None,
+        valid_p: None,
+        rkpr_delta1: Some(1.7865f64),
     },
     PureGas {
         id: "C6H12",
         name: "Cyclohexane",
         tc: 553.8f64,
         pc: 4_070_000f64,
-        w: 0.212f64,
+        w: Some(0.212f64),
+        molar_mass: Some(0.084161f64),
+        cp_ideal_coeffs: [106.27f64, 0f64, 0f64, 0f64],
+        valid_t: None,
+        valid_p: None,
+        rkpr_delta1: Some(1.8471f64),
     },
     PureGas {
         id: "C3H6",
         name: "Cyclopropane",
         tc: 397.8f64,
         pc: 5_490_000f64,
-        w: 0.130f64,
+        w: Some(0.130f64),
+        molar_mass: Some(0.042081f64),
+        cp_ideal_coeffs: [55.94f64, 0f64, 0f64, 0f64],
+        valid_t: None,
+        valid_p: None,
+        rkpr_delta1: Some(1.9669f64),
     },
     PureGas {
         id: "C2H6",
         name: "Ethane",
         tc: 305.4f64,
         pc: 4_880_000f64,
-        w: 0.099f64,
+        w: Some(0.099f64),
+        molar_mass: Some(0.030069f64),
+        cp_ideal_coeffs: [5.409f64, 1.781e-1, -6.938e-5, 8.713e-9],
+        valid_t: None,
+        valid_p: None,
+        rkpr_delta1: Some(1.03f64),
     },
     PureGas {
         id: "C2H4",
         name: "Ethylene",
         tc: 282.4f64,
         pc: 5_040_000f64,
-        w: 0.089f64,
+        w: Some(0.089f64),
+        molar_mass: Some(0.028054f64),
+        cp_ideal_coeffs: [42.90f64, 0f64, 0f64, 0f64],
+        valid_t: None,
+        valid_p: None,
+        rkpr_delta1: Some(1.4032f64),
+    },
+    PureGas {
+        id: "CH4",
+        name: "Methane",
+        tc: 190.4f64,
+        pc: 4_600_000f64,
+        w: Some(0.011f64),
+        molar_mass: Some(0.016043f64),
+        cp_ideal_coeffs: [19.25f64, 5.213e-2, 1.197e-5, -1.132e-8],
+        valid_t: Some((90.6941f64, 625f64)),
+        valid_p: Some((0f64, 1_000_000_000f64)),
+        rkpr_delta1: Some(0.7388f64),
+    },
+    PureGas {
+        id: "C3H8",
+        name: "Propane",
+        tc: 369.8f64,
+        pc: 4_250_000f64,
+        w: Some(0.153f64),
+        molar_mass: Some(0.044097f64),
+        cp_ideal_coeffs: [-4.224f64, 3.063e-1, -1.586e-4, 3.215e-8],
+        valid_t: Some((85.525f64, 650f64)),
+        valid_p: Some((0f64, 1_000_000_000f64)),
+        rkpr_delta1: Some(1.3342f64),
     },
     PureGas {
         id: "NH3",
         name: "Ammonia",
         tc: 405.5f64,
         pc: 11_350_000f64,
-        w: 0.250f64,
+        w: Some(0.250f64),
+        molar_mass: Some(0.017031f64),
+        cp_ideal_coeffs: [27.31f64, 2.383e-2, 1.707e-5, -1.185e-8],
+        valid_t: None,
+        valid_p: None,
+        rkpr_delta1: Some(3.6067f64),
     },
     PureGas {
         id: "CO2",
         name: "Carbon dioxide",
         tc: 304.1f64,
         pc: 7_380_000f64,
-        w: 0.239f64,
+        w: Some(0.239f64),
+        molar_mass: Some(0.04401f64),
+        cp_ideal_coeffs: [19.80f64, 7.344e-2, -5.602e-5, 1.715e-8],
+        valid_t: Some((216.592f64, 2000f64)),
+        valid_p: Some((0f64, 800_000_000f64)),
+        rkpr_delta1: Some(1.7865f64),
     },
     PureGas {
         id: "CO",
         name: "Carbon monoxide",
         tc: 132.9f64,
         pc: 3_500_000f64,
-        w: 0.066f64,
+        w: Some(0.066f64),
+        molar_mass: Some(0.02801f64),
+        cp_ideal_coeffs: [30.87f64, -1.285e-2, 2.789e-5, -1.272e-8],
+        valid_t: None,
+        valid_p: None,
+        rkpr_delta1: Some(0.4284f64),
     },
     PureGas {
         id: "NO",
         name: "Nitric oxide",
         tc: 180f64,
         pc: 6_480_000f64,
-        w: 0.588f64,
+        w: Some(0.588f64),
+        molar_mass: Some(0.030006f64),
+        cp_ideal_coeffs: [29.86f64, 0f64, 0f64, 0f64],
+        valid_t: None,
+        valid_p: None,
+        rkpr_delta1: Some(3.2201f64),
     },
     PureGas {
         id: "SO2",
         name: "Sulfur dioxide",
         tc: 430.8f64,
         pc: 7_880_000f64,
-        w: 0.256f64,
+        w: Some(0.256f64),
+        molar_mass: Some(0.064064f64),
+        cp_ideal_coeffs: [39.87f64, 0f64, 0f64, 0f64],
+        valid_t: None,
+        valid_p: None,
+        rkpr_delta1: Some(2.0851f64),
     },
     PureGas {
         id: "SO3",
         name: "Sulfur trioxide",
         tc: 491f64,
         pc: 8_210_000f64,
-        w: 0.481f64,
+        w: Some(0.481f64),
+        molar_mass: Some(0.080063f64),
+        cp_ideal_coeffs: [50.67f64, 0f64, 0f64, 0f64],
+        valid_t: None,
+        valid_p: None,
+        rkpr_delta1: Some(2.8514f64),
     },
     PureGas {
         id: "H2O",
         name: "Water",
         tc: 647.3f64,
         pc: 22_120_000f64,
-        w: 0.344f64,
+        w: Some(0.344f64),
+        molar_mass: Some(0.018015f64),
+        cp_ideal_coeffs: [32.24f64, 1.924e-3, 1.055e-5, -3.596e-9],
+        valid_t: None,
+        valid_p: None,
+        rkpr_delta1: Some(4.2301f64),
     },
     PureGas {
         id: "CH3COOH",
         name: "Acetic acid",
         tc: 592.7f64,
         pc: 5_790_000f64,
-        w: 0.09f64,
+        w: Some(0.09f64),
+        molar_mass: Some(0.060052f64),
+        cp_ideal_coeffs: [63.44f64, 0f64, 0f64, 0f64],
+        valid_t: None,
+        valid_p: None,
+        rkpr_delta1: Some(7.2283f64),
     },
     PureGas {
         id: "CH3H6O",
         name: "Acetone",
         tc: 508.1f64,
         pc: 4_700_000f64,
-        w: 0.304f64,
+        w: Some(0.304f64),
+        molar_mass: Some(0.05808f64),
+        cp_ideal_coeffs: [74.52f64, 0f64, 0f64, 0f64],
+        valid_t: None,
+        valid_p: None,
+        rkpr_delta1: Some(4.4512f64),
     },
     PureGas {
         id: "C2H5OH",
         name: "Ethanol",
         tc: 513.9f64,
         pc: 6_140_000f64,
-        w: 0.644f64,
+        w: Some(0.644f64),
+        molar_mass: Some(0.046069f64),
+        cp_ideal_coeffs: [65.21f64, 0f64, 0f64, 0f64],
+        valid_t: None,
+        valid_p: None,
+        rkpr_delta1: Some(3.8768f64),
     },
     PureGas {
         id: "CH3OH",
         name: "Methanol",
         tc: 512.6f64,
         pc: 8_090_000f64,
-        w: 0.556f64,
+        w: Some(0.556f64),
+        molar_mass: Some(0.032042f64),
+        cp_ideal_coeffs: [44.06f64, 0f64, 0f64, 0f64],
+        valid_t: None,
+        valid_p: None,
+        rkpr_delta1: Some(5.0779f64),
     },
 ];
@@ -0,0 +1,341 @@
+//! Compression factor and thermodynamic properties of gases and mixtures
+//! with cubic equations of state
+pub mod cache;
+pub mod check;
+pub mod eos;
+pub mod gas;
+pub mod gasdb;
+pub mod gases;
+pub mod range;
+pub mod solver;
+pub mod units;
+pub mod util;
+pub mod viscosity;
+
+use eos::{Eos, EosError, EosGas};
+use gas::Gas;
+use std::convert::TryFrom;
+use std::fmt;
+#[cfg(feature = "binary")]
+use std::io::{self, Read, Write};
+use std::ops::Index;
+use units::{Kelvin, Pascal};
+
+/// Error of the parsing of a gas spec, an equation of state or a range
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseError {
+    Gas(String),
+    Eos(String),
+    Range(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Gas(msg) | ParseError::Eos(msg) | ParseError::Range(msg) => {
+                write!(f, "{}", msg)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl From<ParseError> for String {
+    fn from(err: ParseError) -> Self {
+        err.to_string()
+    }
+}
+
+impl TryFrom<&str> for Gas {
+    type Error = ParseError;
+
+    /// Parses a gas spec with the default options (see `Gas::from_string`)
+    fn try_from(input: &str) -> Result<Gas, ParseError> {
+        Gas::from_string(input).map_err(ParseError::Gas)
+    }
+}
+
+impl TryFrom<&str> for Eos {
+    type Error = ParseError;
+
+    /// Parses an abbreviation of `Eos::as_abbrev` (case insensitive)
+    fn try_from(input: &str) -> Result<Eos, ParseError> {
+        input.parse().map_err(ParseError::Eos)
+    }
+}
+
+/// Compression factor of `gas` at pressure `p` and temperature `t`
+pub fn compute_z(gas: &Gas, eos: Eos, p: Pascal, t: Kelvin) -> Result<f64, EosError> {
+    gas.z(eos, p, t)
+}
+
+/// Compression factors of `gas` with one row per pressure of `ps`
+/// and one column per temperature of `ts`
+pub fn z_grid(gas: &Gas, eos: Eos, ps: &[Pascal], ts: &[Kelvin]) -> Result<ZGrid, EosError> {
+    let mut values = Vec::with_capacity(ps.len() * ts.len());
+    for &p in ps.iter() {
+        for &t in ts.iter() {
+            values.push(gas.z(eos, p, t)?);
+        }
+    }
+    Ok(ZGrid {
+        pressures: ps.iter().map(|p| p.0).collect(),
+        temperatures: ts.iter().map(|t| t.0).collect(),
+        values,
+    })
+}
+
+/// Values over a grid of pressures (rows) and temperatures (columns)
+#[derive(Clone, Debug, PartialEq)]
+pub struct ZGrid {
+    pressures: Vec<f64>,
+    temperatures: Vec<f64>,
+    /// Values in row-major order
+    values: Vec<f64>,
+}
+
+impl ZGrid {
+    /// Pressures of the rows in Pa
+    pub fn pressures(&self) -> &[f64] {
+        &self.pressures
+    }
+
+    /// Temperatures of the columns in K
+    pub fn temperatures(&self) -> &[f64] {
+        &self.temperatures
+    }
+
+    /// Value at row `i` and column `j`, if in the grid
+    pub fn get(&self, i: usize, j: usize) -> Option<f64> {
+        if i < self.pressures.len() && j < self.temperatures.len() {
+            Some(self.values[i * self.temperatures.len() + j])
+        } else {
+            None
+        }
+    }
+
+    /// Values of the row `i`, one per temperature
+    pub fn row(&self, i: usize) -> &[f64] {
+        let n = self.temperatures.len();
+        &self.values[i * n..(i + 1) * n]
+    }
+
+    /// Iterator over the (pressure, temperature, value) triples, row by row
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            grid: self,
+            index: 0,
+        }
+    }
+}
+
+impl Index<(usize, usize)> for ZGrid {
+    type Output = f64;
+
+    /// Value at row `i` and column `j`
+    fn index(&self, (i, j): (usize, usize)) -> &f64 {
+        assert!(j < self.temperatures.len(), "column {} out of the grid", j);
+        &self.values[i * self.temperatures.len() + j]
+    }
+}
+
+/// Leading bytes of the binary format of `ZGrid`
+#[cfg(feature = "binary")]
+const BINARY_MAGIC: &[u8; 4] = b"RKZG";
+
+#[cfg(feature = "binary")]
+impl ZGrid {
+    /// Writes the grid in a length-prefixed binary format: the magic "RKZG",
+    /// the numbers of pressures and temperatures as u64, then the pressures,
+    /// the temperatures and the values in row-major order as f64, all little-endian
+    pub fn write_binary(&self, w: &mut dyn Write) -> io::Result<()> {
+        w.write_all(BINARY_MAGIC)?;
+        w.write_all(&(self.pressures.len() as u64).to_le_bytes())?;
+        w.write_all(&(self.temperatures.len() as u64).to_le_bytes())?;
+        for v in self
+            .pressures
+            .iter()
+            .chain(&self.temperatures)
+            .chain(&self.values)
+        {
+            w.write_all(&v.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Reads a grid written by `write_binary`
+    pub fn read_binary(r: &mut dyn Read) -> io::Result<ZGrid> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+        let mut magic = [0u8; 4];
+        r.read_exact(&mut magic)?;
+        if &magic != BINARY_MAGIC {
+            return Err(invalid("not a binary grid"));
+        }
+        let mut read_len = || -> io::Result<usize> {
+            let mut buf = [0u8; 8];
+            r.read_exact(&mut buf)?;
+            Ok(u64::from_le_bytes(buf) as usize)
+        };
+        let (np, nt) = (read_len()?, read_len()?);
+        let len = np
+            .checked_mul(nt)
+            .ok_or_else(|| invalid("grid too large"))?;
+        let mut read_f64s = |n: usize| -> io::Result<Vec<f64>> {
+            let mut res = Vec::new();
+            let mut buf = [0u8; 8];
+            for _ in 0..n {
+                r.read_exact(&mut buf)?;
+                res.push(f64::from_le_bytes(buf));
+            }
+            Ok(res)
+        };
+        Ok(ZGrid {
+            pressures: read_f64s(np)?,
+            temperatures: read_f64s(nt)?,
+            values: read_f64s(len)?,
+        })
+    }
+}
+
+/// Iterator over the (pressure, temperature, value) triples of a `ZGrid`
+pub struct Iter<'a> {
+    grid: &'a ZGrid,
+    index: usize,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (f64, f64, f64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let n = self.grid.temperatures.len();
+        let value = *self.grid.values.get(self.index)?;
+        let res = (
+            self.grid.pressures[self.index / n],
+            self.grid.temperatures[self.index % n],
+            value,
+        );
+        self.index += 1;
+        Some(res)
+    }
+}
+
+impl<'a> IntoIterator for &'a ZGrid {
+    type Item = (f64, f64, f64);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+impl IntoIterator for ZGrid {
+    type Item = (f64, f64, f64);
+    type IntoIter = std::vec::IntoIter<(f64, f64, f64)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter().collect::<Vec<_>>().into_iter()
+    }
+}
+
+#[test]
+fn test_z_grid() {
+    let gas = Gas::from_string("78%N2+21%O2+Ar").unwrap();
+    let ps = [Pascal(1e5), Pascal(100e5), Pascal(200e5)];
+    let ts = [Kelvin(250f64), Kelvin(300f64)];
+    let grid = z_grid(&gas, Eos::PengRobinson, &ps, &ts).unwrap();
+    assert_eq!(grid.pressures(), &[1e5, 100e5, 200e5]);
+    assert_eq!(grid.temperatures(), &[250f64, 300f64]);
+    for (i, &p) in ps.iter().enumerate() {
+        assert_eq!(grid.row(i).len(), 2);
+        for (j, &t) in ts.iter().enumerate() {
+            let z = compute_z(&gas, Eos::PengRobinson, p, t).unwrap();
+            assert_eq!(grid[(i, j)], z);
+            assert_eq!(grid.get(i, j), Some(z));
+        }
+    }
+    assert_eq!(grid.get(3, 0), None);
+    assert_eq!(grid.get(0, 2), None);
+}
+
+#[test]
+fn test_z_grid_iter() {
+    let gas = Gas::from_string("N2").unwrap();
+    let ps = [Pascal(1e5), Pascal(100e5)];
+    let ts = [Kelvin(250f64), Kelvin(300f64), Kelvin(350f64)];
+    let grid = z_grid(&gas, Eos::PengRobinson, &ps, &ts).unwrap();
+    let triples: Vec<(f64, f64, f64)> = grid.iter().collect();
+    assert_eq!(triples.len(), 6);
+    // row by row
+    assert_eq!((triples[0].0, triples[0].1), (1e5, 250f64));
+    assert_eq!((triples[2].0, triples[2].1), (1e5, 350f64));
+    assert_eq!((triples[3].0, triples[3].1), (100e5, 250f64));
+    assert_eq!(triples[4].2, grid[(1, 1)]);
+    assert_eq!(grid.into_iter().collect::<Vec<_>>(), triples);
+}
+
+#[test]
+fn test_try_from() {
+    let gas = Gas::try_from("78%N2+21%O2+Ar").unwrap();
+    assert_eq!(gas.components().count(), 3);
+    assert_eq!(
+        Gas::try_from("N2+Xx").unwrap_err(),
+        ParseError::Gas("The requested gas is not referenced".into())
+    );
+
+    assert_eq!(Eos::try_from("pr"), Ok(Eos::PengRobinson));
+    assert_eq!(
+        Eos::try_from("XX"),
+        Err(ParseError::Eos("Unknown equation of state: XX".into()))
+    );
+
+    let range = range::Range::try_from("0:100:5n").unwrap();
+    assert_eq!(range.iter().count(), 5);
+    let err = range::Range::try_from("100:0").unwrap_err();
+    assert_eq!(
+        err,
+        ParseError::Range("Range stop must be higher than start".into())
+    );
+    assert_eq!(String::from(err), "Range stop must be higher than start");
+}
+
+#[cfg(feature = "binary")]
+#[test]
+fn test_binary_round_trip() {
+    let gas = Gas::from_string("78%N2+21%O2+Ar").unwrap();
+    let grid = z_grid(
+        &gas,
+        Eos::PengRobinson,
+        &[Pascal(1e5), Pascal(200e5)],
+        &[Kelvin(250f64), Kelvin(300f64), Kelvin(350f64)],
+    )
+    .unwrap();
+    let mut buf = Vec::new();
+    grid.write_binary(&mut buf).unwrap();
+    assert_eq!(buf.len(), 4 + 2 * 8 + (2 + 3 + 6) * 8);
+    assert_eq!(ZGrid::read_binary(&mut buf.as_slice()).unwrap(), grid);
+
+    // truncated or foreign data
+    assert!(ZGrid::read_binary(&mut &buf[..buf.len() - 1]).is_err());
+    assert!(ZGrid::read_binary(&mut &b"P \\ T\t250"[..]).is_err());
+}
+
+#[test]
+fn test_compute_z_units() {
+    use units::{Bar, Celsius};
+    // H2 metering reference: Z = 1.00061 at 0°C and 1.01325 bar
+    let h2 = Gas::from_string("H2").unwrap();
+    let z = compute_z(
+        &h2,
+        Eos::PengRobinson,
+        Bar(1.01325).into(),
+        Celsius(0f64).into(),
+    )
+    .unwrap();
+    assert_eq!(
+        z,
+        h2.z(Eos::PengRobinson, Pascal(101325f64), Kelvin(273.15))
+            .unwrap()
+    );
+    assert!((z - 1.00061).abs() < 1e-3, "{}", z);
+}
@@ -1,17 +1,125 @@
-use clap::{App, Arg};
+use clap::{App, Arg, ArgMatches};
+use std::borrow::Cow;
+use std::fmt;
+use std::hint;
+use std::io::{self, BufRead, BufWriter, IsTerminal, Write};
+#[cfg(feature = "watch")]
+use std::path::Path;
 use std::process;
+#[cfg(feature = "watch")]
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::OnceLock;
+#[cfg(feature = "watch")]
+use std::time::Duration;
+use std::time::Instant;
 
-mod eos;
-mod gas;
-mod gases;
-mod util;
+mod cases;
 
-use eos::{Eos, EosGas};
-use gas::Gas;
-use gases::GASES;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use rkz::cache::ZCache;
+use rkz::check;
+use rkz::eos::{Eos, EosError, EosGas, ProcessPath, RootStrategy, R};
+use rkz::gas::{
+    find_gas, find_gas_in, BRule, Gas, GasComp, GasMixture, ParseOpts, PureGas,
+    MAX_FRACTION_DECIMALS,
+};
+use rkz::gasdb;
+use rkz::gases::GASES;
+use rkz::range::Range;
+use rkz::solver::SolverConfig;
+use rkz::units::{Bar, Celsius, Fahrenheit, Kelvin, Pascal};
+use rkz::util;
+use rkz::viscosity;
+use rkz::{z_grid, ParseError};
+use std::convert::TryFrom;
 
-fn main() {
-    let matches = App::new("rkz")
+/// Values of --format
+#[cfg(feature = "binary")]
+const FORMATS: &[&str] = &["text", "jsonl", "table", "binary"];
+#[cfg(not(feature = "binary"))]
+const FORMATS: &[&str] = &["text", "jsonl", "table"];
+
+/// Whether stdout is a terminal, for --color auto. Set by main: the processing functions
+/// called otherwise, as by the tests, write to buffers and don't color.
+static STDOUT_IS_TERMINAL: OnceLock<bool> = OnceLock::new();
+
+/// Default of --eos: the value of the RKZ_EOS environment variable, or RK.
+/// Not an env of clap, which would add the variable to the repeated options.
+fn default_eos() -> &'static str {
+    static DEFAULT: OnceLock<String> = OnceLock::new();
+    DEFAULT.get_or_init(|| std::env::var("RKZ_EOS").unwrap_or_else(|_| "RK".into()))
+}
+
+/// Modes that take neither the gas, the temperature nor the pressure of the environment
+const ENV_FREE_MODES: &[&str] = &[
+    "list-gas",
+    "about-gas",
+    "params",
+    "license",
+    "consistency-check",
+    "metering-check",
+    "batch",
+    "cases",
+];
+
+/// Options given by an environment variable when absent from the command line:
+/// (option, variable, other modes that don't take the option)
+const ENV_OPTIONS: &[(&str, &str, &[&str])] = &[
+    ("gas", "RKZ_GAS", &["compare-gas"]),
+    (
+        "temperature",
+        "RKZ_TEMPERATURE",
+        &["boyle", "chart", "benchmark-eos", "compare-eos-to-data"],
+    ),
+    (
+        "pressure",
+        "RKZ_PRESSURE",
+        &[
+            "saturation",
+            "hvap",
+            "sat-vapor-density",
+            "boyle",
+            "virial-b",
+            "density-input",
+            "chart",
+            "benchmark-eos",
+            "compare-eos-to-data",
+        ],
+    ),
+];
+
+/// Command line `args` completed with the options of `ENV_OPTIONS` whose variable is set
+/// in `env`, unless the option is on the command line or doesn't apply to the requested mode.
+/// Not env of clap, which counts the variables as given options and makes them conflict.
+fn args_with_env(args: Vec<String>, env: impl Fn(&str) -> Option<String>) -> Vec<String> {
+    let matches = match app().get_matches_from_safe(&args) {
+        Ok(matches) => matches,
+        // reported by the parsing of the command line alone
+        Err(_) => return args,
+    };
+    if ENV_FREE_MODES.iter().any(|&mode| matches.is_present(mode)) {
+        return args;
+    }
+    let mut res = args;
+    for &(name, var, modes) in ENV_OPTIONS {
+        if matches.is_present(name) || modes.iter().any(|&mode| matches.is_present(mode)) {
+            continue;
+        }
+        if let Some(value) = env(var) {
+            let mut with_var = res.clone();
+            with_var.push(format!("--{}={}", name, value));
+            // still ignored if clap finds a conflict the modes above missed
+            if app().get_matches_from_safe(&with_var).is_ok() {
+                res = with_var;
+            }
+        }
+    }
+    res
+}
+
+fn app() -> App<'static, 'static> {
+    let app = App::new("rkz")
         .version(env!("CARGO_PKG_VERSION"))
         .bin_name("rkz")
         .author("Rémi Thebault <remi.thebault@gmail.com>")
@@ -22,7 +130,10 @@ fn main() {
             "  - Van der Waals\n",
             "  - Redlich-Kwong (default)\n",
             "  - Soave-Redlich-Kwong\n",
+            "  - Soave-Redlich-Kwong with Peneloux volume translation\n",
             "  - Peng-Robinson\n",
+            "  - Peng-Robinson with Peneloux volume translation, constant or temperature-dependent\n",
+            "  - Redlich-Kwong-Peng-Robinson\n",
             "\n",
             "                                                           PV\n",
             "The compression factor for a mole of gas is defined as Z = --.\n",
@@ -31,15 +142,20 @@ fn main() {
             "the result is written in CSV format with one Z value per combination of pressure and temperature ",
             "(1 row per pressure condition, 1 column per temperature condition).\n",
             "Range are provided in the form of min:max[:step] (e.g. '20:800' or '20:800:10'). ",
-            "If step is omitted, it is assumed to be equal to one.\n\n",
+            "If step is omitted, it is assumed to be equal to one. ",
+            "The step can also be given as a number of evenly spaced values including min and max ",
+            "(e.g. '0:100:5n' for 0, 25, 50, 75 and 100).\n\n",
             "Mixture for option --gas|-g can be specified in the form of molar_fraction%gas_id+[molar_fraction%gas_id]. ",
-            "Mixture molar fractions can only be specified as percentage or be omitted. ",
+            "Mixture molar fractions can be specified as percentage, as ppm or ppb (e.g. 5ppm_CO) or be omitted. ",
             "Gases without molar fraction evenly take the rest of the mixture. Examples:\n",
             "  - '80%N2+20%O2' => 80% Nitrogen and 20% Oxygen\n",
             "  - '80%N2+O2' => 80% Nitrogen and 20% Oxygen\n",
             "  - '80%N2+O2+CO2' => 80% Nitrogen, 10% Oxygen and 10% Carbon dioxide\n",
             "  - '78%N2+21%O2+Ar' => air composition (more or less)\n",
-            "  - 'N2+O2' => 50% Nitrogen and 50% Oxygen\n\n",
+            "  - 'crit:Tc=126.2,Pc=33.9e5,w=0.039' => unreferenced gas of critical temperature (K), critical pressure (Pa) and acentric factor\n",
+            "  - 'air' (or 'air_dry') => dry air: 78.084% Nitrogen, 20.946% Oxygen, 0.934% Argon and 0.036% Carbon dioxide\n",
+            "  - 'N2+O2' => 50% Nitrogen and 50% Oxygen\n",
+            "  - 'O2+5ppm_CO' => Oxygen with 5 ppm of Carbon monoxide (ppb is also accepted)\n\n",
             "DISCLAIMER: rkz is provided \"as is\" without any warranty. See the --license option for details.\n",
         ))
         .after_help(concat!(
@@ -50,56 +166,432 @@ fn main() {
             "        Z-factor of Nitrogen at 200bar and 20°C\n",
             "    rkz -g 78%N2+21%O2+Ar -p 200 -t 50 -e PR\n",
             "        Z-factor of air at 200bar and 50°C with Peng-Robinson equation of state\n",
+            "    rkz -g N2 -p 200 -t 20 -e RK -e PR\n",
+            "        Z-factor of Nitrogen with both Redlich-Kwong and Peng-Robinson\n",
             "    rkz -g H2 -p 0:1000:10 -t -40:80 -r stdatm\n",
             "        Z-factor CSV table of Hydrogen from 0 to 1000barG and -40 to +80°C\n",
+            "    rkz -g N2 -t -180:-150 --saturation -e PR\n",
+            "        Saturation pressure of Nitrogen from -180 to -150°C with Peng-Robinson\n",
+            "    printf 'N2 200 20\\nair 50 0\\n' | rkz --batch --threads 4\n",
+            "        Z-factors of the conditions read from stdin, computed on 4 threads\n",
+            "        (with the rayon cargo feature)\n",
+            "\n",
+            "EXIT CODES:\n",
+            "    0    success\n",
+            "    1    output failure or consistency check violations\n",
+            "    2    invalid or insufficient arguments\n",
+            "    3    unknown gas or invalid gas spec or cases file\n",
+            "    4    computation failure (no root, no convergence...)\n",
+            "\n",
+            "ENVIRONMENT:\n",
+            "    RKZ_GAS, RKZ_PRESSURE, RKZ_TEMPERATURE and RKZ_EOS give the value of\n",
+            "    -g, -p, -t and -e when the option is absent from the command line.\n",
+            "    The option always takes precedence over the variable. A variable is\n",
+            "    ignored by the modes that don't take its option, e.g. RKZ_GAS by\n",
+            "    --compare-gas and RKZ_PRESSURE by --boyle, and by --batch and --cases.\n",
         ))
         .arg(Arg::with_name("gas")
             .short("g")
             .long("gas")
-            .help("Specify the gas by id or by mixture spec (see above)")
+            .help("Specify the gas by id or by mixture spec (see above), or @path to read the spec from the file at path")
+            .takes_value(true))
+        .arg(Arg::with_name("compare-gas")
+            .long("compare-gas")
+            .help("Specify a comma separated list of gases (ids or mixture specs) to compare. Produces a CSV table with one Z column per gas.")
+            .conflicts_with("gas")
+            .takes_value(true))
+        .arg(Arg::with_name("gas-db")
+            .long("gas-db")
+            .help("Reads additional pure gases from a CSV file with a header line, found before the referenced gases. The columns are recognized by their usual names (e.g. Tc, T_crit or CriticalTemperature; Pc, P_crit or CriticalPressure; w, omega or AcentricFactor; id or Formula; optional Name and MW), with an optional unit in the header (e.g. Pc[bar]). Tc defaults to K, Pc to Pa and MW to g/mol. An empty acentric factor is unknown: it is taken as zero and reported by --warn with SRK and PR.")
+            .takes_value(true))
+        .arg(Arg::with_name("set-w")
+            .long("set-w")
+            .help("Overrides the acentric factor of a referenced gas or of a gas of --gas-db for the run, e.g. --set-w CO2=0.25, which changes SRK and PR but not VdW and RK. Can be repeated for several gases.")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1))
+        .arg(Arg::with_name("allow-trace")
+            .long("allow-trace")
+            .help("Drops the gases without molar fraction of a mixture when the other fractions already sum to 100% (instead of failing)"))
+        .arg(Arg::with_name("drop-zero")
+            .long("drop-zero")
+            .help("Drops the gases given an explicit 0% fraction in a mixture (e.g. 80%N2+0%CO2+O2), the gases without fraction taking the remainder (instead of failing)"))
+        .arg(Arg::with_name("b-rule")
+            .long("b-rule")
+            .help("Specify the combining rule of the co-volume b of mixtures. \"linear\" is the mole fraction average, \"lorentz\" is the double sum of the cubic mean of the component co-volumes.")
+            .possible_values(&["linear", "lorentz"])
+            .default_value("linear")
             .takes_value(true))
         .arg(Arg::with_name("temperature")
             .short("t")
             .long("temperature")
             .allow_hyphen_values(true)
-            .help("Specify the temperature in °C. A range can be specified in the form of start:stop[:step].")
+            .help("Specify the temperature in °C, or in the unit of --temperature-unit. A range can be specified in the form of start:stop[:step].")
             .takes_value(true))
         .arg(Arg::with_name("pressure")
             .short("p")
             .long("pressure")
-            .help("Specify the pressure in bar. By default absolute unless --relative is used. A range can be specified in the form of start:stop[:step].")
+            .allow_hyphen_values(true)
+            .help("Specify the pressure in bar, or in the unit of --pressure-unit. By default absolute unless --relative is used. A range can be specified in the form of start:stop[:step].")
+            .takes_value(true))
+        .arg(Arg::with_name("temperature-unit")
+            .long("temperature-unit")
+            .help("Specify the unit of the temperatures entered with -t: C for °C (default), K or F for °F")
+            .possible_values(&["C", "K", "F"])
+            .case_insensitive(true)
+            .takes_value(true))
+        .arg(Arg::with_name("pressure-unit")
+            .long("pressure-unit")
+            .help("Specify the unit of the pressures entered with -p: bar (default), psi, kPa, MPa or atm. The offset of --relative is still in hPa.")
+            .possible_values(&["bar", "psi", "kPa", "MPa", "atm"])
+            .case_insensitive(true)
             .takes_value(true))
         .arg(Arg::with_name("equation")
             .short("e")
             .long("eos")
-            .help("Specify the equation of state (case insensitive). Choices are VdW for Van-der-Waals, RK for Redlich-Kwong, SRK for Soave-Redlich-Kwong and PR for Peng-Robinson.")
+            .help("Specify the equation of state (case insensitive). Choices are VdW for Van-der-Waals, RK for Redlich-Kwong, SRK for Soave-Redlich-Kwong, SRKP for Soave-Redlich-Kwong with Peneloux volume translation (better liquid densities), PR for Peng-Robinson, PRP for Peng-Robinson with Peneloux volume translation and PRPT for Peng-Robinson with a temperature-dependent volume translation (better liquid densities near the critical point) and RKPR for Redlich-Kwong-Peng-Robinson (δ1 fitted to the critical compressibility of each gas, better liquid densities than RK, SRK and PR). Can be repeated (e.g. -e RK -e PR) to compute with several equations, producing one labeled result or one group of columns per equation. Defaults to RK, or to the value of the RKZ_EOS environment variable.")
             .takes_value(true)
-            .default_value("RK")
+            .multiple(true)
+            .number_of_values(1)
+            .default_value(default_eos())
         )
+        .arg(Arg::with_name("volume-shift")
+            .long("volume-shift")
+            .help("Applies a volume translation to the equations of state: constant turns SRK into SRKP and PR into PRP, temperature-dependent turns PR into PRPT")
+            .possible_values(&["constant", "temperature-dependent"])
+            .takes_value(true))
         .arg(Arg::with_name("relative")
             .short("r")
             .long("relative")
-            .help("Specify that the pressure is relative to the pressure indicated in this parameter (in hPa). \"stdatm\" can be used for 1013.25.")
+            .help("Specify that the pressure is relative to the pressure indicated in this parameter (in hPa). \"stdatm\" can be used for 1013.25. Only the pressure is offset: temperatures are always absolute.")
+            .takes_value(true))
+        .arg(Arg::with_name("reduced")
+            .long("reduced")
+            .conflicts_with_all(&["relative", "compare-gas", "clip-domain", "saturation", "boyle", "temperature-unit", "pressure-unit"])
+            .help("Interprets the pressure and temperature as reduced coordinates Pr and Tr, relative to the critical point of the gas (pseudo-critical point of Kay's rule for mixtures)"))
+        .arg(Arg::with_name("strict")
+            .long("strict")
+            .help("Fails when a relative pressure of a range is not positive once made absolute, instead of skipping it with a warning"))
+        .arg(Arg::with_name("gamma")
+            .long("gamma")
+            .help("Computes the heat capacity ratio Cp/Cv instead of the compression factor"))
+        .arg(Arg::with_name("root-count")
+            .long("root-count")
+            .conflicts_with_all(&["gamma", "enthalpy", "entropy"])
+            .help("Writes the number of real roots of the cubic equation instead of the compression factor. 3 roots indicate the two-phase region."))
+        .arg(Arg::with_name("root")
+            .long("root")
+            .possible_values(&["min", "middle", "max"])
+            .conflicts_with_all(&["gamma", "enthalpy", "entropy", "root-count", "assume-ideal-below"])
+            .help("Selects the root of the cubic equation by magnitude among the physical roots (molar volume above the co-volume) instead of the vapor root. Fails if the root doesn't exist (middle requires three physical roots).")
+            .takes_value(true))
+        .arg(Arg::with_name("phase")
+            .long("phase")
+            .possible_values(&["vapor", "liquid", "gibbs"])
+            .conflicts_with_all(&["gamma", "enthalpy", "entropy", "root-count", "root", "assume-ideal-below"])
+            .help("Selects the root of the cubic equation among the physical roots: the highest (vapor, the default), the smallest (liquid) or the root of lowest Gibbs energy (gibbs), which is the stable phase")
+            .takes_value(true))
+        .arg(Arg::with_name("liquid-density")
+            .long("liquid-density")
+            .conflicts_with_all(&["gamma", "enthalpy", "entropy", "root-count", "root", "assume-ideal-below"])
+            .help("Computes the density in kg/m3 of the liquid root (the smallest physical root) instead of the compression factor, with the volume translation of the equation of state (see --volume-shift). Out of the two-phase region, this is the single root, which is a vapor below the saturation pressure."))
+        .arg(Arg::with_name("viscosity")
+            .long("viscosity")
+            .conflicts_with_all(&["gamma", "enthalpy", "entropy", "root-count", "root", "assume-ideal-below", "liquid-density"])
+            .help("Computes the viscosity in µPa.s instead of the compression factor, with the correlation of Lee, Gonzalez and Eakin for natural gases from the density of the vapor root. Warns about the conditions out of the data of the correlation (38 to 171°C, 6.9 to 552 bar)."))
+        .arg(Arg::with_name("assume-ideal-below")
+            .long("assume-ideal-below")
+            .conflicts_with_all(&["gamma", "enthalpy", "entropy", "root-count", "chart", "partial-volumes"])
+            .help("Takes Z as exactly 1 without solving the equation of state when the reduced pressure Pr is below this value (pseudo-critical pressure for mixtures). Speeds up large sweeps at low pressure.")
+            .takes_value(true))
+        .arg(Arg::with_name("enthalpy")
+            .long("enthalpy")
+            .conflicts_with_all(&["gamma", "entropy", "root-count"])
+            .help("Computes the enthalpy in J/mol relative to the reference state instead of the compression factor"))
+        .arg(Arg::with_name("entropy")
+            .long("entropy")
+            .conflicts_with_all(&["gamma", "enthalpy", "root-count"])
+            .help("Computes the entropy in J/(mol.K) relative to the reference state instead of the compression factor"))
+        .arg(Arg::with_name("reference")
+            .long("reference")
+            .allow_hyphen_values(true)
+            .help("Specify the reference state of --enthalpy and --entropy as temperature (°C) and pressure (bar) separated by a comma. The gas has zero enthalpy and entropy at this state.")
+            .default_value("25,1")
+            .takes_value(true))
+        .arg(Arg::with_name("boyle")
+            .long("boyle")
+            .conflicts_with_all(&["compare-gas", "temperature", "pressure"])
+            .help("Computes the Boyle temperature in °C of a pure gas, where the second virial coefficient vanishes. No temperature or pressure is needed."))
+        .arg(Arg::with_name("virial-b")
+            .long("virial-b")
+            .conflicts_with_all(&["compare-gas", "gamma", "enthalpy", "entropy", "root-count", "boyle", "pressure", "reduced", "chart"])
+            .help("Computes the second virial coefficient B in m3/mol implied by the equation of state over the temperature range. No pressure is needed."))
+        .arg(Arg::with_name("density-input")
+            .long("density-input")
+            .conflicts_with_all(&["compare-gas", "gamma", "enthalpy", "entropy", "root-count", "saturation", "hvap", "boyle", "virial-b", "pressure", "reduced", "chart"])
+            .help("Computes the absolute pressure in bar and the compression factor of the gas at this density in kg/m3 over the temperature range, from the equation of state explicit in pressure. Requires the molar mass of the gas. No pressure is needed.")
+            .takes_value(true))
+        .arg(Arg::with_name("saturation")
+            .long("saturation")
+            .conflicts_with_all(&["compare-gas", "gamma", "enthalpy", "entropy"])
+            .help("Computes the saturation pressure in bar of a pure gas over the temperature range and the Z-factors of the saturated liquid and vapor. No pressure is needed."))
+        .arg(Arg::with_name("partial-volumes")
+            .long("partial-volumes")
+            .conflicts_with_all(&["compare-gas", "gamma", "enthalpy", "entropy", "root-count", "saturation"])
+            .help("Computes the partial molar volume in cm3/mol of each component of a gas mixture instead of the compression factor, with one column per component over ranges"))
+        .arg(Arg::with_name("sensitivity")
+            .long("sensitivity")
+            .possible_values(&["w"])
+            .conflicts_with_all(&["compare-gas", "gamma", "enthalpy", "entropy", "root-count", "root", "liquid-density", "partial-volumes", "partial-pressures", "chart"])
+            .help("Writes the compression factor and its derivative dZ/dw with respect to the acentric factor (of every component of mixtures), by central finite difference, for a single temperature and pressure. Zero with VdW and RK, which ignore the acentric factor.")
+            .takes_value(true))
+        .arg(Arg::with_name("sensitivity-delta")
+            .long("sensitivity-delta")
+            .requires("sensitivity")
+            .help("Offset of the acentric factor of the finite difference of --sensitivity (0.01 by default)")
+            .takes_value(true))
+        .arg(Arg::with_name("path")
+            .long("path")
+            .possible_values(&["isentropic", "isenthalpic"])
+            .requires("target-pressure")
+            .conflicts_with_all(&["compare-gas", "gamma", "enthalpy", "entropy", "root-count", "root", "phase", "liquid-density", "partial-volumes", "partial-pressures", "sensitivity", "chart"])
+            .help("Follows a compression or expansion at constant entropy (isentropic) or enthalpy (isenthalpic, e.g. throttling) from a single pressure and temperature to --target-pressure, and writes the final temperature and compression factor")
+            .takes_value(true))
+        .arg(Arg::with_name("target-pressure")
+            .long("target-pressure")
+            .requires("path")
+            .allow_hyphen_values(true)
+            .help("Specify the final pressure of --path, in the unit and relative to the offset of -p")
+            .takes_value(true))
+        .arg(Arg::with_name("properties")
+            .long("properties")
+            .conflicts_with_all(&["compare-gas", "gamma", "enthalpy", "entropy", "root-count", "root", "phase", "liquid-density", "partial-volumes", "partial-pressures", "sensitivity", "path", "chart"])
+            .help("Writes a labeled block of properties at a single pressure and temperature: Z, molar volume, density, fugacity coefficient, enthalpy and entropy departures, residual Cp, Cp/Cv, speed of sound and Joule-Thomson coefficient. The properties requiring the molar mass or the ideal gas heat capacity are noted as unavailable for gases without these data."))
+        .arg(Arg::with_name("coeffs")
+            .long("coeffs")
+            .conflicts_with_all(&["compare-gas", "gamma", "enthalpy", "entropy", "root-count", "root", "phase", "liquid-density", "partial-volumes", "partial-pressures", "sensitivity", "path", "chart", "properties"])
+            .help("Writes the dimensionless parameters A = aP/(RT)² and B = bP/(RT) and the coefficients a3, a2, a1 and a0 of the cubic equation a3.Z³ + a2.Z² + a1.Z + a0 = 0 at a single pressure and temperature, followed by the resulting Z. With a volume translation, Z is the cubic root less the translation shift c.P/(RT), also written."))
+        .arg(Arg::with_name("gradient")
+            .long("gradient")
+            .conflicts_with_all(&["compare-gas", "gamma", "enthalpy", "entropy", "root-count", "root", "phase", "liquid-density", "partial-volumes", "partial-pressures", "sensitivity", "path", "chart", "properties", "coeffs"])
+            .help("Writes Z and its derivatives ∂Z/∂P and ∂Z/∂T over the pressure and temperature ranges, one line per condition. Both derivatives are analytic, from the implicit differentiation of the equation of state. The derivatives are per unit of the pressure and temperature as entered."))
+        .arg(Arg::with_name("partial-pressures")
+            .long("partial-pressures")
+            .conflicts_with_all(&["compare-gas", "gamma", "enthalpy", "entropy", "root-count", "root", "saturation", "partial-volumes"])
+            .help("Writes the partial pressure xi.P of each component of a gas mixture at a single condition, followed by its fugacity xi.φi.P (the effective partial pressure), both absolute in the pressure unit"))
+        .arg(Arg::with_name("chart")
+            .long("chart")
+            .conflicts_with_all(&["compare-gas", "relative", "saturation", "boyle", "partial-volumes"])
+            .help("Writes a generalized compressibility chart: a CSV table of Z with one row per reduced pressure Pr and one column per reduced temperature Tr. -p and -t specify the Pr and Tr ranges (0:10:21n and 1:2:11n by default). The fluid is the gas given with -g or a generic fluid given with --acentric."))
+        .arg(Arg::with_name("acentric")
+            .long("acentric")
+            .requires("chart")
+            .conflicts_with("gas")
+            .allow_hyphen_values(true)
+            .help("Specify the acentric factor of the generic fluid of --chart")
+            .takes_value(true))
+        .arg(Arg::with_name("sat-vapor-density")
+            .long("sat-vapor-density")
+            .conflicts_with_all(&["compare-gas", "gamma", "enthalpy", "entropy", "saturation", "hvap"])
+            .help("Computes the density in kg/m3 of the saturated vapor of a pure gas over the temperature range, from the vapor root at the saturation pressure. No pressure is needed."))
+        .arg(Arg::with_name("hvap")
+            .long("hvap")
+            .conflicts_with_all(&["compare-gas", "gamma", "enthalpy", "entropy", "saturation"])
+            .help("Computes the enthalpy of vaporization in J/mol of a pure gas over the temperature range, from the saturated liquid and vapor. Temperatures above the critical temperature are skipped. No pressure is needed."))
+        .arg(Arg::with_name("max-iter")
+            .long("max-iter")
+            .help("Specify the maximum number of iterations of the iterative solvers")
+            .takes_value(true))
+        .arg(Arg::with_name("tol")
+            .long("tol")
+            .help("Specify the relative tolerance of the iterative solvers")
+            .takes_value(true))
+        .arg(Arg::with_name("precision")
+            .long("precision")
+            .help("Specify the number of decimals of the computed values")
+            .takes_value(true))
+        .arg(Arg::with_name("scientific")
+            .long("scientific")
+            .help("Writes the computed values in exponential notation (e.g. 1.234e-03)"))
+        .arg(Arg::with_name("color")
+            .long("color")
+            .help("Colors the Z values by their deviation from the ideal gas (green within 1%, yellow within 10%, red beyond). \"auto\" colors only when writing to a terminal.")
+            .possible_values(&["auto", "always", "never"])
+            .default_value("auto")
+            .takes_value(true))
+        .arg(Arg::with_name("annotate")
+            .long("annotate")
+            .help("Writes the quantity and the conditions along with a scalar result, e.g. \"Z(200 barG, 20°C) = 1.0268\""))
+        .arg(Arg::with_name("format")
+            .long("format")
+            .possible_values(FORMATS)
+            .default_value("text")
+            .help("Output format of the tables. 'jsonl' writes one JSON object per line and per condition, e.g. {\"p\":200,\"p_unit\":\"bar\",\"t\":20,\"t_unit\":\"°C\",\"z\":1.0268}, with an \"eos\" member when several equations of state are given. 'table' writes the table with aligned columns and a light border, for reading in a terminal. 'binary' (with the binary cargo feature) writes the compression factors of a single equation of state in the length-prefixed binary format of rkz::ZGrid, with the pressures in Pa and the temperatures in K."))
+        .arg(Arg::with_name("bare-header")
+            .long("bare-header")
+            .help("Writes the CSV header without units"))
+        .arg(Arg::with_name("debug-roots")
+            .long("debug-roots")
+            .help("Prints the raw roots of the cubic equation and the selected one to stderr for each computed condition"))
+        .arg(Arg::with_name("show-composition")
+            .long("show-composition")
+            .help("Prints the normalized molar fractions of the gas to stderr before computing"))
+        .arg(Arg::with_name("sort-components")
+            .long("sort-components")
+            .help("Orders the components of the mixtures by descending molar fraction, then by id, so that the output doesn't depend on the order of the spec"))
+        .arg(Arg::with_name("fraction-precision")
+            .long("fraction-precision")
+            .help("Rounds the molar fractions of the mixtures to this number of decimals (e.g. 3 for 0.333, 0.333 and 0.334 with N2+O2+CO2). The largest component takes the rounding residual so that the fractions still sum to 1. At most 15 decimals.")
+            .takes_value(true))
+        .arg(Arg::with_name("max-cells")
+            .long("max-cells")
+            .help("Fails before computing when the pressure and temperature ranges would make more cells than this number (10 million by default)")
+            .takes_value(true))
+        .arg(Arg::with_name("clip-domain")
+            .long("clip-domain")
+            .help("Restricts the temperature and pressure ranges to the validity domain of the gas data, warning about the clipped portions"))
+        .arg(Arg::with_name("force-table")
+            .long("force-table")
+            .help("Writes a table with headers even for a single pressure and temperature, which are otherwise written as a single value"))
+        .arg(Arg::with_name("transpose")
+            .long("transpose")
+            .help("Writes the CSV table with one row per temperature and one column per pressure (T \\ P) instead of one row per pressure")
+            .conflicts_with_all(&["compare-gas", "partial-volumes"]))
+        .arg(Arg::with_name("summary")
+            .long("summary")
+            .help("Prints the minimum, maximum and mean of the table values to stderr, along with the conditions of the extremes"))
+        .arg(Arg::with_name("warn")
+            .long("warn")
+            .help("Prints notes about the validity of the computation to stderr"))
+        .arg(Arg::with_name("cases")
+            .long("cases")
+            .help("Runs the named cases of a TOML file and writes one labeled result per case. Each [[case]] has a name, gas, pressure (bar), temperature (°C) and optionally eos, quantity (z, gamma, enthalpy, entropy, root-count or viscosity) and reference (T,P).")
+            .conflicts_with_all(&["gas", "compare-gas"])
+            .takes_value(true))
+        .arg(Arg::with_name("params")
+            .long("params")
+            .help("Prints the parameters of the equation of state selected with --eos"))
+        .arg(Arg::with_name("dry-run")
+            .long("dry-run")
+            .conflicts_with_all(&["saturation", "cases"])
+            .help("Validates the arguments and prints a summary of the computation (equation, composition and number of cells) without computing"))
+        .arg(Arg::with_name("error-format")
+            .long("error-format")
+            .help("Specify the format of the error messages written to stderr. \"json\" writes an object with the error category, the message and the exit code, e.g. {\"error\":\"ParseError\",\"message\":\"...\",\"code\":3}")
+            .possible_values(&["text", "json"])
+            .default_value("text")
+            .takes_value(true))
+        .arg(Arg::with_name("about-gas")
+            .long("about-gas")
+            .help("Prints a card about a referenced gas: critical constants, acentric factor, molar mass, heat capacity data, validity domain, recommended equation of state and notes about its accuracy")
             .takes_value(true))
         .arg(Arg::with_name("list-gas")
             .long("list-gas")
             .help("Prints a list of referenced gases"))
+        .arg(Arg::with_name("consistency-check")
+            .long("consistency-check")
+            .help("Checks the equations of state invariants for every referenced gas and reports the violations"))
+        .arg(Arg::with_name("metering-check")
+            .long("metering-check")
+            .help("Compares the Z-factor of the equation of state selected with --eos to reference values at gas metering conditions and reports the deviations"))
+        .arg(Arg::with_name("compare-eos-to-data")
+            .long("compare-eos-to-data")
+            .conflicts_with_all(&["temperature", "pressure", "compare-gas"])
+            .help("Reads measured points \"P,T,Z\" of the gas (-g) in bar and °C, one per line, and writes the RMS deviation of the Z-factor of every equation of state, followed by the best fit")
+            .takes_value(true))
+        .arg(Arg::with_name("batch")
+            .long("batch")
+            .conflicts_with_all(&["gas", "compare-gas", "temperature", "pressure", "cases"])
+            .help("Reads one condition per line from stdin: a gas (id or mixture spec), a pressure in bar and a temperature in °C separated by whitespace. Blank lines and '#' comments are skipped. Writes the Z-factor of each line, or an error message in place of the lines that can't be parsed or computed."))
+        .arg(Arg::with_name("cache")
+            .long("cache")
+            .requires("batch")
+            .value_name("N")
+            .help("Keeps the last N Z-factors computed by --batch and reuses them for the lines of the same gas and equation of state. The conditions are rounded to 1 Pa and 1 mK: a line within these steps of a cached one gets its Z-factor.")
+            .takes_value(true))
+        .arg(Arg::with_name("benchmark-eos")
+            .long("benchmark-eos")
+            .conflicts_with_all(&["temperature", "pressure", "compare-gas"])
+            .help("Times the computation of Z with every equation of state on the gas given with -g (air by default) and prints the mean time per computation"))
         .arg(Arg::with_name("license")
             .long("license")
             .help("Prints the license text and exits")
-        )
-        .get_matches();
+        );
+    #[cfg(feature = "rayon")]
+    let app = app.arg(Arg::with_name("threads")
+        .long("threads")
+        .requires("batch")
+        .conflicts_with("cache")
+        .help("Processes the lines of --batch on this number of threads (with the rayon cargo feature). The whole input is read first and the results are written in the input order.")
+        .takes_value(true));
+    #[cfg(feature = "watch")]
+    let app = app.arg(Arg::with_name("watch")
+        .long("watch")
+        .help("Watches the cases file (--cases) or the gas file (-g @path) and recomputes each time it is saved, until Ctrl-C (with the watch cargo feature)")
+        .conflicts_with_all(&["batch", "dry-run"]));
+    app
+}
+
+fn main() {
+    let _ = STDOUT_IS_TERMINAL.set(io::stdout().is_terminal());
+    let args = args_with_env(std::env::args().collect(), |var| std::env::var(var).ok());
+    let matches = match app().get_matches_from_safe(args) {
+        Ok(matches) => matches,
+        Err(err) if err.use_stderr() => {
+            let format = ErrorFormat::from_raw_args(std::env::args());
+            exit_with(Error::Usage(err.message), format);
+        }
+        // --help or --version
+        Err(err) => err.exit(),
+    };
+    let format = ErrorFormat::from_matches(&matches);
+
+    if let Err(err) = check_flags(&matches) {
+        exit_with(Error::Usage(err), format);
+    }
+
+    #[cfg(feature = "watch")]
+    if matches.is_present("watch") {
+        report(process_watch(&matches, format), format);
+        return;
+    }
 
     let mut done_something = false;
 
     if matches.is_present("list-gas") {
-        println!("Gases referenced by RKZ:");
-        println!("    ID        Name");
-        for g in GASES.iter() {
-            let space = 10 - g.id.chars().count();
-            assert!(space > 0);
-            let space = " ".repeat(space);
-            println!("    {}{}{}", g.id, space, g.name);
+        report(
+            write_gas_list(GASES, &mut stdout_writer()).map_err(Error::Io),
+            format,
+        );
+        done_something = true;
+    }
+
+    if let Some(id) = matches.value_of("about-gas") {
+        let gas = find_gas(id).unwrap_or_else(|| {
+            exit_with(
+                Error::Parse(format!("{}: the requested gas is not referenced", id)),
+                format,
+            )
+        });
+        report(write_gas_card(gas, &mut stdout_writer()), format);
+        done_something = true;
+    }
+
+    if matches.is_present("params") {
+        let eos =
+            eos_from_matches(&matches).unwrap_or_else(|err| exit_with(Error::Usage(err), format));
+        println!("Equation: {}", eos.name());
+        println!("    P = RT/(v-b) - a(T)/((v+δ1.b)(v+δ2.b))");
+        match (eos.deltas(), eos.critical_z()) {
+            (Some((d1, d2)), Some(zc)) => {
+                println!("    δ1 = {}", d1);
+                println!("    δ2 = {}", d2);
+                println!("    Zc = {}", zc);
+            }
+            _ => {
+                println!("    δ1 fitted to the critical compressibility of each gas");
+                println!("    δ2 = (1-δ1)/(1+δ1)");
+            }
         }
         done_something = true;
     }
@@ -110,48 +602,437 @@ fn main() {
         done_something = true;
     }
 
-    let gas = matches.value_of("gas");
+    if matches.is_present("consistency-check") {
+        let (num, violations) = check::consistency_check();
+        for v in violations.iter() {
+            println!("{} {:?} {:?}: {}", v.gas, v.eos, v.invariant, v.message);
+        }
+        println!("{} checks, {} violations", num, violations.len());
+        if !violations.is_empty() {
+            process::exit(1);
+        }
+        done_something = true;
+    }
+
+    if matches.is_present("metering-check") {
+        report(
+            process_metering_check(&matches, &mut stdout_writer()),
+            format,
+        );
+        done_something = true;
+    }
+
+    if matches.is_present("benchmark-eos") {
+        report(process_benchmark(&matches, &mut stdout_writer()), format);
+        done_something = true;
+    }
+
+    if let Some(path) = matches.value_of("cases") {
+        report(process_cases(path, &matches, &mut stdout_writer()), format);
+        done_something = true;
+    }
+
+    if matches.is_present("batch") {
+        report(
+            process_batch(&matches, &mut io::stdin().lock(), &mut stdout_writer()),
+            format,
+        );
+        done_something = true;
+    }
+
+    let gas = matches
+        .value_of("gas")
+        .or_else(|| matches.value_of("compare-gas"));
     let temperature = matches.value_of("temperature");
     let pressure = matches.value_of("pressure");
-    let relative = matches.value_of("relative");
-    let eos = matches.value_of("equation");
+
+    if matches.is_present("chart") {
+        report(process_chart(&matches, &mut stdout_writer()), format);
+        done_something = true;
+    }
 
     match (gas, temperature, pressure) {
         (None, None, None) => {}
-        (Some(gas), Some(temperature), Some(pressure)) => {
-            match process_args(gas, temperature, pressure, relative, eos) {
-                Err(err) => {
-                    eprintln!("{}", err);
-                    process::exit(1);
-                }
-                _ => {
-                    done_something = true;
-                }
-            }
+        _ if matches.is_present("chart") => {}
+        (Some(_), None, None) if matches.is_present("benchmark-eos") => {}
+        (Some(_), None, None) if matches.is_present("compare-eos-to-data") => {
+            report(process_data_fit(&matches, &mut stdout_writer()), format);
+            done_something = true;
         }
-        _ => {
-            eprintln!("Unsufficient parameters. Please specify gas, temperature and pressure");
-            process::exit(1);
+        (Some(_), None, None) if matches.is_present("boyle") => {
+            report(process_boyle(&matches, &mut stdout_writer()), format);
+            done_something = true;
+        }
+        (Some(_), Some(_), _)
+            if matches.is_present("saturation")
+                || matches.is_present("hvap")
+                || matches.is_present("sat-vapor-density") =>
+        {
+            report(process_saturation(&matches, &mut stdout_writer()), format);
+            done_something = true;
+        }
+        (Some(_), Some(_), None) if matches.is_present("density-input") => {
+            report(process_density(&matches, &mut stdout_writer()), format);
+            done_something = true;
+        }
+        (Some(_), Some(_), None) if matches.is_present("virial-b") => {
+            report(process_virial(&matches, &mut stdout_writer()), format);
+            done_something = true;
         }
+        (Some(_), Some(_), Some(_)) => {
+            report(process_args(&matches, &mut stdout_writer()), format);
+            done_something = true;
+        }
+        _ => exit_with(
+            Error::Usage(
+                "Unsufficient parameters. Please specify gas, temperature and pressure".into(),
+            ),
+            format,
+        ),
     }
 
     if !done_something {
-        eprintln!("No parameter supplied.");
-        process::exit(1);
-    }
-}
-
-fn process_args(
-    gas: &str,
-    temperature: &str,
-    pressure: &str,
-    relative: Option<&str>,
-    eos: Option<&str>,
-) -> Result<(), String> {
-    let gas = Gas::from_string(gas)?;
-    let temperature = Range::parse(temperature)?;
-    let mut pressure = Range::parse(pressure)?;
-    let relative = relative.map(|r| {
+        exit_with(Error::Usage("No parameter supplied.".into()), format);
+    }
+}
+
+/// Writes the id and name of `gases`, with the name column aligned after the longest id
+fn write_gas_list(gases: &[PureGas], out: &mut dyn Write) -> io::Result<()> {
+    let width = gases
+        .iter()
+        .map(|g| g.id.chars().count() + 2)
+        .fold(10, usize::max);
+    writeln!(out, "Gases referenced by RKZ:")?;
+    writeln!(out, "    {:width$}Name", "ID", width = width)?;
+    for g in gases.iter() {
+        writeln!(out, "    {:width$}{}", g.id, g.name, width = width)?;
+    }
+    out.flush()
+}
+
+/// Writes what is known about `gas` and how well the equations of state describe it
+fn write_gas_card(gas: &PureGas, out: &mut dyn Write) -> Result<(), Error> {
+    let line = |out: &mut dyn Write, label: &str, value: String| {
+        writeln!(out, "    {:22}{}", label, value)
+    };
+    writeln!(out, "{} - {}", gas.id, gas.name)?;
+    line(
+        out,
+        "critical temperature",
+        format!("{} K ({:.2}°C)", gas.tc, Celsius::from(Kelvin(gas.tc)).0),
+    )?;
+    line(
+        out,
+        "critical pressure",
+        format!("{} bar", Bar::from(Pascal(gas.pc)).0),
+    )?;
+    line(
+        out,
+        "acentric factor",
+        gas.w.map_or("unknown".into(), |w| w.to_string()),
+    )?;
+    line(
+        out,
+        "molar mass",
+        gas.molar_mass
+            .map_or("unknown".into(), |m| format!("{:.3} g/mol", m * 1000f64)),
+    )?;
+    let cp = match gas.cp_ideal_coeffs {
+        [0f64, 0f64, 0f64, 0f64] => "unknown".to_string(),
+        [c0, 0f64, 0f64, 0f64] => format!("constant, {} J/(mol.K)", c0),
+        _ => "polynomial of T".to_string(),
+    };
+    line(out, "ideal gas Cp", cp)?;
+    let domain = match (gas.valid_t, gas.valid_p) {
+        (Some((t_min, t_max)), Some((_, p_max))) => format!(
+            "{} to {} K, up to {} bar",
+            t_min,
+            t_max,
+            Bar::from(Pascal(p_max)).0
+        ),
+        _ => "unknown".to_string(),
+    };
+    line(out, "validity domain", domain)?;
+    let eos = gas.recommended_eos();
+    line(
+        out,
+        "recommended EOS",
+        format!("{} ({})", eos.as_abbrev(), eos.name()),
+    )?;
+
+    let mut notes = Vec::new();
+    if gas.is_associating() {
+        notes.push(
+            "forms hydrogen bonds, which the cubic equations ignore: expect errors of several percent near saturation and in the liquid",
+        );
+    }
+    if eos == Eos::RedlichKwong {
+        notes.push(
+            "the acentric factor is out of the range of the SRK and PR correlations (quantum gas or unknown)",
+        );
+    }
+    if Kelvin(gas.tc) > Kelvin::from(Celsius(0f64)) {
+        notes.push(
+            "condensable at ambient temperature: the volume translation (-e PRP or --volume-shift) or RKPR (-e RKPR) improve the liquid densities",
+        );
+    }
+    for note in notes.iter() {
+        writeln!(out, "    note: {}", note)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Rejects the combinations of flags that clap can't express and that would be silently ignored
+/// or would conflict in confusing ways
+fn check_flags(matches: &ArgMatches) -> Result<(), String> {
+    let has = |name| matches.is_present(name);
+    // modes computing without the pressure of -p
+    let no_pressure = [
+        "saturation",
+        "hvap",
+        "sat-vapor-density",
+        "boyle",
+        "virial-b",
+        "density-input",
+        "chart",
+        "cases",
+    ]
+    .iter()
+    .any(|&mode| has(mode));
+
+    if has("relative") && !has("pressure") {
+        return Err(
+            "--relative offsets the pressure only and requires a pressure (-p). Temperatures are always absolute."
+                .into(),
+        );
+    }
+    if has("pressure-unit") && (!has("pressure") || no_pressure) {
+        return Err(
+            "--pressure-unit sets the unit of the pressure (-p) and requires a computation at this pressure. --chart takes reduced pressures and --cases pressures in bar."
+                .into(),
+        );
+    }
+    if has("temperature-unit") && (!has("temperature") || has("chart") || has("cases")) {
+        return Err(
+            "--temperature-unit sets the unit of the temperature (-t) and requires a temperature. --chart takes reduced temperatures and --cases temperatures in °C."
+                .into(),
+        );
+    }
+    if has("reduced") && (has("chart") || has("cases")) {
+        return Err(
+            "--reduced applies to -p and -t only. --chart already takes reduced coordinates and --cases absolute conditions."
+                .into(),
+        );
+    }
+    let gas_file = matches
+        .value_of("gas")
+        .is_some_and(|gas| gas.starts_with('@'));
+    if has("watch") && !has("cases") && !(gas_file && has("temperature") && has("pressure")) {
+        return Err(
+            "--watch requires a cases file (--cases), or a gas file (-g @path) with a temperature and a pressure."
+                .into(),
+        );
+    }
+    if matches.value_of("format") == Some("binary") {
+        // modes writing their own output instead of the table of Z
+        let mode = [
+            "compare-gas",
+            "dry-run",
+            "saturation",
+            "hvap",
+            "sat-vapor-density",
+            "boyle",
+            "virial-b",
+            "density-input",
+            "partial-volumes",
+            "partial-pressures",
+            "sensitivity",
+            "path",
+            "properties",
+            "coeffs",
+            "gradient",
+            "chart",
+            "cases",
+            "batch",
+            "compare-eos-to-data",
+            "metering-check",
+            "consistency-check",
+            "benchmark-eos",
+        ]
+        .iter()
+        .find(|&&mode| has(mode));
+        if let Some(mode) = mode {
+            return Err(format!(
+                "--format binary writes the table of compression factors and doesn't apply to --{}.",
+                mode
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn stdout_writer() -> BufWriter<io::Stdout> {
+    BufWriter::new(io::stdout())
+}
+
+/// Exits the process if the processing failed
+fn report(result: Result<(), Error>, format: ErrorFormat) {
+    match result {
+        Err(Error::Io(err)) if err.kind() == io::ErrorKind::BrokenPipe => {
+            // the reader went away (e.g. `rkz ... | head`), nothing left to do
+            process::exit(0);
+        }
+        Err(err) => exit_with(err, format),
+        Ok(()) => {}
+    }
+}
+
+/// Prints the error to stderr
+fn print_error(err: &Error, format: ErrorFormat) {
+    match format {
+        ErrorFormat::Text => eprintln!("{}", err),
+        ErrorFormat::Json => eprintln!("{}", err.to_json()),
+    }
+}
+
+/// Prints the error and exits with its code
+fn exit_with(err: Error, format: ErrorFormat) -> ! {
+    print_error(&err, format);
+    process::exit(err.code());
+}
+
+/// Format of the error messages written to stderr
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum ErrorFormat {
+    Text,
+    Json,
+}
+
+impl ErrorFormat {
+    fn from_matches(matches: &ArgMatches) -> ErrorFormat {
+        match matches.value_of("error-format") {
+            Some("json") => ErrorFormat::Json,
+            _ => ErrorFormat::Text,
+        }
+    }
+
+    /// Looks for --error-format in the raw arguments, when clap could not parse them
+    fn from_raw_args<I: Iterator<Item = String>>(args: I) -> ErrorFormat {
+        let args: Vec<String> = args.collect();
+        let json = args.iter().any(|a| a == "--error-format=json")
+            || args
+                .windows(2)
+                .any(|w| w[0] == "--error-format" && w[1] == "json");
+        if json {
+            ErrorFormat::Json
+        } else {
+            ErrorFormat::Text
+        }
+    }
+}
+
+/// Error of the command line processing, by category of exit code
+#[derive(Debug)]
+enum Error {
+    /// Invalid or insufficient arguments
+    Usage(String),
+    /// Unknown gas, invalid gas spec or invalid cases file
+    Parse(String),
+    /// Failure of the computation
+    Compute(String),
+    /// Failure to write the output
+    Io(io::Error),
+}
+
+impl Error {
+    /// Exit code of the process (see the EXIT CODES section of the help)
+    fn code(&self) -> i32 {
+        match self {
+            Error::Io(_) => 1,
+            Error::Usage(_) => 2,
+            Error::Parse(_) => 3,
+            Error::Compute(_) => 4,
+        }
+    }
+
+    /// Name of the error category
+    fn name(&self) -> &'static str {
+        match self {
+            Error::Usage(_) => "UsageError",
+            Error::Parse(_) => "ParseError",
+            Error::Compute(_) => "ComputeError",
+            Error::Io(_) => "IoError",
+        }
+    }
+
+    /// JSON object with the category, the message and the exit code
+    fn to_json(&self) -> String {
+        format!(
+            "{{\"error\":\"{}\",\"message\":{},\"code\":{}}}",
+            self.name(),
+            json_string(&self.to_string()),
+            self.code()
+        )
+    }
+}
+
+/// Quotes and escapes `s` as a JSON string
+fn json_string(s: &str) -> String {
+    let mut res = String::with_capacity(s.len() + 2);
+    res.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            '\r' => res.push_str("\\r"),
+            '\t' => res.push_str("\\t"),
+            c if (c as u32) < 0x20 => res.push_str(&format!("\\u{:04x}", c as u32)),
+            c => res.push(c),
+        }
+    }
+    res.push('"');
+    res
+}
+
+impl From<ParseError> for Error {
+    fn from(err: ParseError) -> Self {
+        match err {
+            ParseError::Gas(msg) => Error::Parse(msg),
+            ParseError::Eos(msg) | ParseError::Range(msg) => Error::Usage(msg),
+        }
+    }
+}
+
+impl From<EosError> for Error {
+    fn from(err: EosError) -> Self {
+        Error::Compute(err.to_string())
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Usage(msg) | Error::Parse(msg) | Error::Compute(msg) => write!(f, "{}", msg),
+            Error::Io(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+/// Default of --max-cells
+const DEFAULT_MAX_CELLS: usize = 10_000_000;
+
+fn process_args(matches: &ArgMatches, out: &mut dyn Write) -> Result<(), Error> {
+    let temperature = Range::try_from(matches.value_of("temperature").unwrap())?;
+    let pressure = Range::try_from(matches.value_of("pressure").unwrap())?;
+    let relative = matches.value_of("relative").map(|r| {
         if r == "stdatm" {
             Ok(1.01325)
         } else {
@@ -161,148 +1042,4376 @@ fn process_args(
     // convert from Option<Result<f64>> to Option<f64> (returning the Err if any).
     let relative = match relative {
         Some(Ok(rel)) => Some(rel),
-        Some(Err(err)) => return Err(err),
+        Some(Err(err)) => return Err(Error::Usage(err)),
         None => None,
     };
 
-    let eos = match eos {
-        Some(eos) => {
-            let lw = eos.to_lowercase();
-            if lw == "vdw" {
-                Eos::VanDerWaals
-            } else if lw == "rk" {
-                Eos::RedlichKwong
-            } else if lw == "srk" {
-                Eos::SoaveRedlichKwong
-            } else if lw == "pr" {
-                Eos::PengRobinson
-            } else {
-                panic!("Unknown equation of state: {}", eos)
-            }
-        }
-        None => Eos::RedlichKwong,
+    let eoses = eoses_from_matches(matches).map_err(Error::Usage)?;
+
+    let mut cond = Conditions {
+        temperature,
+        pressure,
+        relative,
+        strict: matches.is_present("strict"),
+        reduced: None,
+        t_unit: TemperatureUnit::from_matches(matches),
+        p_unit: PressureUnit::from_matches(matches),
     };
 
-    if let Some(relative) = relative {
-        pressure.start += relative;
-        pressure.stop += relative;
+    let max_cells = match matches.value_of("max-cells") {
+        Some(max) => max
+            .parse()
+            .map_err(|_| Error::Usage(format!("Can't parse \"{}\" as a number of cells", max)))?,
+        None => DEFAULT_MAX_CELLS,
+    };
+    let (num_p, num_t) = (cond.pressure.num_values(), cond.temperature.num_values());
+    if num_p.saturating_mul(num_t) > max_cells {
+        return Err(Error::Usage(format!(
+            "{} pressures x {} temperatures make {} cells, more than the limit of {} (see --max-cells)",
+            num_p,
+            num_t,
+            num_p as u128 * num_t as u128,
+            max_cells
+        )));
     }
 
-    match (temperature.is_scalar(), pressure.is_scalar()) {
-        (true, true) => {
-            let p_pa = pressure.start * 100000f64;
-            let t_k = temperature.start + 273.15;
-            println!("{}", gas.z(eos, p_pa, t_k));
+    let quantity = Quantity::from_matches(matches).map_err(Error::Usage)?;
+    let output = OutputOpts::from_matches(matches).map_err(Error::Usage)?;
+    let parse_opts = parse_opts_from_matches(matches)?;
+
+    if let Some(specs) = matches.value_of("compare-gas") {
+        let mut gases = Vec::new();
+        for spec in specs.split(',') {
+            let gas = Gas::from_string_with(spec, &parse_opts)
+                .map_err(|err| Error::Parse(format!("{}: {}", spec, err)))?;
+            gases.push((spec, gas));
         }
-        (_, _) => {
-            // writing CSV
-            // header
-            print!("P \\ T");
-            for t in temperature.iter() {
-                print!("\t{}", t);
+        if matches.is_present("show-composition") {
+            for (spec, gas) in gases.iter() {
+                eprintln!("{}: {}", spec, gas.composition());
             }
-            // rows
-            for p in pressure.iter() {
-                let phead = if let Some(relative) = relative {
-                    p - relative
-                } else {
-                    p
-                };
-                print!("\n{}", phead);
-                let p = p * 100000f64;
-                for t in temperature.iter().map(|t| t + 273.15f64) {
-                    print!("\t{}", gas.z(eos, p, t));
+        }
+        if matches.is_present("warn") {
+            for (_, gas) in gases.iter() {
+                for &(_, eos) in eoses.iter() {
+                    print_warnings(gas, eos);
                 }
             }
-            println!();
         }
+        if matches.is_present("clip-domain") {
+            for (_, gas) in gases.iter() {
+                for w in cond.clip_domain(gas).map_err(Error::Usage)? {
+                    eprintln!("warning: {}", w);
+                }
+            }
+        }
+        if matches!(quantity, Quantity::Viscosity) {
+            print_viscosity_warnings(&cond);
+        }
+        if matches.is_present("dry-run") {
+            return write_dry_run(&gases, &eoses, quantity, &cond, out);
+        }
+        return compare_gases(&gases, &eoses, quantity, &cond, &output, out);
     }
-    Ok(())
-}
 
-struct Range {
-    start: f64,
-    stop: f64,
-    step: f64,
-}
+    let gas = Gas::from_string_with(gas_spec(matches)?.as_deref().unwrap(), &parse_opts)
+        .map_err(Error::Parse)?;
+
+    if matches.is_present("show-composition") {
+        eprintln!("composition: {}", gas.composition());
+    }
+
+    if matches.is_present("reduced") {
+        cond.reduced = Some(gas.criticals());
+    }
+
+    if matches.is_present("warn") {
+        for &(_, eos) in eoses.iter() {
+            print_warnings(&gas, eos);
+        }
+    }
+
+    if matches.is_present("clip-domain") {
+        for w in cond.clip_domain(&gas).map_err(Error::Usage)? {
+            eprintln!("warning: {}", w);
+        }
+    }
+
+    if matches!(quantity, Quantity::Viscosity) {
+        print_viscosity_warnings(&cond);
+    }
 
-impl Range {
-    fn parse(input: &str) -> Result<Range, String> {
-        let v = {
-            let mut v: Vec<f64> = Vec::new();
-            for s in input.split(':') {
-                v.push(util::parse_num(s)?);
+    if matches.is_present("dry-run") {
+        let spec = matches.value_of("gas").unwrap();
+        return write_dry_run(&[(spec, gas)], &eoses, quantity, &cond, out);
+    }
+
+    if matches.is_present("partial-volumes") {
+        let mix = match gas {
+            Gas::Mixture(mix) => mix,
+            Gas::Pure(_) => {
+                return Err(Error::Usage(
+                    "Partial molar volumes require a gas mixture".into(),
+                ))
             }
-            v
         };
+        return write_partial_volumes(&mix, &eoses, &cond, &output, out);
+    }
 
-        match v.len() {
-            1 => {
-                let val = v[0];
-                Ok(Range {
-                    start: val,
-                    stop: val,
-                    step: 1f64,
-                })
-            }
-            2 => {
-                let start = v[0];
-                let stop = v[1];
-                if stop <= start {
-                    Err("Range stop must be higher than start".into())
-                } else {
-                    Ok(Range {
-                        start,
-                        stop,
-                        step: 1f64,
-                    })
-                }
-            }
-            3 => {
-                let start = v[0];
-                let stop = v[1];
-                let step = v[2];
-                if stop <= start {
-                    Err("Range stop must be higher than start".into())
-                } else if step <= 0f64 {
-                    Err("Range step must be positive".into())
-                } else {
-                    Ok(Range { start, stop, step })
-                }
+    if matches.is_present("partial-pressures") {
+        let mix = match gas {
+            Gas::Mixture(mix) => mix,
+            Gas::Pure(_) => {
+                return Err(Error::Usage(
+                    "Partial pressures require a gas mixture".into(),
+                ))
             }
-            _ => Err(format!("Can't parse \"{}\" as a range", input)),
+        };
+        return write_partial_pressures(&mix, &eoses, &cond, &output, out);
+    }
+
+    if matches.is_present("sensitivity") {
+        let delta = util::parse_num(matches.value_of("sensitivity-delta").unwrap_or("0.01"))
+            .map_err(Error::Usage)?;
+        if delta <= 0f64 {
+            return Err(Error::Usage("--sensitivity-delta must be positive".into()));
         }
+        return write_sensitivity(&gas, &eoses, &cond, &output, delta, out);
     }
 
-    fn is_scalar(&self) -> bool {
-        self.start + self.step > self.stop
+    if matches.is_present("properties") {
+        return write_properties(&gas, &eoses, &cond, &output, out);
     }
 
-    fn iter(&self) -> ScalarIt {
-        ScalarIt {
-            cur: self.start,
-            stop: self.stop,
-            step: self.step,
-        }
+    if matches.is_present("coeffs") {
+        return write_coeffs(&gas, &eoses, &cond, &output, out);
     }
-}
 
-struct ScalarIt {
-    cur: f64,
-    stop: f64,
-    step: f64,
-}
+    if matches.is_present("gradient") {
+        return write_gradient(&gas, &eoses, &cond, &output, out);
+    }
+
+    if let Some(path) = matches.value_of("path") {
+        let path: ProcessPath = path.parse().map_err(Error::Usage)?;
+        let target =
+            util::parse_num(matches.value_of("target-pressure").unwrap()).map_err(Error::Usage)?;
+        let config = solver_config_from_matches(matches).map_err(Error::Usage)?;
+        return write_path(&gas, &eoses, &cond, &output, path, target, &config, out);
+    }
+
+    let Conditions {
+        temperature,
+        pressure,
+        ..
+    } = &cond;
 
-impl Iterator for ScalarIt {
-    type Item = f64;
+    // a single condition is written as a single value, unless a table is forced
+    let write_scalar = |out: &mut dyn Write, p: f64, t: f64| -> Result<(), Error> {
+        if let Some(msg) = cond.non_positive(p) {
+            return Err(Error::Usage(msg));
+        }
+        let p_pa = cond.pressure_pa(p);
+        let t_k = cond.temperature_k(t);
+        for &(label, eos) in eoses.iter() {
+            if output.debug_roots {
+                print_debug_roots(&gas, eos, p_pa, t_k);
+            }
+            let value = quantity.compute(&gas, eos, p_pa, t_k)?;
+            if eoses.len() > 1 {
+                write!(out, "{}\t", label)?;
+            }
+            if output.annotate {
+                // labelled with the pressure as entered by the user
+                writeln!(
+                    out,
+                    "{}({}, {}) = {}{}",
+                    quantity.label(),
+                    cond.pressure_display(p),
+                    cond.temperature_display(t),
+                    output.format_quantity(quantity, value),
+                    quantity.unit()
+                )?;
+            } else {
+                writeln!(out, "{}", output.format_quantity(quantity, value))?;
+            }
+        }
+        out.flush()?;
+        Ok(())
+    };
+    let jsonl = matches.value_of("format") == Some("jsonl");
+    let bordered = matches.value_of("format") == Some("table");
+    let binary = matches.value_of("format") == Some("binary");
+    let force_table = matches.is_present("force-table") || jsonl || bordered;
+    let scalar_binary = || {
+        Error::Usage(
+            "Binary output writes a table and requires a range of pressures or temperatures (or --force-table)"
+                .into(),
+        )
+    };
+    if temperature.is_scalar() && pressure.is_scalar() && !force_table {
+        if binary {
+            return Err(scalar_binary());
+        }
+        return write_scalar(out, pressure.start, temperature.start);
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.cur <= self.stop {
-            let res = self.cur;
-            self.cur += self.step;
-            Some(res)
+    // writing CSV, flushing each row so that large tables are streamed
+    // one row per pressure and one column per temperature, or the opposite if transposed
+    let transpose = matches.is_present("transpose");
+    let mut pressures = Vec::new();
+    for p in pressure.iter() {
+        if !cond.skip_pressure(p).map_err(Error::Usage)? {
+            pressures.push(p);
+        }
+    }
+    let temperatures: Vec<f64> = temperature.iter().collect();
+    // e.g. a single pressure left once the non-positive ones are skipped
+    if pressures.len() == 1 && temperatures.len() == 1 && !force_table {
+        if binary {
+            return Err(scalar_binary());
+        }
+        return write_scalar(out, pressures[0], temperatures[0]);
+    }
+    #[cfg(feature = "binary")]
+    {
+        if binary {
+            if eoses.len() > 1 || !matches!(quantity, Quantity::Z) {
+                return Err(Error::Usage(
+                    "Binary output requires the compression factor of a single equation of state"
+                        .into(),
+                ));
+            }
+            let ps: Vec<Pascal> = pressures.iter().map(|&p| cond.pressure_pa(p)).collect();
+            let ts: Vec<Kelvin> = temperatures
+                .iter()
+                .map(|&t| cond.temperature_k(t))
+                .collect();
+            z_grid(&gas, eoses[0].1, &ps, &ts)?.write_binary(out)?;
+            out.flush()?;
+            return Ok(());
+        }
+    }
+    if jsonl {
+        let mut summary = Summary::default();
+        for &p in pressures.iter() {
+            for &t in temperatures.iter() {
+                let (p_pa, t_k) = (cond.pressure_pa(p), cond.temperature_k(t));
+                for &(label, eos) in eoses.iter() {
+                    if output.debug_roots {
+                        print_debug_roots(&gas, eos, p_pa, t_k);
+                    }
+                    let value = quantity.compute(&gas, eos, p_pa, t_k)?;
+                    write_json_line(
+                        out,
+                        &cond,
+                        p,
+                        t,
+                        label,
+                        eoses.len() > 1,
+                        quantity,
+                        &output.format_json_value(value),
+                    )?;
+                    summary.add(value, p, t, label);
+                }
+            }
+        }
+        if matches.is_present("summary") {
+            let multi_eos = eoses.len() > 1;
+            summary.write(quantity, &cond, multi_eos, &mut io::stderr())?;
+        }
+        return Ok(());
+    }
+    // --format table aligns the columns once all the rows are written in a buffer
+    let mut buffer = Vec::new();
+    let (out, table_out): (&mut dyn Write, _) = if bordered {
+        (&mut buffer, Some(out))
+    } else {
+        (out, None)
+    };
+    let (rows, cols) = if transpose {
+        (&temperatures, &pressures)
+    } else {
+        (&pressures, &temperatures)
+    };
+    // header
+    // with several equations, one group of columns per equation
+    let group = |label: &str| {
+        if eoses.len() > 1 {
+            format!("{} ", label)
         } else {
-            None
+            String::new()
         }
+    };
+    if output.bare_header {
+        write!(out, "{}", if transpose { "T \\ P" } else { "P \\ T" })?;
+        for &(label, _) in eoses.iter() {
+            for c in cols.iter() {
+                write!(out, "\t{}{}", group(label), c)?;
+            }
+        }
+    } else {
+        let (p_header, t_header) = (cond.pressure_header(), cond.temperature_header());
+        if transpose {
+            write!(out, "{} \\ {}", t_header, p_header)?;
+        } else {
+            write!(out, "{} \\ {}", p_header, t_header)?;
+        }
+        for &(label, _) in eoses.iter() {
+            for &c in cols.iter() {
+                let c = if transpose {
+                    cond.pressure_display(c)
+                } else {
+                    cond.temperature_display(c)
+                };
+                write!(out, "\t{}{}", group(label), c)?;
+            }
+        }
+    }
+    writeln!(out)?;
+    out.flush()?;
+    let mut summary = Summary::default();
+    // rows
+    for &r in rows.iter() {
+        write!(out, "{}", r)?;
+        for &(label, eos) in eoses.iter() {
+            for &c in cols.iter() {
+                let (p, t) = if transpose { (c, r) } else { (r, c) };
+                let (p_pa, t_k) = (cond.pressure_pa(p), cond.temperature_k(t));
+                if output.debug_roots {
+                    print_debug_roots(&gas, eos, p_pa, t_k);
+                }
+                let value = quantity.compute(&gas, eos, p_pa, t_k)?;
+                write!(out, "\t{}", output.format_quantity(quantity, value))?;
+                summary.add(value, p, t, label);
+            }
+        }
+        writeln!(out)?;
+        out.flush()?;
+    }
+    if let Some(table_out) = table_out {
+        write_bordered_table(table_out, &String::from_utf8_lossy(&buffer))?;
+    }
+    if matches.is_present("summary") {
+        let multi_eos = eoses.len() > 1;
+        summary.write(quantity, &cond, multi_eos, &mut io::stderr())?;
+    }
+    Ok(())
+}
+
+/// Writes the tab separated `table` with aligned columns and a light border,
+/// the first line being the header.
+/// The columns of numbers are aligned to the right, the others to the left.
+fn write_bordered_table(out: &mut dyn Write, table: &str) -> Result<(), Error> {
+    // the color escape sequences take no room on the terminal
+    let visible = |cell: &str| {
+        let mut s = String::new();
+        let mut chars = cell.chars();
+        while let Some(c) = chars.next() {
+            if c == '\x1b' {
+                chars.by_ref().find(|&c| c == 'm');
+            } else {
+                s.push(c);
+            }
+        }
+        s
+    };
+    let rows: Vec<Vec<&str>> = table.lines().map(|l| l.split('\t').collect()).collect();
+    let num_cols = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+    let mut widths = vec![0; num_cols];
+    let mut numeric = vec![true; num_cols];
+    for (i, row) in rows.iter().enumerate() {
+        for (j, cell) in row.iter().enumerate() {
+            let cell = visible(cell);
+            widths[j] = widths[j].max(cell.chars().count());
+            if i > 0 && cell.parse::<f64>().is_err() {
+                numeric[j] = false;
+            }
+        }
+    }
+
+    let border = |out: &mut dyn Write, left, mid, right| -> io::Result<()> {
+        let lines: Vec<String> = widths.iter().map(|w| "─".repeat(w + 2)).collect();
+        writeln!(out, "{}{}{}", left, lines.join(mid), right)
+    };
+    border(out, "┌", "┬", "┐")?;
+    for (i, row) in rows.iter().enumerate() {
+        for (j, &width) in widths.iter().enumerate() {
+            let cell = row.get(j).copied().unwrap_or("");
+            let pad = " ".repeat(width - visible(cell).chars().count());
+            if numeric[j] {
+                write!(out, "│ {}{} ", pad, cell)?;
+            } else {
+                write!(out, "│ {}{} ", cell, pad)?;
+            }
+        }
+        writeln!(out, "│")?;
+        if i == 0 {
+            border(out, "├", "┼", "┤")?;
+        }
+    }
+    border(out, "└", "┴", "┘")?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Writes the object of one condition of --format jsonl and flushes it,
+/// with the pressure and temperature as entered by the user
+#[allow(clippy::too_many_arguments)]
+fn write_json_line(
+    out: &mut dyn Write,
+    cond: &Conditions,
+    p: f64,
+    t: f64,
+    label: &str,
+    multi_eos: bool,
+    quantity: Quantity,
+    value: &str,
+) -> Result<(), Error> {
+    let (p_unit, t_unit) = match cond.reduced {
+        Some(_) => ("reduced".to_string(), "reduced"),
+        None => (cond.pressure_unit(), cond.t_unit.symbol()),
+    };
+    write!(
+        out,
+        "{{\"p\":{},\"p_unit\":{},\"t\":{},\"t_unit\":{},",
+        p,
+        json_string(&p_unit),
+        t,
+        json_string(t_unit)
+    )?;
+    if multi_eos {
+        write!(out, "\"eos\":{},", json_string(label))?;
+    }
+    writeln!(out, "{}:{}}}", json_string(quantity.key()), value)?;
+    out.flush()?;
+    Ok(())
+}
+
+fn parse_opts_from_matches(matches: &ArgMatches) -> Result<ParseOpts, Error> {
+    Ok(ParseOpts {
+        allow_trace: matches.is_present("allow-trace"),
+        drop_zero: matches.is_present("drop-zero"),
+        b_rule: match matches.value_of("b-rule") {
+            Some("lorentz") => BRule::Lorentz,
+            _ => BRule::Linear,
+        },
+        sort_components: matches.is_present("sort-components"),
+        fraction_precision: match matches.value_of("fraction-precision") {
+            Some(decimals) => match decimals.parse() {
+                Ok(decimals) if decimals <= MAX_FRACTION_DECIMALS => Some(decimals),
+                _ => {
+                    return Err(Error::Usage(format!(
+                        "Can't parse \"{}\" as a number of decimals (from 0 to {})",
+                        decimals, MAX_FRACTION_DECIMALS
+                    )))
+                }
+            },
+            None => None,
+        },
+        gas_db: gas_db_from_matches(matches)?,
+    })
+}
+
+/// Gases of --gas-db, preceded by the gases of which --set-w overrides the acentric factor
+fn gas_db_from_matches(matches: &ArgMatches) -> Result<Vec<PureGas>, Error> {
+    let mut gas_db = match matches.value_of("gas-db") {
+        Some(path) => read_gas_db(path)?,
+        None => Vec::new(),
+    };
+    let mut overridden = Vec::new();
+    for arg in matches.values_of("set-w").into_iter().flatten() {
+        let (id, w) = arg.split_once('=').ok_or_else(|| {
+            Error::Usage(format!("--set-w expects <id>=<value> (got \"{}\")", arg))
+        })?;
+        let w = util::parse_num(w.trim()).map_err(Error::Usage)?;
+        let gas = find_gas_in(&gas_db, id.trim())
+            .or_else(|| find_gas(id.trim()))
+            .ok_or_else(|| Error::Parse(format!("{}: the requested gas is not referenced", id)))?;
+        overridden.push(gas.with_acentric_factor(w).map_err(Error::Usage)?);
+    }
+    overridden.append(&mut gas_db);
+    Ok(overridden)
+}
+
+fn eos_from_matches(matches: &ArgMatches) -> Result<Eos, String> {
+    let eos = match matches.value_of("equation") {
+        Some(eos) => Eos::try_from(eos)?,
+        None => Eos::RedlichKwong,
+    };
+    volume_shift(eos, matches.value_of("volume-shift"))
+}
+
+/// Equations of state selected with --eos, along with their id as entered by the user
+fn eoses_from_matches<'a>(matches: &'a ArgMatches) -> Result<Vec<(&'a str, Eos)>, String> {
+    let shift = matches.value_of("volume-shift");
+    let mut eoses = Vec::new();
+    for label in matches.values_of("equation").unwrap() {
+        eoses.push((label, volume_shift(Eos::try_from(label)?, shift)?));
+    }
+    Ok(eoses)
+}
+
+/// Translated variant of `eos` selected with --volume-shift
+fn volume_shift(eos: Eos, shift: Option<&str>) -> Result<Eos, String> {
+    match (shift, eos) {
+        (None, _) => Ok(eos),
+        (Some("constant"), Eos::SoaveRedlichKwong) => Ok(Eos::SrkPeneloux),
+        (Some("constant"), Eos::PengRobinson) => Ok(Eos::PrPeneloux),
+        (Some("temperature-dependent"), Eos::PengRobinson) => Ok(Eos::PrPenelouxT),
+        (Some(shift), _) => Err(format!(
+            "No {} volume translation for {}",
+            shift,
+            eos.name()
+        )),
+    }
+}
+
+/// Parses a reference state "T,P" in °C and bar to (t_ref, p_ref)
+fn parse_reference(input: &str) -> Result<(Kelvin, Pascal), String> {
+    let tp: Vec<&str> = input.split(',').collect();
+    if tp.len() != 2 {
+        return Err(format!(
+            "Can't parse \"{}\" as a reference state (expected T,P)",
+            input
+        ));
+    }
+    let t_ref = Kelvin::from(Celsius(util::parse_num(tp[0])?));
+    let p_ref = Pascal::from(Bar(util::parse_num(tp[1])?));
+    Ok((t_ref, p_ref))
+}
+
+fn solver_config_from_matches(matches: &ArgMatches) -> Result<SolverConfig, String> {
+    let mut config = SolverConfig::default();
+    if let Some(max_iter) = matches.value_of("max-iter") {
+        config.max_iter = max_iter
+            .parse()
+            .map_err(|_| format!("Can't parse \"{}\" as a number of iterations", max_iter))?;
+    }
+    if let Some(tol) = matches.value_of("tol") {
+        config.tol = util::parse_num(tol)?;
+        if config.tol <= 0f64 {
+            return Err("Solver tolerance must be positive".into());
+        }
+    }
+    Ok(config)
+}
+
+/// Spec of -g, read from the file at path when given as @path
+fn gas_spec<'a>(matches: &'a ArgMatches) -> Result<Option<Cow<'a, str>>, Error> {
+    match matches.value_of("gas") {
+        Some(arg) => match arg.strip_prefix('@') {
+            Some(path) => {
+                let spec = std::fs::read_to_string(path)
+                    .map_err(|err| Error::Parse(format!("{}: {}", path, err)))?;
+                let spec = spec.trim();
+                if spec.is_empty() {
+                    return Err(Error::Parse(format!("{}: no gas spec", path)));
+                }
+                Ok(Some(Cow::Owned(spec.to_string())))
+            }
+            None => Ok(Some(Cow::Borrowed(arg))),
+        },
+        None => Ok(None),
+    }
+}
+
+/// Parses the gas spec with the options of the command line (e.g. --gas-db and --set-w)
+fn parse_gas(spec: &str, matches: &ArgMatches) -> Result<Gas, Error> {
+    Gas::from_string_with(spec, &parse_opts_from_matches(matches)?).map_err(Error::Parse)
+}
+
+/// Reads the CSV database of pure gases at `path`
+fn read_gas_db(path: &str) -> Result<Vec<PureGas>, Error> {
+    let input =
+        std::fs::read_to_string(path).map_err(|err| Error::Parse(format!("{}: {}", path, err)))?;
+    gasdb::parse_gas_db(&input).map_err(|err| Error::Parse(format!("{}: {}", path, err)))
+}
+
+/// Writes the result of each case of the file at `path`, labeled by case name
+fn process_cases(path: &str, matches: &ArgMatches, out: &mut dyn Write) -> Result<(), Error> {
+    let input =
+        std::fs::read_to_string(path).map_err(|err| Error::Parse(format!("{}: {}", path, err)))?;
+    let cases = cases::parse_cases(&input, &parse_opts_from_matches(matches)?)
+        .map_err(|err| Error::Parse(format!("{}: {}", path, err)))?;
+    let output = OutputOpts::from_matches(matches).map_err(Error::Usage)?;
+    for case in cases.iter() {
+        if matches.is_present("warn") {
+            print_warnings(&case.gas, case.eos);
+        }
+        if matches!(case.quantity, Quantity::Viscosity) {
+            for w in viscosity::lge_warnings((case.p, case.p), (case.t, case.t)) {
+                eprintln!("warning: {}: {}", case.name, w);
+            }
+        }
+        let value = case
+            .quantity
+            .compute(&case.gas, case.eos, case.p, case.t)
+            .map_err(|err| Error::Compute(format!("{}: {}", case.name, err)))?;
+        writeln!(out, "{}\t{}", case.name, output.format_value(value))?;
+        out.flush()?;
+    }
+    Ok(())
+}
+
+/// Event of the --watch loop
+#[cfg(feature = "watch")]
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum WatchEvent {
+    /// The watched file was modified
+    Changed,
+    /// Ctrl-C was pressed
+    Interrupted,
+}
+
+/// Writes the results of the cases file or of the gas file, then again each time
+/// the file is saved, until Ctrl-C.
+/// The errors of a recomputation are printed and don't stop the watch, as the file may be
+/// saved in the middle of an edit.
+#[cfg(feature = "watch")]
+fn process_watch(matches: &ArgMatches, format: ErrorFormat) -> Result<(), Error> {
+    let cases = matches.value_of("cases");
+    let path = match cases {
+        Some(path) => path,
+        // checked by check_flags
+        None => matches.value_of("gas").unwrap().trim_start_matches('@'),
+    };
+    let compute = || {
+        let res = match cases {
+            Some(path) => process_cases(path, matches, &mut stdout_writer()),
+            None => process_args(matches, &mut stdout_writer()),
+        };
+        if let Err(err) = res {
+            print_error(&err, format);
+        }
+    };
+
+    let (tx, rx) = mpsc::channel();
+    let interrupt = tx.clone();
+    ctrlc::set_handler(move || {
+        let _ = interrupt.send(WatchEvent::Interrupted);
+    })
+    .map_err(|err| Error::Io(io::Error::other(err)))?;
+
+    compute();
+    watch_file(Path::new(path), tx, rx, compute)
+}
+
+/// Calls `on_change` each time the file at `path` is modified, until `rx` receives
+/// `WatchEvent::Interrupted`. The modifications are sent to `tx`.
+#[cfg(feature = "watch")]
+fn watch_file(
+    path: &Path,
+    tx: Sender<WatchEvent>,
+    rx: Receiver<WatchEvent>,
+    mut on_change: impl FnMut(),
+) -> Result<(), Error> {
+    use notify::{RecursiveMode, Watcher};
+
+    let watch_error = |err: notify::Error| Error::Io(io::Error::other(err));
+    let name = path.file_name().map(|n| n.to_os_string());
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        let changed = res.is_ok_and(|event| {
+            (event.kind.is_modify() || event.kind.is_create())
+                && event.paths.iter().any(|p| p.file_name() == name.as_deref())
+        });
+        if changed {
+            let _ = tx.send(WatchEvent::Changed);
+        }
+    })
+    .map_err(watch_error)?;
+    // editors often save by replacing the file, which a watch of the file itself would miss
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    watcher
+        .watch(dir, RecursiveMode::NonRecursive)
+        .map_err(watch_error)?;
+
+    while let Ok(event) = rx.recv() {
+        if event == WatchEvent::Interrupted {
+            break;
+        }
+        // a save comes as several events: recompute once they are all in
+        std::thread::sleep(Duration::from_millis(50));
+        if rx.try_iter().any(|e| e == WatchEvent::Interrupted) {
+            break;
+        }
+        on_change();
+    }
+    Ok(())
+}
+
+/// Writes the deviation of the equation of state from each metering reference point
+fn process_metering_check(matches: &ArgMatches, out: &mut dyn Write) -> Result<(), Error> {
+    let eos = eos_from_matches(matches).map_err(Error::Usage)?;
+    let points = check::metering_check(eos).map_err(Error::Compute)?;
+    writeln!(out, "Gas\tP[bar]\tT[°C]\tZ_ref\tZ\tdeviation[%]")?;
+    for pt in points.iter() {
+        writeln!(
+            out,
+            "{}\t{}\t{}\t{}\t{:.5}\t{:+.3}",
+            pt.gas,
+            pt.p,
+            pt.t,
+            pt.z_ref,
+            pt.z,
+            pt.deviation()
+        )?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Writes the RMS deviation of Z from the measured points of --compare-eos-to-data
+/// with every equation of state, then the equation of lowest deviation
+fn process_data_fit(matches: &ArgMatches, out: &mut dyn Write) -> Result<(), Error> {
+    let gas = parse_gas(gas_spec(matches)?.as_deref().unwrap(), matches)?;
+    let path = matches.value_of("compare-eos-to-data").unwrap();
+    let input =
+        std::fs::read_to_string(path).map_err(|err| Error::Parse(format!("{}: {}", path, err)))?;
+    let points = check::parse_data_points(&input)
+        .map_err(|err| Error::Parse(format!("{}: {}", path, err)))?;
+    let output = OutputOpts::from_matches(matches).map_err(Error::Usage)?;
+    let rms = check::rms_deviations(&gas, &points).map_err(Error::Compute)?;
+    writeln!(out, "EOS\tRMS(Z)")?;
+    let mut best: Option<(Eos, f64)> = None;
+    for &(eos, dev) in rms.iter() {
+        writeln!(out, "{}\t{}", eos.as_abbrev(), output.format_value(dev))?;
+        if best.is_none_or(|(_, b)| dev < b) {
+            best = Some((eos, dev));
+        }
+    }
+    if let Some((eos, _)) = best {
+        writeln!(
+            out,
+            "best fit: {} ({} points)",
+            eos.as_abbrev(),
+            points.len()
+        )?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Computes the Z-factor of a --batch line "gas P T", in bar and °C,
+/// or gets it from `cache`
+fn batch_z(
+    line: &str,
+    eos: Eos,
+    opts: &ParseOpts,
+    cache: Option<&mut ZCache>,
+) -> Result<f64, String> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() != 3 {
+        return Err(format!(
+            "expected a gas, a pressure and a temperature (got \"{}\")",
+            line
+        ));
+    }
+    let gas =
+        Gas::from_string_with(fields[0], opts).map_err(|err| format!("{}: {}", fields[0], err))?;
+    let p = Pascal::from(Bar(util::parse_num(fields[1])?));
+    let t = Kelvin::from(Celsius(util::parse_num(fields[2])?));
+    match cache {
+        Some(cache) => cache.z(&gas, eos, p, t),
+        None => gas.z(eos, p, t),
+    }
+    .map_err(|err| err.to_string())
+}
+
+/// Writes the Z-factor of every line of `input`, or the error of the line at its position
+fn process_batch(
+    matches: &ArgMatches,
+    input: &mut dyn BufRead,
+    out: &mut dyn Write,
+) -> Result<(), Error> {
+    let eos = eos_from_matches(matches).map_err(Error::Usage)?;
+    let output = OutputOpts::from_matches(matches).map_err(Error::Usage)?;
+    let parse_opts = parse_opts_from_matches(matches)?;
+    let format = |num: usize, res: Result<f64, String>| match res {
+        Ok(z) => output.format_value(z),
+        Err(err) => format!("error: line {}: {}", num, err),
+    };
+    let mut cache = match matches.value_of("cache") {
+        Some(capacity) => {
+            let capacity = capacity.parse::<usize>().map_err(|_| {
+                Error::Usage(format!("Can't parse \"{}\" as a cache capacity", capacity))
+            })?;
+            Some(ZCache::new(capacity).map_err(Error::Usage)?)
+        }
+        None => None,
+    };
+
+    #[cfg(feature = "rayon")]
+    {
+        if let Some(threads) = matches.value_of("threads") {
+            let threads = match threads.parse::<usize>() {
+                Ok(threads) if threads > 0 => threads,
+                _ => {
+                    return Err(Error::Usage(format!(
+                        "Can't parse \"{}\" as a number of threads",
+                        threads
+                    )))
+                }
+            };
+            let mut text = String::new();
+            input.read_to_string(&mut text)?;
+            let lines: Vec<(usize, &str)> = util::content_lines(&text).collect();
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .map_err(|err| Error::Compute(err.to_string()))?;
+            let results: Vec<Result<f64, String>> = pool.install(|| {
+                lines
+                    .par_iter()
+                    .map(|&(_, line)| batch_z(line, eos, &parse_opts, None))
+                    .collect()
+            });
+            for (&(num, _), res) in lines.iter().zip(results) {
+                writeln!(out, "{}", format(num, res))?;
+            }
+            out.flush()?;
+            return Ok(());
+        }
+    }
+
+    for (i, line) in input.lines().enumerate() {
+        let line = line?;
+        if let Some(line) = util::line_content(&line) {
+            let z = batch_z(line, eos, &parse_opts, cache.as_mut());
+            writeln!(out, "{}", format(i + 1, z))?;
+            out.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// Number of Z computations timed per equation of state by --benchmark-eos
+const BENCHMARK_CALLS: u32 = 20_000;
+
+/// Writes the mean time of a Z computation with each equation of state,
+/// over pressures from 1 to 200 bar at 300 K
+fn process_benchmark(matches: &ArgMatches, out: &mut dyn Write) -> Result<(), Error> {
+    let gas = parse_gas(gas_spec(matches)?.as_deref().unwrap_or("air"), matches)?;
+    let ps: Vec<f64> = (0..BENCHMARK_CALLS)
+        .map(|i| 1e5 + 199e5 * f64::from(i) / f64::from(BENCHMARK_CALLS))
+        .collect();
+    for &eos in Eos::all() {
+        let start = Instant::now();
+        for &p in ps.iter() {
+            hint::black_box(gas.z(eos, Pascal(hint::black_box(p)), Kelvin(300f64))?);
+        }
+        let ns = start.elapsed().as_nanos() as f64 / f64::from(BENCHMARK_CALLS);
+        writeln!(out, "{}\t{:.0} ns/call", eos.as_abbrev(), ns)?;
+        out.flush()?;
+    }
+    Ok(())
+}
+
+/// Default reduced pressures of --chart
+const CHART_PR: &str = "0:10:21n";
+/// Default reduced temperatures of --chart
+const CHART_TR: &str = "1:2:11n";
+
+/// Writes a generalized compressibility chart of the gas or of a generic fluid:
+/// Z with one row per reduced pressure and one column per reduced temperature
+fn process_chart(matches: &ArgMatches, out: &mut dyn Write) -> Result<(), Error> {
+    let eos = eos_from_matches(matches).map_err(Error::Usage)?;
+    let output = OutputOpts::from_matches(matches).map_err(Error::Usage)?;
+    let gas = match (gas_spec(matches)?.as_deref(), matches.value_of("acentric")) {
+        (Some(spec), _) => parse_gas(spec, matches)?,
+        (None, Some(w)) => Gas::Pure(PureGas::generic(util::parse_num(w).map_err(Error::Usage)?)),
+        (None, None) => return Err(Error::Usage(
+            "--chart requires a gas (-g) or the acentric factor of a generic fluid (--acentric)"
+                .into(),
+        )),
+    };
+    let prs: Vec<f64> = Range::try_from(matches.value_of("pressure").unwrap_or(CHART_PR))?
+        .iter()
+        .collect();
+    let trs: Vec<f64> = Range::try_from(matches.value_of("temperature").unwrap_or(CHART_TR))?
+        .iter()
+        .collect();
+    if prs.iter().any(|&pr| pr < 0f64) {
+        return Err(Error::Usage(
+            "Reduced pressures of the chart must not be negative".into(),
+        ));
+    }
+    if trs.iter().any(|&tr| tr <= 0f64) {
+        return Err(Error::Usage(
+            "Reduced temperatures of the chart must be positive".into(),
+        ));
+    }
+
+    if matches.is_present("warn") {
+        print_warnings(&gas, eos);
+    }
+
+    let (tc, pc) = gas.criticals();
+    let ps: Vec<Pascal> = prs.iter().map(|pr| Pascal(pr * pc)).collect();
+    let ts: Vec<Kelvin> = trs.iter().map(|tr| Kelvin(tr * tc)).collect();
+    let grid = z_grid(&gas, eos, &ps, &ts)?;
+
+    write!(out, "Pr \\ Tr")?;
+    for tr in trs.iter() {
+        write!(out, "\t{}", tr)?;
+    }
+    writeln!(out)?;
+    for (i, pr) in prs.iter().enumerate() {
+        write!(out, "{}", pr)?;
+        for z in grid.row(i).iter() {
+            write!(out, "\t{}", output.format_value(*z))?;
+        }
+        writeln!(out)?;
+        out.flush()?;
+    }
+    Ok(())
+}
+
+/// Writes the Boyle temperature of a pure gas
+fn process_boyle(matches: &ArgMatches, out: &mut dyn Write) -> Result<(), Error> {
+    let eos = eos_from_matches(matches).map_err(Error::Usage)?;
+    let output = OutputOpts::from_matches(matches).map_err(Error::Usage)?;
+    let gas = match parse_gas(gas_spec(matches)?.as_deref().unwrap(), matches)? {
+        Gas::Pure(gas) => gas,
+        Gas::Mixture(_) => {
+            return Err(Error::Usage("Boyle temperature requires a pure gas".into()))
+        }
+    };
+    let t = gas.boyle_temperature(eos).ok_or_else(|| {
+        Error::Compute(format!(
+            "{}: no Boyle temperature with {} between Tc and 100 Tc",
+            gas.id,
+            eos.name()
+        ))
+    })?;
+    writeln!(out, "{}", output.format_value(Celsius::from(t).0))?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Writes the second virial coefficient of the gas over the temperature range
+fn process_virial(matches: &ArgMatches, out: &mut dyn Write) -> Result<(), Error> {
+    let temperature = Range::try_from(matches.value_of("temperature").unwrap())?;
+    let eos = eos_from_matches(matches).map_err(Error::Usage)?;
+    let output = OutputOpts::from_matches(matches).map_err(Error::Usage)?;
+    let gas = parse_gas(gas_spec(matches)?.as_deref().unwrap(), matches)?;
+    let t_unit = TemperatureUnit::from_matches(matches);
+
+    if matches.is_present("warn") {
+        print_warnings(&gas, eos);
+    }
+    gas.check_constants()?;
+
+    let virial_b = |t: f64| -> Result<f64, Error> {
+        let t_k = t_unit.to_kelvin(t);
+        if t_k <= Kelvin(0f64) {
+            return Err(Error::Usage(format!(
+                "{} is not a positive temperature",
+                t_unit.display(t)
+            )));
+        }
+        Ok(gas.second_virial_coeff(eos, t_k))
+    };
+
+    if temperature.is_scalar() {
+        writeln!(out, "{}", output.format_value(virial_b(temperature.start)?))?;
+    } else {
+        if output.bare_header {
+            writeln!(out, "T\tB")?;
+        } else {
+            writeln!(out, "T[{}]\tB[m3/mol]", t_unit.symbol())?;
+        }
+        for t in temperature.iter() {
+            writeln!(out, "{}\t{}", t, output.format_value(virial_b(t)?))?;
+            out.flush()?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Writes the pressure and the Z-factor of the gas at the density of --density-input
+/// over the temperature range
+fn process_density(matches: &ArgMatches, out: &mut dyn Write) -> Result<(), Error> {
+    let temperature = Range::try_from(matches.value_of("temperature").unwrap())?;
+    let density =
+        util::parse_num(matches.value_of("density-input").unwrap()).map_err(Error::Usage)?;
+    if density <= 0f64 {
+        return Err(Error::Usage("The density must be positive".into()));
+    }
+    let eos = eos_from_matches(matches).map_err(Error::Usage)?;
+    let output = OutputOpts::from_matches(matches).map_err(Error::Usage)?;
+    let gas = parse_gas(gas_spec(matches)?.as_deref().unwrap(), matches)?;
+    let t_unit = TemperatureUnit::from_matches(matches);
+
+    if matches.is_present("warn") {
+        print_warnings(&gas, eos);
+    }
+
+    let p_z = |t: f64| -> Result<String, Error> {
+        let t_k = t_unit.to_kelvin(t);
+        if t_k <= Kelvin(0f64) {
+            return Err(Error::Usage(format!(
+                "{} is not a positive temperature",
+                t_unit.display(t)
+            )));
+        }
+        let p = gas.pressure_from_density(eos, density, t_k)?;
+        if p <= Pascal(0f64) {
+            return Err(Error::Compute(format!(
+                "negative pressure at {}: the density is in the two-phase region",
+                t_unit.display(t)
+            )));
+        }
+        let z = p.0 * gas.molar_mass().unwrap() / (density * R * t_k.0);
+        Ok(format!(
+            "{}\t{}",
+            output.format_value(Bar::from(p).0),
+            output.format_value(z)
+        ))
+    };
+
+    if temperature.is_scalar() {
+        writeln!(out, "{}", p_z(temperature.start)?)?;
+    } else {
+        if output.bare_header {
+            writeln!(out, "T\tP\tZ")?;
+        } else {
+            writeln!(out, "T[{}]\tP[bar]\tZ", t_unit.symbol())?;
+        }
+        for t in temperature.iter() {
+            writeln!(out, "{}\t{}", t, p_z(t)?)?;
+            out.flush()?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Writes the saturation pressure of a pure gas over the temperature range,
+/// along with the Z-factors of both phases with --saturation,
+/// or the enthalpy of vaporization with --hvap
+fn process_saturation(matches: &ArgMatches, out: &mut dyn Write) -> Result<(), Error> {
+    let temperature = Range::try_from(matches.value_of("temperature").unwrap())?;
+    let eos = eos_from_matches(matches).map_err(Error::Usage)?;
+    let config = solver_config_from_matches(matches).map_err(Error::Usage)?;
+    let output = OutputOpts::from_matches(matches).map_err(Error::Usage)?;
+    let gas = parse_gas(gas_spec(matches)?.as_deref().unwrap(), matches)?;
+    let t_unit = TemperatureUnit::from_matches(matches);
+    let t_header = format!("T[{}]", t_unit.symbol());
+
+    if matches.is_present("warn") {
+        print_warnings(&gas, eos);
+    }
+
+    let gas = match gas {
+        Gas::Pure(gas) => gas,
+        Gas::Mixture(_) => {
+            return Err(Error::Usage(
+                "Saturation pressure requires a pure gas".into(),
+            ))
+        }
+    };
+
+    if matches.is_present("hvap") {
+        let hvap = |t: f64| {
+            gas.enthalpy_of_vaporization(eos, t_unit.to_kelvin(t), &config)
+                .map_err(|err| Error::Compute(format!("{}: {}", t_unit.display(t), err)))
+        };
+        let above_tc = |t: f64| {
+            format!(
+                "{} is above the critical temperature of {} ({})",
+                t_unit.display(t),
+                gas.id,
+                t_unit.display(t_unit.kelvin_to_unit(Kelvin(gas.tc)))
+            )
+        };
+        if temperature.is_scalar() {
+            let t = temperature.start;
+            let hvap = hvap(t)?.ok_or_else(|| Error::Compute(above_tc(t)))?;
+            writeln!(out, "{}", output.format_value(hvap))?;
+        } else {
+            if output.bare_header {
+                writeln!(out, "T\tHvap")?;
+            } else {
+                writeln!(out, "{}\tHvap[J/mol]", t_header)?;
+            }
+            for t in temperature.iter() {
+                match hvap(t)? {
+                    Some(hvap) => writeln!(out, "{}\t{}", t, output.format_value(hvap))?,
+                    None => eprintln!("warning: skipped {}", above_tc(t)),
+                }
+                out.flush()?;
+            }
+        }
+    } else if matches.is_present("sat-vapor-density") {
+        let molar_mass = gas
+            .molar_mass
+            .ok_or_else(|| Error::Compute(format!("{}: the molar mass is unknown", gas.id)))?;
+        let density = |t: f64| {
+            gas.saturation(eos, t_unit.to_kelvin(t), &config)
+                .map(|sat| sat.vapor_density(molar_mass))
+                .map_err(|err| Error::Compute(format!("{}: {}", t_unit.display(t), err)))
+        };
+        if temperature.is_scalar() {
+            writeln!(out, "{}", output.format_value(density(temperature.start)?))?;
+        } else {
+            if output.bare_header {
+                writeln!(out, "T\trho_vapor")?;
+            } else {
+                writeln!(out, "{}\trho_vapor[kg/m3]", t_header)?;
+            }
+            for t in temperature.iter() {
+                writeln!(out, "{}\t{}", t, output.format_value(density(t)?))?;
+                out.flush()?;
+            }
+        }
+    } else {
+        if output.bare_header {
+            writeln!(out, "T\tPsat\tZ_liquid\tZ_vapor")?;
+        } else {
+            writeln!(out, "{}\tPsat[bar]\tZ_liquid\tZ_vapor", t_header)?;
+        }
+        for t in temperature.iter() {
+            let sat = gas.saturation(eos, t_unit.to_kelvin(t), &config);
+            let sat =
+                sat.map_err(|err| Error::Compute(format!("{}: {}", t_unit.display(t), err)))?;
+            writeln!(
+                out,
+                "{}\t{}\t{}\t{}",
+                t,
+                output.format_value(Bar::from(Pascal(sat.p)).0),
+                output.format_value(sat.z_liquid),
+                output.format_value(sat.z_vapor)
+            )?;
+            out.flush()?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Quantity computed for each condition of pressure and temperature
+#[derive(Copy, Clone, Debug)]
+enum Quantity {
+    /// Compression factor
+    Z,
+    /// Heat capacity ratio
+    Gamma,
+    /// Enthalpy relative to the reference state (t_ref, p_ref)
+    Enthalpy((Kelvin, Pascal)),
+    /// Entropy relative to the reference state (t_ref, p_ref)
+    Entropy((Kelvin, Pascal)),
+    /// Number of real roots of the cubic equation in Z
+    RootCount,
+    /// Compression factor, taken as exactly 1 without solving the cubic
+    /// below the given reduced pressure
+    ZIdealBelow(f64),
+    /// Compression factor of the root selected by `--root` or `--phase`
+    ZRoot(RootStrategy),
+    /// Density of the liquid root in kg/m3
+    LiquidDensity,
+    /// Viscosity in µPa.s
+    Viscosity,
+}
+
+impl Quantity {
+    fn from_matches(matches: &ArgMatches) -> Result<Quantity, String> {
+        if matches.is_present("gamma") {
+            Ok(Quantity::Gamma)
+        } else if matches.is_present("root-count") {
+            Ok(Quantity::RootCount)
+        } else if matches.is_present("liquid-density") {
+            Ok(Quantity::LiquidDensity)
+        } else if matches.is_present("viscosity") {
+            Ok(Quantity::Viscosity)
+        } else if matches.is_present("enthalpy") || matches.is_present("entropy") {
+            let reference = parse_reference(matches.value_of("reference").unwrap())?;
+            if matches.is_present("enthalpy") {
+                Ok(Quantity::Enthalpy(reference))
+            } else {
+                Ok(Quantity::Entropy(reference))
+            }
+        } else if let Some(pr) = matches.value_of("assume-ideal-below") {
+            let pr = util::parse_num(pr)?;
+            if pr <= 0f64 {
+                return Err("Reduced pressure of --assume-ideal-below must be positive".into());
+            }
+            Ok(Quantity::ZIdealBelow(pr))
+        } else if let Some(root) = matches.value_of("root") {
+            Ok(Quantity::ZRoot(RootStrategy::Magnitude(root.parse()?)))
+        } else if let Some(phase) = matches.value_of("phase") {
+            Ok(Quantity::ZRoot(phase.parse()?))
+        } else {
+            Ok(Quantity::Z)
+        }
+    }
+
+    /// Parses a quantity by name ("z", "gamma", "enthalpy", "entropy", "root-count"
+    /// or "viscosity").
+    /// `reference` is the reference state of enthalpy and entropy in K and Pa.
+    fn parse(name: &str, reference: (Kelvin, Pascal)) -> Result<Quantity, String> {
+        match name.to_lowercase().as_str() {
+            "z" => Ok(Quantity::Z),
+            "gamma" => Ok(Quantity::Gamma),
+            "enthalpy" => Ok(Quantity::Enthalpy(reference)),
+            "entropy" => Ok(Quantity::Entropy(reference)),
+            "root-count" => Ok(Quantity::RootCount),
+            "viscosity" => Ok(Quantity::Viscosity),
+            _ => Err(format!("Unknown quantity: {}", name)),
+        }
+    }
+
+    /// Name of the quantity in the JSON output
+    fn key(&self) -> &'static str {
+        match self {
+            Quantity::Z | Quantity::ZIdealBelow(_) | Quantity::ZRoot(_) => "z",
+            Quantity::Gamma => "gamma",
+            Quantity::Enthalpy(_) => "enthalpy",
+            Quantity::Entropy(_) => "entropy",
+            Quantity::RootCount => "root_count",
+            Quantity::LiquidDensity => "liquid_density",
+            Quantity::Viscosity => "viscosity",
+        }
+    }
+
+    /// Symbol of the quantity
+    fn label(&self) -> &'static str {
+        match self {
+            Quantity::Z | Quantity::ZIdealBelow(_) | Quantity::ZRoot(_) => "Z",
+            Quantity::Gamma => "Cp/Cv",
+            Quantity::Enthalpy(_) => "H",
+            Quantity::Entropy(_) => "S",
+            Quantity::RootCount => "roots",
+            Quantity::LiquidDensity => "rho",
+            Quantity::Viscosity => "mu",
+        }
+    }
+
+    /// Unit of the quantity, with a leading space
+    fn unit(&self) -> &'static str {
+        match self {
+            Quantity::Z
+            | Quantity::ZIdealBelow(_)
+            | Quantity::ZRoot(_)
+            | Quantity::Gamma
+            | Quantity::RootCount => "",
+            Quantity::Enthalpy(_) => " J/mol",
+            Quantity::Entropy(_) => " J/(mol.K)",
+            Quantity::LiquidDensity => " kg/m3",
+            Quantity::Viscosity => " µPa.s",
+        }
+    }
+
+    /// Computes the quantity at pressure `p` and temperature `t`
+    fn compute(&self, gas: &Gas, eos: Eos, p: Pascal, t: Kelvin) -> Result<f64, EosError> {
+        match *self {
+            Quantity::Z => gas.z(eos, p, t),
+            Quantity::Gamma => gas.heat_capacity_ratio(eos, p, t),
+            Quantity::Enthalpy(reference) => gas.enthalpy(eos, p, t, reference),
+            Quantity::Entropy(reference) => gas.entropy(eos, p, t, reference),
+            Quantity::RootCount => Ok(gas.z_roots(eos, p, t)?.len() as f64),
+            Quantity::ZIdealBelow(pr) if p.0 < pr * gas.criticals().1 => Ok(1f64),
+            Quantity::ZIdealBelow(_) => gas.z(eos, p, t),
+            Quantity::ZRoot(strategy) => gas.z_with_strategy(eos, p, t, strategy),
+            Quantity::LiquidDensity => gas.liquid_density(eos, p, t),
+            Quantity::Viscosity => Ok(gas.viscosity(eos, p, t)? * 1e6),
+        }
+    }
+}
+
+/// Options of the output formatting
+struct OutputOpts {
+    /// Number of decimals of the values (shortest representation if None)
+    precision: Option<usize>,
+    /// Write the values in exponential notation
+    scientific: bool,
+    /// Write the CSV header without units
+    bare_header: bool,
+    /// Print the roots of the cubic equation to stderr
+    debug_roots: bool,
+    /// Write the conditions along with the scalar value
+    annotate: bool,
+    /// Color the Z values with ANSI escape codes
+    color: bool,
+}
+
+impl OutputOpts {
+    fn from_matches(matches: &ArgMatches) -> Result<OutputOpts, String> {
+        let precision = match matches.value_of("precision") {
+            Some(precision) => Some(
+                precision
+                    .parse()
+                    .map_err(|_| format!("Can't parse \"{}\" as a precision", precision))?,
+            ),
+            None => None,
+        };
+        Ok(OutputOpts {
+            precision,
+            scientific: matches.is_present("scientific"),
+            bare_header: matches.is_present("bare-header"),
+            debug_roots: matches.is_present("debug-roots"),
+            annotate: matches.is_present("annotate"),
+            color: match matches.value_of("color") {
+                Some("always") => true,
+                Some("never") => false,
+                _ => STDOUT_IS_TERMINAL.get().copied().unwrap_or(false),
+            },
+        })
+    }
+
+    /// Formats a computed value of `quantity`, colored if it is a Z-factor and color is enabled
+    fn format_quantity(&self, quantity: Quantity, value: f64) -> String {
+        let s = self.format_value(value);
+        match quantity {
+            Quantity::Z | Quantity::ZIdealBelow(_) | Quantity::ZRoot(_) if self.color => {
+                let deviation = (value - 1f64).abs();
+                let code = if deviation < 0.01 {
+                    32 // green
+                } else if deviation < 0.1 {
+                    33 // yellow
+                } else {
+                    31 // red
+                };
+                format!("\x1b[{}m{}\x1b[0m", code, s)
+            }
+            _ => s,
+        }
+    }
+
+    /// Formats a computed value according to the options
+    fn format_value(&self, value: f64) -> String {
+        match (self.scientific, self.precision) {
+            (false, None) => format!("{}", value),
+            (false, Some(precision)) => format!("{:.*}", precision, value),
+            (true, precision) => {
+                let s = match precision {
+                    Some(precision) => format!("{:.*e}", precision, value),
+                    None => format!("{:e}", value),
+                };
+                // C-like exponent with sign and at least two digits
+                match s.find('e') {
+                    Some(pos) if !value.is_nan() && !value.is_infinite() => {
+                        let (mantissa, exp) = s.split_at(pos);
+                        let exp: i32 = exp[1..].parse().unwrap();
+                        let sign = if exp < 0 { '-' } else { '+' };
+                        format!("{}e{}{:02}", mantissa, sign, exp.abs())
+                    }
+                    _ => s,
+                }
+            }
+        }
+    }
+
+    /// Formats the value as a JSON number, or null if it is not finite
+    /// as JSON has no NaN nor infinity
+    fn format_json_value(&self, value: f64) -> String {
+        if value.is_finite() {
+            self.format_value(value)
+        } else {
+            "null".to_string()
+        }
+    }
+}
+
+/// Unit of the temperatures entered by the user
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum TemperatureUnit {
+    Celsius,
+    Kelvin,
+    Fahrenheit,
+}
+
+impl TemperatureUnit {
+    fn from_matches(matches: &ArgMatches) -> TemperatureUnit {
+        match matches.value_of("temperature-unit") {
+            Some(unit) if unit.eq_ignore_ascii_case("K") => TemperatureUnit::Kelvin,
+            Some(unit) if unit.eq_ignore_ascii_case("F") => TemperatureUnit::Fahrenheit,
+            _ => TemperatureUnit::Celsius,
+        }
+    }
+
+    /// Temperature `t` in this unit
+    fn to_kelvin(self, t: f64) -> Kelvin {
+        match self {
+            TemperatureUnit::Celsius => Kelvin::from(Celsius(t)),
+            TemperatureUnit::Kelvin => Kelvin(t),
+            TemperatureUnit::Fahrenheit => Kelvin::from(Fahrenheit(t)),
+        }
+    }
+
+    /// Temperature in this unit from temperature `t`
+    fn kelvin_to_unit(self, t: Kelvin) -> f64 {
+        match self {
+            TemperatureUnit::Celsius => Celsius::from(t).0,
+            TemperatureUnit::Kelvin => t.0,
+            TemperatureUnit::Fahrenheit => Fahrenheit::from(t).0,
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        match self {
+            TemperatureUnit::Celsius => "°C",
+            TemperatureUnit::Kelvin => "K",
+            TemperatureUnit::Fahrenheit => "°F",
+        }
+    }
+
+    /// Temperature `t` with the unit symbol, e.g. "20°C" or "300 K"
+    fn display(self, t: f64) -> String {
+        match self {
+            TemperatureUnit::Kelvin => format!("{} K", t),
+            _ => format!("{}{}", t, self.symbol()),
+        }
+    }
+}
+
+/// Unit of the pressures entered by the user
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum PressureUnit {
+    Bar,
+    Psi,
+    KPa,
+    MPa,
+    Atm,
+}
+
+impl PressureUnit {
+    fn from_matches(matches: &ArgMatches) -> PressureUnit {
+        let unit = matches.value_of("pressure-unit").unwrap_or("bar");
+        [
+            PressureUnit::Psi,
+            PressureUnit::KPa,
+            PressureUnit::MPa,
+            PressureUnit::Atm,
+        ]
+        .iter()
+        .copied()
+        .find(|u| u.name().eq_ignore_ascii_case(unit))
+        .unwrap_or(PressureUnit::Bar)
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            PressureUnit::Bar => "bar",
+            PressureUnit::Psi => "psi",
+            PressureUnit::KPa => "kPa",
+            PressureUnit::MPa => "MPa",
+            PressureUnit::Atm => "atm",
+        }
+    }
+
+    /// Value of the unit
+    fn pascals(self) -> Pascal {
+        match self {
+            PressureUnit::Bar => Pascal::from(Bar(1f64)),
+            PressureUnit::Psi => Pascal(6894.757293168361),
+            PressureUnit::KPa => Pascal(1000f64),
+            PressureUnit::MPa => Pascal(1000000f64),
+            PressureUnit::Atm => Pascal(101325f64),
+        }
+    }
+
+    /// Pressure `p` in this unit
+    fn to_pa(self, p: f64) -> Pascal {
+        Pascal(p * self.pascals().0)
+    }
+
+    /// Pressure in this unit from pressure `p`
+    fn pa_to_unit(self, p: Pascal) -> f64 {
+        p.0 / self.pascals().0
+    }
+
+    /// Pressure in this unit from pressure `p` in bar
+    fn bar_to_unit(self, p: f64) -> f64 {
+        match self {
+            PressureUnit::Bar => p,
+            _ => self.pa_to_unit(Pascal::from(Bar(p))),
+        }
+    }
+}
+
+/// Conditions of pressure and temperature of a computation
+struct Conditions {
+    /// Temperature as entered by the user
+    temperature: Range,
+    /// Pressure as entered by the user (relative if `relative` is set)
+    pressure: Range,
+    /// Offset in bar of the relative pressures entered by the user
+    relative: Option<f64>,
+    /// Fail on the non-positive absolute pressures of a range instead of skipping them
+    strict: bool,
+    /// Critical temperature in K and pressure in Pa if the conditions are reduced (Tr and Pr)
+    reduced: Option<(f64, f64)>,
+    /// Unit of the temperatures entered by the user
+    t_unit: TemperatureUnit,
+    /// Unit of the pressures entered by the user
+    p_unit: PressureUnit,
+}
+
+impl Conditions {
+    /// Absolute pressure in the unit of the user from pressure `p` as entered by the user
+    fn absolute(&self, p: f64) -> f64 {
+        if let Some(relative) = self.relative {
+            p + self.p_unit.bar_to_unit(relative)
+        } else {
+            p
+        }
+    }
+
+    /// Absolute pressure from pressure `p` as entered by the user
+    fn pressure_pa(&self, p: f64) -> Pascal {
+        match self.reduced {
+            Some((_, pc)) => Pascal(p * pc),
+            None => self.p_unit.to_pa(self.absolute(p)),
+        }
+    }
+
+    /// Temperature from temperature `t` as entered by the user
+    fn temperature_k(&self, t: f64) -> Kelvin {
+        match self.reduced {
+            Some((tc, _)) => Kelvin(t * tc),
+            None => self.t_unit.to_kelvin(t),
+        }
+    }
+
+    /// Error message if pressure `p` as entered by the user is not positive once made absolute
+    fn non_positive(&self, p: f64) -> Option<String> {
+        if self.absolute(p) > 0f64 {
+            None
+        } else {
+            Some(format!(
+                "absolute pressure at {} is not positive",
+                self.pressure_display(p)
+            ))
+        }
+    }
+
+    /// Whether pressure `p` of a range must be skipped because it is not positive once made absolute.
+    /// Fails instead in strict mode.
+    fn skip_pressure(&self, p: f64) -> Result<bool, String> {
+        match self.non_positive(p) {
+            Some(msg) if self.strict => Err(msg),
+            Some(msg) => {
+                eprintln!("warning: skipped {}", msg);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Restricts the ranges to the validity domain of `gas`.
+    /// Returns warnings describing the clipped portions.
+    fn clip_domain(&mut self, gas: &Gas) -> Result<Vec<String>, String> {
+        let mut warnings = Vec::new();
+        if let Some((min, max)) = gas.valid_t() {
+            let unit = self.t_unit;
+            let (min, max) = (
+                unit.kelvin_to_unit(Kelvin(min)),
+                unit.kelvin_to_unit(Kelvin(max)),
+            );
+            let clipped = self.temperature.clip(min, max).ok_or_else(|| {
+                format!(
+                    "temperature range is outside of the validity domain ({} to {})",
+                    min,
+                    unit.display(max)
+                )
+            })?;
+            if clipped != self.temperature {
+                warnings.push(format!(
+                    "temperature range clipped to {}:{} (validity domain is {} to {})",
+                    clipped.start,
+                    unit.display(clipped.stop),
+                    min,
+                    unit.display(max)
+                ));
+                self.temperature = clipped;
+            }
+        }
+        if let Some((min, max)) = gas.valid_p() {
+            let (min, max) = (
+                self.p_unit.pa_to_unit(Pascal(min)),
+                self.p_unit.pa_to_unit(Pascal(max)),
+            );
+            let offset = self.absolute(0f64);
+            let clipped = self
+                .pressure
+                .clip(min - offset, max - offset)
+                .ok_or_else(|| {
+                    format!(
+                        "pressure range is outside of the validity domain ({} to {} {})",
+                        min,
+                        max,
+                        self.p_unit.name()
+                    )
+                })?;
+            if clipped != self.pressure {
+                warnings.push(format!(
+                    "pressure range clipped to {}:{} {} (validity domain is {} to {} {})",
+                    clipped.start,
+                    clipped.stop,
+                    self.pressure_unit(),
+                    min,
+                    max,
+                    self.p_unit.name()
+                ));
+                self.pressure = clipped;
+            }
+        }
+        Ok(warnings)
+    }
+
+    /// Unit of the pressure as written in tables, e.g. "barG" for relative pressures
+    fn pressure_unit(&self) -> String {
+        if self.relative.is_some() {
+            format!("{}G", self.p_unit.name())
+        } else {
+            self.p_unit.name().to_string()
+        }
+    }
+
+    /// Header of the pressure column, e.g. "P[bar]"
+    fn pressure_header(&self) -> String {
+        match self.reduced {
+            Some(_) => "Pr".to_string(),
+            None => format!("P[{}]", self.pressure_unit()),
+        }
+    }
+
+    /// Header of the temperature column, e.g. "T[°C]"
+    fn temperature_header(&self) -> String {
+        match self.reduced {
+            Some(_) => "Tr".to_string(),
+            None => format!("T[{}]", self.t_unit.symbol()),
+        }
+    }
+
+    /// Pressure `p` as entered by the user with its unit, e.g. "200 barG"
+    fn pressure_display(&self, p: f64) -> String {
+        match self.reduced {
+            Some(_) => format!("Pr = {}", p),
+            None => format!("{} {}", p, self.pressure_unit()),
+        }
+    }
+
+    /// Temperature `t` as entered by the user with its unit, e.g. "20°C"
+    fn temperature_display(&self, t: f64) -> String {
+        match self.reduced {
+            Some(_) => format!("Tr = {}", t),
+            None => self.t_unit.display(t),
+        }
+    }
+}
+
+/// Value of a table at given conditions
+struct Extreme<'a> {
+    value: f64,
+    /// Pressure as entered by the user
+    p: f64,
+    /// Temperature as entered by the user
+    t: f64,
+    /// Equation of state as entered by the user
+    eos: &'a str,
+}
+
+/// Statistics of the values of a table, collected row by row
+#[derive(Default)]
+struct Summary<'a> {
+    count: usize,
+    sum: f64,
+    min: Option<Extreme<'a>>,
+    max: Option<Extreme<'a>>,
+}
+
+impl<'a> Summary<'a> {
+    fn add(&mut self, value: f64, p: f64, t: f64, eos: &'a str) {
+        self.count += 1;
+        self.sum += value;
+        if self.min.as_ref().is_none_or(|min| value < min.value) {
+            self.min = Some(Extreme { value, p, t, eos });
+        }
+        if self.max.as_ref().is_none_or(|max| value > max.value) {
+            self.max = Some(Extreme { value, p, t, eos });
+        }
+    }
+
+    fn mean(&self) -> Option<f64> {
+        if self.count > 0 {
+            Some(self.sum / self.count as f64)
+        } else {
+            None
+        }
+    }
+
+    /// Writes the minimum, maximum and mean of the values.
+    /// The extremes are labelled with the equation of state if `multi_eos`.
+    fn write(
+        &self,
+        quantity: Quantity,
+        cond: &Conditions,
+        multi_eos: bool,
+        out: &mut dyn Write,
+    ) -> io::Result<()> {
+        let (min, max, mean) = match (&self.min, &self.max, self.mean()) {
+            (Some(min), Some(max), Some(mean)) => (min, max, mean),
+            _ => return writeln!(out, "summary: no values"),
+        };
+        for (name, ext) in [("min", min), ("max", max)].iter() {
+            write!(
+                out,
+                "{} {} = {}{} at {}, {}",
+                name,
+                quantity.label(),
+                ext.value,
+                quantity.unit(),
+                cond.pressure_display(ext.p),
+                cond.temperature_display(ext.t)
+            )?;
+            if multi_eos {
+                write!(out, " ({})", ext.eos)?;
+            }
+            writeln!(out)?;
+        }
+        writeln!(
+            out,
+            "mean {} = {}{} over {} values",
+            quantity.label(),
+            mean,
+            quantity.unit(),
+            self.count
+        )
+    }
+}
+
+fn print_warnings(gas: &Gas, eos: Eos) {
+    for w in gas.warnings(eos) {
+        eprintln!("warning: {}", w);
+    }
+}
+
+/// Prints the notes about the conditions out of the domain of the viscosity correlation
+fn print_viscosity_warnings(cond: &Conditions) {
+    let p_range = (
+        cond.pressure_pa(cond.pressure.start),
+        cond.pressure_pa(cond.pressure.stop),
+    );
+    let t_range = (
+        cond.temperature_k(cond.temperature.start),
+        cond.temperature_k(cond.temperature.stop),
+    );
+    for w in viscosity::lge_warnings(p_range, t_range) {
+        eprintln!("warning: {}", w);
+    }
+}
+
+/// Prints the roots of the cubic equation at `p` and `t` to stderr
+fn print_debug_roots(gas: &Gas, eos: Eos, p: Pascal, t: Kelvin) {
+    eprintln!("debug: roots at {} Pa and {} K", p.0, t.0);
+    for line in gas.debug_roots(eos, p, t) {
+        eprintln!("debug:   {}", line);
+    }
+}
+
+/// Writes a summary of what would be computed, without solving the equation of state
+fn write_dry_run(
+    gases: &[(&str, Gas)],
+    eoses: &[(&str, Eos)],
+    quantity: Quantity,
+    cond: &Conditions,
+    out: &mut dyn Write,
+) -> Result<(), Error> {
+    let num_p = cond.pressure.iter().count();
+    let num_t = cond.temperature.iter().count();
+    for &(_, eos) in eoses.iter() {
+        writeln!(out, "equation: {}", eos.name())?;
+    }
+    writeln!(out, "quantity: {}", quantity.label())?;
+    for (spec, gas) in gases.iter() {
+        writeln!(out, "gas {}: {}", spec, gas.composition())?;
+    }
+    writeln!(
+        out,
+        "cells: {} ({} pressures x {} temperatures x {} gases x {} equations)",
+        num_p * num_t * gases.len() * eoses.len(),
+        num_p,
+        num_t,
+        gases.len(),
+        eoses.len()
+    )?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Writes a CSV table with one row per condition of pressure and temperature
+/// and one column per gas (and per equation of state if several are selected)
+fn compare_gases(
+    gases: &[(&str, Gas)],
+    eoses: &[(&str, Eos)],
+    quantity: Quantity,
+    cond: &Conditions,
+    output: &OutputOpts,
+    out: &mut dyn Write,
+) -> Result<(), Error> {
+    if output.bare_header {
+        write!(out, "P\tT")?;
+    } else {
+        write!(
+            out,
+            "{}\t{}",
+            cond.pressure_header(),
+            cond.temperature_header()
+        )?;
+    }
+    for &(label, _) in eoses.iter() {
+        for (spec, _) in gases.iter() {
+            if eoses.len() > 1 {
+                write!(out, "\t{} {}", label, spec)?;
+            } else {
+                write!(out, "\t{}", spec)?;
+            }
+        }
+    }
+    writeln!(out)?;
+    out.flush()?;
+    for p in cond.pressure.iter() {
+        if cond.skip_pressure(p).map_err(Error::Usage)? {
+            continue;
+        }
+        for t in cond.temperature.iter() {
+            write!(out, "{}\t{}", p, t)?;
+            let (p, t) = (cond.pressure_pa(p), cond.temperature_k(t));
+            for &(_, eos) in eoses.iter() {
+                for (_, gas) in gases.iter() {
+                    if output.debug_roots {
+                        print_debug_roots(gas, eos, p, t);
+                    }
+                    let value = quantity.compute(gas, eos, p, t)?;
+                    write!(out, "\t{}", output.format_quantity(quantity, value))?;
+                }
+            }
+            writeln!(out)?;
+            out.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes the partial molar volumes in cm3/mol of the components of `mix`,
+/// one line per component for a single condition, or one column per component over ranges
+fn write_partial_volumes(
+    mix: &GasMixture,
+    eoses: &[(&str, Eos)],
+    cond: &Conditions,
+    output: &OutputOpts,
+    out: &mut dyn Write,
+) -> Result<(), Error> {
+    let volumes = |eos: Eos, p: f64, t: f64| -> Result<Vec<f64>, Error> {
+        let vs = mix.partial_molar_volumes(eos, cond.pressure_pa(p), cond.temperature_k(t))?;
+        Ok(vs.into_iter().map(|v| v * 1e6).collect())
+    };
+
+    if cond.temperature.is_scalar() && cond.pressure.is_scalar() {
+        let (p, t) = (cond.pressure.start, cond.temperature.start);
+        if let Some(msg) = cond.non_positive(p) {
+            return Err(Error::Usage(msg));
+        }
+        for &(label, eos) in eoses.iter() {
+            for (c, v) in mix.comps.iter().zip(volumes(eos, p, t)?) {
+                if eoses.len() > 1 {
+                    write!(out, "{}\t", label)?;
+                }
+                writeln!(out, "{}\t{}", c.pure_gas().id, output.format_value(v))?;
+            }
+        }
+        out.flush()?;
+        return Ok(());
+    }
+
+    if output.bare_header {
+        write!(out, "P\tT")?;
+    } else {
+        write!(
+            out,
+            "{}\t{}",
+            cond.pressure_header(),
+            cond.temperature_header()
+        )?;
+    }
+    for &(label, _) in eoses.iter() {
+        for c in mix.comps.iter() {
+            if eoses.len() > 1 {
+                write!(out, "\t{} {}", label, c.pure_gas().id)?;
+            } else {
+                write!(out, "\t{}", c.pure_gas().id)?;
+            }
+        }
+    }
+    writeln!(out)?;
+    for p in cond.pressure.iter() {
+        if cond.skip_pressure(p).map_err(Error::Usage)? {
+            continue;
+        }
+        for t in cond.temperature.iter() {
+            write!(out, "{}\t{}", p, t)?;
+            for &(_, eos) in eoses.iter() {
+                for v in volumes(eos, p, t)? {
+                    write!(out, "\t{}", output.format_value(v))?;
+                }
+            }
+            writeln!(out)?;
+            out.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes the partial pressure and the fugacity of each component of the mixture
+/// at a single condition, in the pressure unit of the conditions
+fn write_partial_pressures(
+    mix: &GasMixture,
+    eoses: &[(&str, Eos)],
+    cond: &Conditions,
+    output: &OutputOpts,
+    out: &mut dyn Write,
+) -> Result<(), Error> {
+    if !cond.temperature.is_scalar() || !cond.pressure.is_scalar() {
+        return Err(Error::Usage(
+            "Partial pressures require a single temperature and pressure".into(),
+        ));
+    }
+    let (p, t) = (cond.pressure.start, cond.temperature.start);
+    if let Some(msg) = cond.non_positive(p) {
+        return Err(Error::Usage(msg));
+    }
+    let (p, t) = (cond.pressure_pa(p), cond.temperature_k(t));
+    for &(label, eos) in eoses.iter() {
+        let phis = mix.fugacity_coeffs(eos, p, t)?;
+        for (c, phi) in mix.comps.iter().zip(phis) {
+            let pi = c.molar_fraction() * p.0;
+            if eoses.len() > 1 {
+                write!(out, "{}\t", label)?;
+            }
+            writeln!(
+                out,
+                "{}\t{}\t{}",
+                c.pure_gas().id,
+                output.format_value(cond.p_unit.pa_to_unit(Pascal(pi))),
+                output.format_value(cond.p_unit.pa_to_unit(Pascal(phi * pi)))
+            )?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Writes Z and dZ/dw by central difference over the acentric factor offset by ±`delta`
+fn write_sensitivity(
+    gas: &Gas,
+    eoses: &[(&str, Eos)],
+    cond: &Conditions,
+    output: &OutputOpts,
+    delta: f64,
+    out: &mut dyn Write,
+) -> Result<(), Error> {
+    if !cond.temperature.is_scalar() || !cond.pressure.is_scalar() {
+        return Err(Error::Usage(
+            "The sensitivity requires a single temperature and pressure".into(),
+        ));
+    }
+    let (p, t) = (cond.pressure.start, cond.temperature.start);
+    if let Some(msg) = cond.non_positive(p) {
+        return Err(Error::Usage(msg));
+    }
+    let (p, t) = (cond.pressure_pa(p), cond.temperature_k(t));
+    let (below, above) = (
+        gas.with_acentric_offset(-delta).map_err(Error::Usage)?,
+        gas.with_acentric_offset(delta).map_err(Error::Usage)?,
+    );
+    for &(label, eos) in eoses.iter() {
+        let z = gas.z(eos, p, t)?;
+        let dz_dw = (above.z(eos, p, t)? - below.z(eos, p, t)?) / (2f64 * delta);
+        if eoses.len() > 1 {
+            write!(out, "{}\t", label)?;
+        }
+        writeln!(
+            out,
+            "{}\t{}",
+            output.format_value(z),
+            output.format_value(dz_dw)
+        )?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Writes the parameters and coefficients of the cubic equation in Z at a single condition
+fn write_coeffs(
+    gas: &Gas,
+    eoses: &[(&str, Eos)],
+    cond: &Conditions,
+    output: &OutputOpts,
+    out: &mut dyn Write,
+) -> Result<(), Error> {
+    if !cond.temperature.is_scalar() || !cond.pressure.is_scalar() {
+        return Err(Error::Usage(
+            "The cubic coefficients require a single temperature and pressure".into(),
+        ));
+    }
+    let (p, t) = (cond.pressure.start, cond.temperature.start);
+    if let Some(msg) = cond.non_positive(p) {
+        return Err(Error::Usage(msg));
+    }
+    let (p, t) = (cond.pressure_pa(p).0, cond.temperature_k(t).0);
+    for (i, &(label, eos)) in eoses.iter().enumerate() {
+        let z = gas.z(eos, Pascal(p), Kelvin(t))?;
+        let params = gas.eos_params(eos, t);
+        let (a, b) = params.dimensionless(p, t);
+        let (a3, a2, a1, a0) = params.cubic_coeffs(eos, p, t);
+        if eoses.len() > 1 {
+            if i > 0 {
+                writeln!(out)?;
+            }
+            writeln!(out, "[{}]", label)?;
+        }
+        let mut lines = vec![
+            ("A", a),
+            ("B", b),
+            ("a3", a3),
+            ("a2", a2),
+            ("a1", a1),
+            ("a0", a0),
+        ];
+        if params.c != 0f64 {
+            lines.push(("shift", params.c * p / (R * t)));
+        }
+        lines.push(("Z", z));
+        for (name, value) in lines.iter() {
+            writeln!(out, "{:8}{}", name, output.format_value(*value))?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Writes Z and its analytic derivatives in the pressure and the temperature
+/// at each condition of the ranges, one line per condition
+fn write_gradient(
+    gas: &Gas,
+    eoses: &[(&str, Eos)],
+    cond: &Conditions,
+    output: &OutputOpts,
+    out: &mut dyn Write,
+) -> Result<(), Error> {
+    let mut pressures = Vec::new();
+    for p in cond.pressure.iter() {
+        if !cond.skip_pressure(p).map_err(Error::Usage)? {
+            pressures.push(p);
+        }
+    }
+    let temperatures: Vec<f64> = cond.temperature.iter().collect();
+    // the conversions from the units as entered are affine
+    let pa_per_unit = cond.pressure_pa(1f64).0 - cond.pressure_pa(0f64).0;
+    let k_per_unit = cond.temperature_k(1f64).0 - cond.temperature_k(0f64).0;
+    let (dp_header, dt_header) = match cond.reduced {
+        Some(_) => ("dZ/dPr".to_string(), "dZ/dTr".to_string()),
+        None => (
+            format!("dZ/dP[1/{}]", cond.pressure_unit()),
+            format!("dZ/dT[1/{}]", cond.t_unit.symbol()),
+        ),
+    };
+
+    let multi_eos = eoses.len() > 1;
+    if multi_eos {
+        write!(out, "EOS\t")?;
+    }
+    writeln!(
+        out,
+        "{}\t{}\tZ\t{}\t{}",
+        cond.pressure_header(),
+        cond.temperature_header(),
+        dp_header,
+        dt_header
+    )?;
+    for &(label, eos) in eoses.iter() {
+        for &p in pressures.iter() {
+            for &t in temperatures.iter() {
+                let (z, dz_dp, dz_dt) =
+                    gas.z_derivatives(eos, cond.pressure_pa(p), cond.temperature_k(t))?;
+                if multi_eos {
+                    write!(out, "{}\t", label)?;
+                }
+                writeln!(
+                    out,
+                    "{}\t{}\t{}\t{}\t{}",
+                    p,
+                    t,
+                    output.format_value(z),
+                    output.format_value(dz_dp * pa_per_unit),
+                    output.format_value(dz_dt * k_per_unit)
+                )?;
+            }
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Writes the properties of `gas` at a single condition, one labeled line per property
+fn write_properties(
+    gas: &Gas,
+    eoses: &[(&str, Eos)],
+    cond: &Conditions,
+    output: &OutputOpts,
+    out: &mut dyn Write,
+) -> Result<(), Error> {
+    if !cond.temperature.is_scalar() || !cond.pressure.is_scalar() {
+        return Err(Error::Usage(
+            "The properties require a single temperature and pressure".into(),
+        ));
+    }
+    let (p, t) = (cond.pressure.start, cond.temperature.start);
+    if let Some(msg) = cond.non_positive(p) {
+        return Err(Error::Usage(msg));
+    }
+    let (p, t) = (cond.pressure_pa(p), cond.temperature_k(t));
+    const NO_MOLAR_MASS: &str = "n/a (unknown molar mass)";
+    const NO_CP: &str = "n/a (unknown ideal gas heat capacity)";
+    for (i, &(label, eos)) in eoses.iter().enumerate() {
+        let props = gas.properties(eos, p, t)?;
+        let value = |v: f64, unit: &str| format!("{}{}", output.format_value(v), unit);
+        let optional = |v: Option<f64>, unit: &str, missing: &str| match v {
+            Some(v) => value(v, unit),
+            None => missing.to_string(),
+        };
+        let speed_missing = if props.gamma.is_none() {
+            NO_CP
+        } else {
+            NO_MOLAR_MASS
+        };
+        let lines = [
+            ("Z", value(props.z, "")),
+            ("molar volume", value(props.molar_volume * 1e6, " cm3/mol")),
+            ("density", optional(props.density, " kg/m3", NO_MOLAR_MASS)),
+            ("fugacity coeff", value(props.fugacity_coeff, "")),
+            ("H departure", value(props.enthalpy_departure, " J/mol")),
+            ("S departure", value(props.entropy_departure, " J/(mol.K)")),
+            ("Cp residual", value(props.residual_cp, " J/(mol.K)")),
+            ("Cp/Cv", optional(props.gamma, "", NO_CP)),
+            (
+                "speed of sound",
+                optional(props.speed_of_sound, " m/s", speed_missing),
+            ),
+            (
+                "JT coefficient",
+                optional(props.joule_thomson.map(|jt| jt * 1e5), " K/bar", NO_CP),
+            ),
+        ];
+        if eoses.len() > 1 {
+            if i > 0 {
+                writeln!(out)?;
+            }
+            writeln!(out, "[{}]", label)?;
+        }
+        for (name, value) in lines.iter() {
+            writeln!(out, "{:16}{}", name, value)?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Writes the final temperature and Z-factor of the process `path`
+/// from a single condition to the pressure `target`
+#[allow(clippy::too_many_arguments)]
+fn write_path(
+    gas: &Gas,
+    eoses: &[(&str, Eos)],
+    cond: &Conditions,
+    output: &OutputOpts,
+    path: ProcessPath,
+    target: f64,
+    config: &SolverConfig,
+    out: &mut dyn Write,
+) -> Result<(), Error> {
+    if !cond.temperature.is_scalar() || !cond.pressure.is_scalar() {
+        return Err(Error::Usage(
+            "The path requires a single initial temperature and pressure".into(),
+        ));
+    }
+    let (p, t) = (cond.pressure.start, cond.temperature.start);
+    for &p in [p, target].iter() {
+        if let Some(msg) = cond.non_positive(p) {
+            return Err(Error::Usage(msg));
+        }
+    }
+    let (p1, t1, p2) = (
+        cond.pressure_pa(p),
+        cond.temperature_k(t),
+        cond.pressure_pa(target),
+    );
+    for &(label, eos) in eoses.iter() {
+        let t2 = gas
+            .path_temperature(eos, path, (p1, t1), p2, config)
+            .map_err(|err| Error::Compute(err.to_string()))?;
+        let z = gas.z(eos, p2, t2)?;
+        if eoses.len() > 1 {
+            write!(out, "{}\t", label)?;
+        }
+        writeln!(
+            out,
+            "{}\t{}",
+            output.format_value(cond.t_unit.kelvin_to_unit(t2)),
+            output.format_value(z)
+        )?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+/// Runs rkz with the given arguments and returns its standard output
+fn run(args: &[&str]) -> Result<String, Error> {
+    run_with(process_args, args)
+}
+
+#[cfg(test)]
+/// Runs the mode `process` with the given arguments and returns its standard output
+fn run_with(
+    process: fn(&ArgMatches, &mut dyn Write) -> Result<(), Error>,
+    args: &[&str],
+) -> Result<String, Error> {
+    let matches = app().get_matches_from(std::iter::once(&"rkz").chain(args.iter()));
+    let mut out = Vec::new();
+    process(&matches, &mut out)?;
+    Ok(String::from_utf8(out).unwrap())
+}
+
+#[cfg(test)]
+/// Writer that fails with a broken pipe once `limit` bytes were written
+struct ClosingPipe {
+    written: usize,
+    limit: usize,
+}
+
+#[cfg(test)]
+impl Write for ClosingPipe {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.written + buf.len() > self.limit {
+            Err(io::ErrorKind::BrokenPipe.into())
+        } else {
+            self.written += buf.len();
+            Ok(buf.len())
+        }
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_broken_pipe() {
+    let pipe = ClosingPipe {
+        written: 0,
+        limit: 4096,
+    };
+    let mut out = BufWriter::new(pipe);
+    let matches = app().get_matches_from(["rkz", "-g", "N2", "-t", "-40:80", "-p", "1:1000"]);
+    let res = process_args(&matches, &mut out);
+    match res {
+        Err(Error::Io(err)) => assert_eq!(err.kind(), io::ErrorKind::BrokenPipe),
+        _ => panic!("expected a broken pipe error"),
+    }
+}
+
+#[test]
+fn test_compare_gas() {
+    let out = run(&["--compare-gas", "H2,N2", "-p", "200", "-t", "20"]).unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0], "P[bar]\tT[°C]\tH2\tN2");
+    let cells: Vec<&str> = lines[1].split('\t').collect();
+    assert_eq!(cells.len(), 4);
+    let h2: f64 = cells[2].parse().unwrap();
+    let n2: f64 = cells[3].parse().unwrap();
+    assert_ne!(h2, n2);
+    assert_eq!(
+        h2,
+        Gas::from_string("H2")
+            .unwrap()
+            .z(Eos::RedlichKwong, Pascal(200e5), Kelvin(293.15))
+            .unwrap()
+    );
+
+    let err = run(&["--compare-gas", "H2,Xx", "-p", "200", "-t", "20"])
+        .err()
+        .unwrap();
+    assert!(err.to_string().starts_with("Xx: "));
+}
+
+#[test]
+fn test_gamma() {
+    let out = run(&["-g", "N2", "-p", "0.01", "-t", "20", "--gamma"]).unwrap();
+    let gamma: f64 = out.trim().parse().unwrap();
+    assert!((gamma - 1.4).abs() < 0.002);
+}
+
+#[test]
+fn test_reference() {
+    let out = run(&[
+        "-g",
+        "CO2",
+        "-p",
+        "20",
+        "-t",
+        "40",
+        "--enthalpy",
+        "--reference",
+        "40,20",
+    ]);
+    let h: f64 = out.unwrap().trim().parse().unwrap();
+    assert!(h.abs() < 1e-6);
+
+    let out = run(&["-g", "CO2", "-p", "20", "-t", "40", "--entropy"]);
+    let s: f64 = out.unwrap().trim().parse().unwrap();
+    assert!(s < 0f64);
+
+    assert!(run(&[
+        "-g",
+        "CO2",
+        "-p",
+        "20",
+        "-t",
+        "40",
+        "--enthalpy",
+        "--reference",
+        "40"
+    ])
+    .is_err());
+}
+
+#[test]
+fn test_header_units() {
+    let out = run(&["-g", "N2", "-p", "200", "-t", "20:22"]).unwrap();
+    let header = out.lines().next().unwrap();
+    assert_eq!(header, "P[bar] \\ T[°C]\t20°C\t21°C\t22°C");
+
+    let out = run(&["-g", "N2", "-p", "200", "-t", "20:22", "-r", "stdatm"]).unwrap();
+    assert!(out.starts_with("P[barG] \\ T[°C]\t"));
+
+    let out = run(&["-g", "N2", "-p", "200", "-t", "20:22", "--bare-header"]).unwrap();
+    assert_eq!(out.lines().next().unwrap(), "P \\ T\t20\t21\t22");
+}
+
+#[test]
+fn test_scientific() {
+    let out = run(&["-g", "N2", "-p", "200", "-t", "20", "--precision", "3"]).unwrap();
+    assert_eq!(out, "1.027\n");
+    let out = run(&["-g", "N2", "-p", "200", "-t", "20", "--scientific"]).unwrap();
+    assert_eq!(out, "1.0268041545042434e+00\n");
+
+    let matches = app().get_matches_from(vec!["rkz", "--scientific", "--precision", "3"]);
+    let output = OutputOpts::from_matches(&matches).unwrap();
+    assert_eq!(output.format_value(0.00123456), "1.235e-03");
+    assert_eq!(output.format_value(-123456.0), "-1.235e+05");
+}
+
+#[test]
+fn test_clip_domain() {
+    let mut cond = Conditions {
+        temperature: Range::try_from("-250:100:50").unwrap(),
+        pressure: Range::try_from("1:30000:1000").unwrap(),
+        relative: None,
+        strict: false,
+        reduced: None,
+        t_unit: TemperatureUnit::Celsius,
+        p_unit: PressureUnit::Bar,
+    };
+    let warnings = cond.clip_domain(&Gas::from_string("CO2").unwrap()).unwrap();
+    assert_eq!(warnings.len(), 2);
+    assert!(warnings[0].starts_with("temperature range clipped to -50:100°C"));
+    assert!(warnings[1].starts_with("pressure range clipped to 1:8000 bar"));
+    assert_eq!(cond.temperature.iter().next(), Some(-50f64));
+    assert_eq!(cond.pressure.iter().last(), Some(7001f64));
+
+    // unknown domain
+    let mut cond = Conditions {
+        temperature: Range::try_from("-250:100:50").unwrap(),
+        pressure: Range::try_from("1").unwrap(),
+        relative: None,
+        strict: false,
+        reduced: None,
+        t_unit: TemperatureUnit::Celsius,
+        p_unit: PressureUnit::Bar,
+    };
+    let warnings = cond.clip_domain(&Gas::from_string("NH3").unwrap()).unwrap();
+    assert!(warnings.is_empty());
+
+    let mut cond = Conditions {
+        temperature: Range::try_from("-250:-200").unwrap(),
+        pressure: Range::try_from("1").unwrap(),
+        relative: None,
+        strict: false,
+        reduced: None,
+        t_unit: TemperatureUnit::Celsius,
+        p_unit: PressureUnit::Bar,
+    };
+    assert!(cond.clip_domain(&Gas::from_string("CO2").unwrap()).is_err());
+}
+
+#[test]
+fn test_gas_db() {
+    let path = std::env::temp_dir().join(format!("rkz-gas-db-{}.csv", process::id()));
+    std::fs::write(
+        &path,
+        "Formula,Compound Name,T_crit[K],P_crit[bar],Omega\nC5H12,n-Pentane,469.7,33.7,0.2515\n",
+    )
+    .unwrap();
+    let path = path.to_str().unwrap();
+    let res = run(&[
+        "-g", "C5H12", "--gas-db", path, "-e", "PR", "-p", "10", "-t", "20",
+    ]);
+    let mix = run(&[
+        "-g",
+        "90%N2+C5H12",
+        "--gas-db",
+        path,
+        "-p",
+        "10",
+        "-t",
+        "20",
+    ]);
+    // the modes without the pressure table parse the gas the same way
+    let sat = |gas| {
+        run_with(
+            process_saturation,
+            &[
+                "-g",
+                gas,
+                "--gas-db",
+                path,
+                "-e",
+                "PR",
+                "-t",
+                "20",
+                "--saturation",
+                "--precision",
+                "6",
+            ],
+        )
+    };
+    let boyle = |gas| run_with(process_boyle, &["-g", gas, "--gas-db", path, "--boyle"]);
+    let crit_spec = "crit:Tc=469.7,Pc=33.7e5,w=0.2515";
+    assert_eq!(sat("C5H12").unwrap(), sat(crit_spec).unwrap());
+    assert_eq!(boyle("C5H12").unwrap(), boyle(crit_spec).unwrap());
+    std::fs::remove_file(path).unwrap();
+
+    let crit = run(&[
+        "-g",
+        "crit:Tc=469.7,Pc=33.7e5,w=0.2515",
+        "-e",
+        "PR",
+        "-p",
+        "10",
+        "-t",
+        "20",
+    ])
+    .unwrap();
+    assert_eq!(res.unwrap(), crit);
+    assert!(mix.is_ok());
+    assert!(run(&["-g", "C5H12", "-p", "10", "-t", "20"]).is_err());
+}
+
+#[test]
+fn test_gas_spec_file() {
+    let path = std::env::temp_dir().join(format!("rkz-gas-spec-{}.txt", process::id()));
+    std::fs::write(&path, "  80%N2+O2\n").unwrap();
+    let arg = format!("@{}", path.to_str().unwrap());
+    let from_file = run(&["-g", &arg, "-p", "10:20:10", "-t", "20"]);
+    let matches = app().get_matches_from(["rkz", "-g", &arg]);
+    let spec = gas_spec(&matches).unwrap().unwrap().into_owned();
+    std::fs::write(&path, "\n").unwrap();
+    let empty = run(&["-g", &arg, "-p", "10", "-t", "20"]);
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(spec, "80%N2+O2");
+    assert_eq!(
+        from_file.unwrap(),
+        run(&["-g", "80%N2+O2", "-p", "10:20:10", "-t", "20"]).unwrap()
+    );
+    match empty {
+        Err(Error::Parse(msg)) => assert!(msg.ends_with(": no gas spec"), "{}", msg),
+        res => panic!("expected a parse error, got {:?}", res.map(|_| ())),
+    }
+    assert!(run(&["-g", "@/no/such/spec.txt", "-p", "10", "-t", "20"]).is_err());
+}
+
+#[test]
+fn test_compare_eos_to_data() {
+    // synthetic data from Soave-Redlich-Kwong
+    let gas = Gas::from_string("CH3OH+N2").unwrap();
+    let mut data = String::from("# P[bar] T[°C] Z\n");
+    for &(p, t) in &[(5f64, 150f64), (50f64, 200f64), (200f64, 300f64)] {
+        let z = gas
+            .z(
+                Eos::SoaveRedlichKwong,
+                Pascal::from(Bar(p)),
+                Kelvin::from(Celsius(t)),
+            )
+            .unwrap();
+        data.push_str(&format!("{},{},{}\n", p, t, z));
+    }
+    let path = std::env::temp_dir().join(format!("rkz-data-{}.csv", process::id()));
+    std::fs::write(&path, data).unwrap();
+    let path = path.to_str().unwrap();
+    let matches = app().get_matches_from(["rkz", "-g", "CH3OH+N2", "--compare-eos-to-data", path]);
+    let mut out = Vec::new();
+    let res = process_data_fit(&matches, &mut out);
+    std::fs::remove_file(path).unwrap();
+    res.unwrap();
+    let out = String::from_utf8(out).unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines.len(), Eos::all().len() + 2);
+    assert_eq!(lines[0], "EOS\tRMS(Z)");
+    assert!(lines.contains(&"SRK\t0"));
+    assert_eq!(lines[lines.len() - 1], "best fit: SRK (3 points)");
+}
+
+#[test]
+fn test_batch() {
+    let batch = |input: &str, args: &[&str]| {
+        let matches = app().get_matches_from(["rkz", "--batch"].iter().chain(args.iter()));
+        let mut out = Vec::new();
+        process_batch(&matches, &mut input.as_bytes(), &mut out)
+            .map(|_| String::from_utf8(out).unwrap())
+    };
+
+    // blank lines and comments are skipped, the errors keep the input line numbers
+    let input =
+        "# gas P[bar] T[°C]\nN2 200 20\n\n  # nitrogen at 100 bar\nN2 100 20 # same T\nN2 abc 20\n";
+    let expected = ["N2 200 20", "N2 100 20"]
+        .iter()
+        .map(|l| batch(l, &[]).unwrap())
+        .collect::<String>()
+        + "error: line 6: Can't parse abc as a number\n";
+    assert_eq!(batch(input, &[]).unwrap(), expected);
+    #[cfg(feature = "rayon")]
+    assert_eq!(batch(input, &["--threads", "2"]).unwrap(), expected);
+
+    let gases = ["N2", "O2", "CO2", "air", "80%N2+20%O2"];
+    let mut input = String::new();
+    for i in 0..500 {
+        if i % 97 == 3 {
+            input.push_str("N2 abc 20\n");
+        } else if i % 89 == 5 {
+            input.push_str("XYZ 10 20\n");
+        } else {
+            input.push_str(&format!(
+                "{} {} {}\n",
+                gases[i % gases.len()],
+                1 + i,
+                i % 50
+            ));
+        }
+    }
+    let sequential = batch(&input, &["-e", "PR"]).unwrap();
+    #[cfg(feature = "rayon")]
+    {
+        let parallel = batch(&input, &["-e", "PR", "--threads", "4"]).unwrap();
+        assert_eq!(parallel, sequential);
+    }
+
+    let lines: Vec<&str> = sequential.lines().collect();
+    assert_eq!(lines.len(), 500);
+    assert_eq!(lines[3], "error: line 4: Can't parse abc as a number");
+    assert!(lines[5].starts_with("error: line 6: XYZ: "));
+    let z = Gas::from_string("CO2")
+        .unwrap()
+        .z(
+            Eos::PengRobinson,
+            Pascal::from(Bar(8f64)),
+            Kelvin::from(Celsius(7f64)),
+        )
+        .unwrap();
+    assert_eq!(lines[7], z.to_string());
+    assert!(
+        lines
+            .iter()
+            .enumerate()
+            .all(|(i, l)| !l.starts_with("error")
+                || l.starts_with(&format!("error: line {}:", i + 1)))
+    );
+
+    #[cfg(feature = "rayon")]
+    assert!(batch("N2 200 20\n", &["--threads", "0"]).is_err());
+
+    // the cache only reuses the Z-factors of repeated lines
+    let cached = batch(&input, &["-e", "PR", "--cache", "16"]).unwrap();
+    assert_eq!(cached, sequential);
+    let input = "N2 200 20\nair 50 0\nN2 200 20\nN2 100 20\nair 50 0\n";
+    assert_eq!(
+        batch(input, &["--cache", "1"]).unwrap(),
+        batch(input, &[]).unwrap()
+    );
+    assert!(batch(input, &["--cache", "0"]).is_err());
+    assert!(batch(input, &["--cache", "x"]).is_err());
+}
+
+#[test]
+fn test_cases() {
+    let path = std::env::temp_dir().join(format!("rkz-cases-{}.toml", process::id()));
+    std::fs::write(
+        &path,
+        concat!(
+            "[[case]]\n",
+            "name = \"n2\"\n",
+            "gas = \"N2\"\n",
+            "pressure = 200\n",
+            "temperature = 20\n",
+            "\n",
+            "[[case]]\n",
+            "name = \"air-gamma\"\n",
+            "gas = \"78%N2+21%O2+Ar\"\n",
+            "eos = \"PR\"\n",
+            "pressure = 1.0\n",
+            "temperature = 20\n",
+            "quantity = \"gamma\"\n",
+        ),
+    )
+    .unwrap();
+    let path = path.to_str().unwrap();
+    let matches = app().get_matches_from(vec!["rkz", "--cases", path]);
+    let mut out = Vec::new();
+    let res = process_cases(path, &matches, &mut out);
+    std::fs::remove_file(path).unwrap();
+    res.unwrap();
+    let out = String::from_utf8(out).unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines.len(), 2);
+    let n2 = Gas::from_string("N2")
+        .unwrap()
+        .z(Eos::RedlichKwong, Pascal(200e5), Kelvin(293.15))
+        .unwrap();
+    assert_eq!(lines[0], format!("n2\t{}", n2));
+    assert!(lines[1].starts_with("air-gamma\t1.40"));
+}
+
+#[test]
+#[cfg(feature = "watch")]
+fn test_watch_file() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let path = std::env::temp_dir().join(format!("rkz-watch-{}.txt", process::id()));
+    std::fs::write(&path, "N2").unwrap();
+    let (tx, rx) = mpsc::channel();
+    let count = Arc::new(AtomicUsize::new(0));
+
+    // saves the file until the recomputation, as the watch may not be set up yet
+    let saver = {
+        let (path, tx, count) = (path.clone(), tx.clone(), count.clone());
+        std::thread::spawn(move || {
+            for _ in 0..50 {
+                std::fs::write(&path, "O2").unwrap();
+                std::thread::sleep(Duration::from_millis(100));
+                if count.load(Ordering::SeqCst) > 0 {
+                    break;
+                }
+            }
+            tx.send(WatchEvent::Interrupted).unwrap();
+        })
+    };
+    let res = watch_file(&path, tx, rx, || {
+        count.fetch_add(1, Ordering::SeqCst);
+    });
+    saver.join().unwrap();
+    std::fs::remove_file(&path).unwrap();
+    res.unwrap();
+    assert!(count.load(Ordering::SeqCst) > 0);
+}
+
+#[test]
+fn test_relative_annotation() {
+    let out = run(&[
+        "-g",
+        "N2",
+        "-p",
+        "200",
+        "-t",
+        "20",
+        "-r",
+        "stdatm",
+        "--annotate",
+    ])
+    .unwrap();
+    let z = Gas::from_string("N2")
+        .unwrap()
+        .z(Eos::RedlichKwong, Pascal(201.01325e5), Kelvin(293.15))
+        .unwrap();
+    assert_eq!(out, format!("Z(200 barG, 20°C) = {}\n", z));
+
+    // same pressure head in CSV mode
+    let out = run(&["-g", "N2", "-p", "200", "-t", "20:21", "-r", "stdatm"]).unwrap();
+    let row = out.lines().nth(1).unwrap();
+    assert_eq!(
+        row,
+        format!(
+            "200\t{}\t{}",
+            z,
+            Gas::from_string("N2")
+                .unwrap()
+                .z(Eos::RedlichKwong, Pascal(201.01325e5), Kelvin(294.15))
+                .unwrap()
+        )
+    );
+}
+
+#[test]
+fn test_root_count() {
+    // supercritical
+    let out = run(&[
+        "-g",
+        "N2",
+        "-p",
+        "10:200:50",
+        "-t",
+        "0:100:50",
+        "--root-count",
+    ])
+    .unwrap();
+    for row in out.lines().skip(1) {
+        assert!(row.split('\t').skip(1).all(|c| c == "1"), "{}", row);
+    }
+    // CO2 below its critical temperature
+    let out = run(&[
+        "-g",
+        "CO2",
+        "-e",
+        "PR",
+        "-p",
+        "10:30:10",
+        "-t",
+        "-20:0:10",
+        "--root-count",
+    ])
+    .unwrap();
+    assert!(out
+        .lines()
+        .skip(1)
+        .any(|row| row.split('\t').skip(1).any(|c| c == "3")));
+}
+
+#[test]
+fn test_density_input() {
+    let density = |args: &[&str]| run_with(process_density, args);
+    let out = density(&["-g", "N2", "-t", "20", "--density-input", "200", "-e", "PR"]).unwrap();
+    let values: Vec<f64> = out.trim().split('\t').map(|v| v.parse().unwrap()).collect();
+    assert_eq!(values.len(), 2);
+    // the Z-factor at the computed pressure gives back the density
+    let n2 = Gas::from_string("N2").unwrap();
+    let (p, t) = (
+        Pascal::from(Bar(values[0])).0,
+        Kelvin::from(Celsius(20f64)).0,
+    );
+    let z = n2.z(Eos::PengRobinson, Pascal(p), Kelvin(t)).unwrap();
+    assert!((z - values[1]).abs() < 1e-9);
+    assert!((n2.molar_mass().unwrap() * p / (z * R * t) - 200f64).abs() < 1e-6);
+
+    let out = density(&["-g", "N2", "-t", "0:40:10", "--density-input", "200"]).unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines[0], "T[°C]\tP[bar]\tZ");
+    assert_eq!(lines.len(), 6);
+
+    assert!(density(&["-g", "N2", "-t", "20", "--density-input", "0"]).is_err());
+    let crit = "crit:Tc=126.2,Pc=33.9e5,w=0.039";
+    assert!(density(&["-g", crit, "-t", "20", "--density-input", "200"]).is_err());
+}
+
+#[test]
+fn test_max_cells() {
+    let err = run(&["-g", "N2", "-p", "0:1000000", "-t", "0:1000"]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "1000001 pressures x 1001 temperatures make 1001001001 cells, more than the limit of 10000000 (see --max-cells)"
+    );
+    assert_eq!(err.code(), 2);
+
+    let args = ["-g", "N2", "-p", "1:100", "-t", "0:99"];
+    assert!(run(&[&args[..], &["--max-cells", "9999"]].concat()).is_err());
+    let out = run(&[&args[..], &["--max-cells", "10000"]].concat()).unwrap();
+    assert_eq!(out.lines().count(), 101);
+
+    assert!(run(&[&args[..], &["--max-cells", "many"]].concat()).is_err());
+}
+
+#[test]
+fn test_dry_run() {
+    let out = run(&[
+        "-g",
+        "80%N2+O2",
+        "-p",
+        "0:100:10",
+        "-t",
+        "-20:20:5",
+        "-e",
+        "PR",
+        "--dry-run",
+    ])
+    .unwrap();
+    assert!(out.contains("equation: Peng-Robinson"));
+    assert!(out.contains("gas 80%N2+O2: N2 0.80, O2 0.20"));
+    assert!(out.contains("cells: 99 (11 pressures x 9 temperatures x 1 gases x 1 equations)"));
+
+    assert!(run(&["-g", "N2", "-p", "100:0", "-t", "20", "--dry-run"]).is_err());
+    assert!(run(&["-g", "N2+XX", "-p", "100", "-t", "20", "--dry-run"]).is_err());
+}
+
+#[test]
+fn test_multiple_eos() {
+    let out = run(&["-g", "N2", "-p", "200", "-t", "20", "-e", "RK", "-e", "PR"]).unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines.len(), 2);
+    let rk: Vec<&str> = lines[0].split('\t').collect();
+    let pr: Vec<&str> = lines[1].split('\t').collect();
+    assert_eq!(rk[0], "RK");
+    assert_eq!(pr[0], "PR");
+    let single = run(&["-g", "N2", "-p", "200", "-t", "20", "-e", "PR"]).unwrap();
+    assert_eq!(pr[1], single.trim());
+    assert_ne!(rk[1], pr[1]);
+
+    let out = run(&[
+        "-g", "N2", "-p", "200", "-t", "20:21", "-e", "RK", "-e", "PR",
+    ])
+    .unwrap();
+    let header = out.lines().next().unwrap();
+    assert_eq!(
+        header,
+        "P[bar] \\ T[°C]\tRK 20°C\tRK 21°C\tPR 20°C\tPR 21°C"
+    );
+}
+
+#[test]
+fn test_args_with_env() {
+    let env = |var: &str| match var {
+        "RKZ_GAS" => Some("N2".to_string()),
+        "RKZ_PRESSURE" => Some("200".to_string()),
+        "RKZ_TEMPERATURE" => Some("-20".to_string()),
+        _ => None,
+    };
+    let complete = |args: &[&str]| {
+        let args = std::iter::once(&"rkz").chain(args.iter());
+        args_with_env(args.map(|a| a.to_string()).collect(), env)[1..].join(" ")
+    };
+    assert_eq!(complete(&[]), "--gas=N2 --temperature=-20 --pressure=200");
+    // the options take precedence
+    assert_eq!(
+        complete(&["-p", "1", "-g", "O2"]),
+        "-p 1 -g O2 --temperature=-20"
+    );
+    // the modes ignore the variables of the options they don't take
+    assert_eq!(
+        complete(&["--compare-gas", "N2,O2"]),
+        "--compare-gas N2,O2 --temperature=-20 --pressure=200"
+    );
+    assert_eq!(complete(&["--boyle"]), "--boyle --gas=N2");
+    assert_eq!(
+        complete(&["--virial-b"]),
+        "--virial-b --gas=N2 --temperature=-20"
+    );
+    assert_eq!(complete(&["--batch"]), "--batch");
+    assert_eq!(complete(&["--list-gas"]), "--list-gas");
+    // and the command line errors are left to the parsing
+    assert_eq!(complete(&["--no-such-flag"]), "--no-such-flag");
+}
+
+#[test]
+fn test_check_flags() {
+    let matches = app().get_matches_from(["rkz", "-g", "N2", "-t", "20", "-r", "stdatm"]);
+    let err = check_flags(&matches).unwrap_err();
+    assert!(err.starts_with("--relative offsets the pressure only"));
+
+    let matches =
+        app().get_matches_from(["rkz", "-g", "N2", "-t", "20", "-p", "1", "-r", "stdatm"]);
+    assert!(check_flags(&matches).is_ok());
+
+    let check = |args: &[&str]| {
+        let matches = app()
+            .get_matches_from_safe(std::iter::once(&"rkz").chain(args.iter()))
+            .map_err(|err| err.message)?;
+        check_flags(&matches)
+    };
+    // rejected by clap
+    assert!(check(&[
+        "-g",
+        "N2",
+        "-t",
+        "1",
+        "-p",
+        "1",
+        "--reduced",
+        "-r",
+        "stdatm"
+    ])
+    .is_err());
+    let err = check(&[
+        "-g",
+        "N2",
+        "-t",
+        "-180",
+        "--saturation",
+        "--pressure-unit",
+        "psi",
+    ])
+    .unwrap_err();
+    assert!(err.starts_with("--pressure-unit sets the unit of the pressure"));
+    let err = check(&[
+        "--chart",
+        "-g",
+        "N2",
+        "-t",
+        "1:2",
+        "--temperature-unit",
+        "K",
+    ])
+    .unwrap_err();
+    assert!(err.starts_with("--temperature-unit sets the unit of the temperature"));
+    let err = check(&["--chart", "-g", "N2", "--reduced"]).unwrap_err();
+    assert!(err.starts_with("--reduced applies to -p and -t only"));
+
+    let args = [
+        "-g",
+        "N2",
+        "-t",
+        "300",
+        "-p",
+        "10:100:10",
+        "--temperature-unit",
+        "K",
+        "--pressure-unit",
+        "MPa",
+        "--assume-ideal-below",
+        "0.01",
+        "--color",
+        "never",
+    ];
+    assert!(check(&args).is_ok());
+    assert!(run(&args).is_ok());
+    let args = [
+        "-g",
+        "N2",
+        "-t",
+        "1.5",
+        "-p",
+        "0.1:2:0.1",
+        "--reduced",
+        "--partial-volumes",
+    ];
+    assert!(check(&args).is_ok());
+}
+
+#[test]
+fn test_boyle() {
+    let matches = app().get_matches_from(["rkz", "-g", "N2", "--boyle", "-e", "VdW"]);
+    let mut out = Vec::new();
+    process_boyle(&matches, &mut out).unwrap();
+    let t: f64 = String::from_utf8(out).unwrap().trim().parse().unwrap();
+    assert!((t - (3.375 * 126.2 - 273.15)).abs() < 1e-6);
+
+    let matches = app().get_matches_from(["rkz", "-g", "N2+O2", "--boyle"]);
+    assert!(process_boyle(&matches, &mut Vec::new()).is_err());
+}
+
+#[test]
+fn test_virial_b() {
+    let out = run_with(
+        process_virial,
+        &["-g", "N2", "-t", "26.85", "--virial-b", "-e", "VdW"],
+    )
+    .unwrap();
+    let b: f64 = out.trim().parse().unwrap();
+    // b - a/(RT) = (1/8 - 27/64 Tc/T) R Tc/Pc
+    let (tc, pc) = (126.2, 3_390_000f64);
+    let expected = (0.125 - 27f64 / 64f64 * tc / 300f64) * rkz::eos::R * tc / pc;
+    assert!((b - expected).abs() < 1e-12);
+
+    let out = run_with(
+        process_virial,
+        &["-g", "N2", "-t", "0:1000:5n", "--virial-b"],
+    )
+    .unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines[0], "T[°C]\tB[m3/mol]");
+    let bs: Vec<f64> = lines[1..]
+        .iter()
+        .map(|l| l.split('\t').nth(1).unwrap().parse().unwrap())
+        .collect();
+    // B increases with temperature and changes sign at the Boyle temperature
+    assert_eq!(bs.len(), 5);
+    assert!(bs.windows(2).all(|w| w[0] < w[1]));
+    assert!(bs[0] < 0f64 && bs[4] > 0f64);
+
+    assert!(run_with(process_virial, &["-g", "N2", "-t", "-300", "--virial-b"]).is_err());
+}
+
+#[test]
+fn test_exit_codes() {
+    let code = |args: &[&str]| run(args).unwrap_err().code();
+    assert_eq!(code(&["-g", "N2", "-p", "200:100", "-t", "20"]), 2);
+    assert_eq!(code(&["-g", "N2", "-p", "200", "-t", "20", "-e", "XX"]), 2);
+    assert_eq!(code(&["-g", "XX", "-p", "200", "-t", "20"]), 3);
+    assert_eq!(
+        code(&["--compare-gas", "N2,XX", "-p", "200", "-t", "20"]),
+        3
+    );
+
+    let matches = app().get_matches_from(["rkz", "-g", "N2", "-t", "20", "-r", "stdatm"]);
+    assert_eq!(Error::Usage(check_flags(&matches).unwrap_err()).code(), 2);
+
+    let saturation = |args: &[&str]| run_with(process_saturation, args);
+    // above the critical temperature
+    let err = saturation(&["-g", "N2", "-t", "0", "--saturation"]).unwrap_err();
+    assert_eq!(err.code(), 4);
+    let err =
+        saturation(&["-g", "N2", "-t", "-170", "--saturation", "--max-iter", "1"]).unwrap_err();
+    assert_eq!(err.code(), 4);
+    let err = saturation(&["-g", "N2+O2", "-t", "-170", "--saturation"]).unwrap_err();
+    assert_eq!(err.code(), 2);
+}
+
+#[test]
+fn test_saturation() {
+    let matches = app().get_matches_from([
+        "rkz",
+        "-g",
+        "N2",
+        "-t",
+        "-180:-170:5",
+        "-e",
+        "PR",
+        "--saturation",
+    ]);
+    let mut out = Vec::new();
+    process_saturation(&matches, &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[0], "T[°C]\tPsat[bar]\tZ_liquid\tZ_vapor");
+    for line in lines[1..].iter() {
+        let cells: Vec<f64> = line.split('\t').map(|c| c.parse().unwrap()).collect();
+        assert_eq!(cells.len(), 4);
+        assert!(cells[1] > 0f64);
+        assert!(cells[2] > 0f64);
+        assert!(cells[3] > cells[2]);
+    }
+}
+
+#[test]
+fn test_json_error() {
+    let err = run(&[
+        "-g",
+        "XX",
+        "-p",
+        "200",
+        "-t",
+        "20",
+        "--error-format",
+        "json",
+    ])
+    .unwrap_err();
+    assert_eq!(
+        err.to_json(),
+        r#"{"error":"ParseError","message":"The requested gas is not referenced","code":3}"#
+    );
+    assert_eq!(json_string("a \"b\"\n\\"), r#""a \"b\"\n\\""#);
+
+    let args = vec!["rkz".to_string(), "--error-format".into(), "json".into()];
+    assert_eq!(
+        ErrorFormat::from_raw_args(args.into_iter()),
+        ErrorFormat::Json
+    );
+}
+
+#[test]
+fn test_non_positive_pressure() {
+    // -1.5 barG is below zero absolute
+    let out = run(&["-g", "N2", "-p", "-1.5:2:0.5", "-t", "20", "-r", "stdatm"]).unwrap();
+    let rows: Vec<&str> = out.lines().skip(1).collect();
+    assert_eq!(rows.len(), 7);
+    assert!(rows[0].starts_with("-1\t"));
+    for row in rows.iter() {
+        let z: f64 = row.split('\t').nth(1).unwrap().parse().unwrap();
+        assert!(z.is_finite() && z > 0f64);
+    }
+
+    let out = run(&[
+        "--compare-gas",
+        "N2,H2",
+        "-p",
+        "-2:0",
+        "-t",
+        "20",
+        "-r",
+        "stdatm",
+    ])
+    .unwrap();
+    assert_eq!(out.lines().count(), 3);
+
+    let args = [
+        "-g",
+        "N2",
+        "-p",
+        "-1.5:2:0.5",
+        "-t",
+        "20",
+        "-r",
+        "stdatm",
+        "--strict",
+    ];
+    assert!(run(&args).is_err());
+    assert!(run(&["-g", "N2", "-p", "-2", "-t", "20", "-r", "stdatm"]).is_err());
+}
+
+#[test]
+fn test_metering_check() {
+    let rows = |eos| {
+        let matches = app().get_matches_from(["rkz", "--metering-check", "-e", eos]);
+        let mut out = Vec::new();
+        process_metering_check(&matches, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        out.lines()
+            .skip(1)
+            .map(|line| {
+                let cells: Vec<String> = line.split('\t').map(String::from).collect();
+                assert_eq!(cells.len(), 6);
+                let p: f64 = cells[1].parse().unwrap();
+                let deviation: f64 = cells[5].parse().unwrap();
+                (p, deviation)
+            })
+            .collect::<Vec<_>>()
+    };
+    for (p, deviation) in rows("PR") {
+        // the cubic equations are within a fraction of percent at normal conditions
+        if p < 2f64 {
+            assert!(deviation.abs() < 0.5);
+        }
+    }
+    // and SRK within about one percent for the natural gases at 60 bar
+    let natural_gases: Vec<f64> = rows("SRK")
+        .into_iter()
+        .filter(|&(p, _)| p > 2f64)
+        .map(|(_, deviation)| deviation)
+        .collect();
+    assert_eq!(natural_gases.len(), 4);
+    assert!(
+        natural_gases.iter().all(|d| d.abs() < 1f64),
+        "{:?}",
+        natural_gases
+    );
+}
+
+#[test]
+fn test_partial_volumes() {
+    let out = run(&[
+        "-g",
+        "30%CO2+N2",
+        "-p",
+        "50",
+        "-t",
+        "27",
+        "-e",
+        "PR",
+        "--partial-volumes",
+    ])
+    .unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("CO2\t"));
+    assert!(lines[1].starts_with("N2\t"));
+    let v_co2: f64 = lines[0].split('\t').nth(1).unwrap().parse().unwrap();
+    assert!(v_co2 > 0f64 && v_co2 < 500f64);
+
+    let out = run(&[
+        "-g",
+        "30%CO2+N2",
+        "-p",
+        "10:50:20",
+        "-t",
+        "27",
+        "--partial-volumes",
+    ])
+    .unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines[0], "P[bar]\tT[°C]\tCO2\tN2");
+    assert_eq!(lines.len(), 4);
+
+    assert!(run(&["-g", "N2", "-p", "50", "-t", "27", "--partial-volumes"]).is_err());
+}
+
+#[test]
+fn test_reduced() {
+    let n2 = find_gas("N2").unwrap();
+    let out = run(&["-g", "N2", "--reduced", "-p", "1", "-t", "1", "-e", "PR"]).unwrap();
+    let z: f64 = out.trim().parse().unwrap();
+    assert_eq!(
+        z,
+        n2.z(Eos::PengRobinson, Pascal(n2.pc), Kelvin(n2.tc))
+            .unwrap()
+    );
+
+    let out = run(&["-g", "N2", "--reduced", "-p", "1", "-t", "1", "--annotate"]).unwrap();
+    assert!(out.starts_with("Z(Pr = 1, Tr = 1) = "));
+
+    let out = run(&["-g", "N2", "--reduced", "-p", "0.5:1:0.5", "-t", "1:2"]).unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines[0], "Pr \\ Tr\tTr = 1\tTr = 2");
+    assert_eq!(lines.len(), 3);
+
+    // pseudo-critical point of a mixture
+    let gas = Gas::from_string("50%N2+50%O2").unwrap();
+    let (tc, pc) = gas.criticals();
+    let out = run(&["-g", "50%N2+50%O2", "--reduced", "-p", "2", "-t", "1.5"]).unwrap();
+    let z: f64 = out.trim().parse().unwrap();
+    assert_eq!(
+        z,
+        gas.z(Eos::RedlichKwong, Pascal(2f64 * pc), Kelvin(1.5 * tc))
+            .unwrap()
+    );
+}
+
+#[test]
+fn test_chart() {
+    let chart = |args: &[&str]| run_with(process_chart, args);
+
+    let out = chart(&[
+        "--chart",
+        "--acentric",
+        "0",
+        "-p",
+        "0",
+        "-t",
+        "1",
+        "-e",
+        "PR",
+    ])
+    .unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines, vec!["Pr \\ Tr\t1", lines[1]]);
+    let z: f64 = lines[1].split('\t').nth(1).unwrap().parse().unwrap();
+    assert!((z - 1f64).abs() < 1e-9);
+
+    // default ranges
+    let out = chart(&["--chart", "-g", "N2"]).unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines.len(), 22);
+    assert_eq!(lines[0].split('\t').count(), 12);
+
+    // a generic fluid has the Z of any gas of same acentric factor in reduced coordinates
+    let n2 = find_gas("N2").unwrap();
+    let w = n2.w.unwrap().to_string();
+    let generic = chart(&["--chart", "--acentric", &w, "-e", "SRK"]).unwrap();
+    let gas = chart(&["--chart", "-g", "N2", "-e", "SRK"]).unwrap();
+    for (lg, ln) in generic.lines().zip(gas.lines()).skip(1) {
+        for (zg, zn) in lg.split('\t').zip(ln.split('\t')).skip(1) {
+            let (zg, zn): (f64, f64) = (zg.parse().unwrap(), zn.parse().unwrap());
+            assert!((zg - zn).abs() < 1e-9);
+        }
+    }
+
+    assert!(chart(&["--chart"]).is_err());
+    assert!(chart(&["--chart", "--acentric", "0", "-p", "-1:1"]).is_err());
+}
+
+#[test]
+fn test_assume_ideal_below() {
+    // Pr of N2 at 1 bar is about 0.03
+    let args = [
+        "-g",
+        "N2",
+        "-p",
+        "1",
+        "-t",
+        "20",
+        "--assume-ideal-below",
+        "0.1",
+    ];
+    assert_eq!(run(&args).unwrap(), "1\n");
+
+    let args = [
+        "-g",
+        "N2",
+        "-p",
+        "100",
+        "-t",
+        "20",
+        "--assume-ideal-below",
+        "0.1",
+    ];
+    let z: f64 = run(&args).unwrap().trim().parse().unwrap();
+    assert_eq!(
+        z,
+        rkz::compute_z(
+            &Gas::from_string("N2").unwrap(),
+            Eos::RedlichKwong,
+            Pascal(100e5),
+            Kelvin(293.15)
+        )
+        .unwrap()
+    );
+
+    let out = run(&[
+        "-g",
+        "N2",
+        "-p",
+        "1:100:99",
+        "-t",
+        "20",
+        "--assume-ideal-below",
+        "0.1",
+    ])
+    .unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines[1], "1\t1");
+    assert_ne!(lines[2], "100\t1");
+
+    assert!(run(&[
+        "-g",
+        "N2",
+        "-p",
+        "1",
+        "-t",
+        "20",
+        "--assume-ideal-below",
+        "0"
+    ])
+    .is_err());
+}
+
+#[test]
+fn test_hvap() {
+    let hvap = |args: &[&str]| run_with(process_saturation, args);
+    let out = hvap(&["-g", "N2", "-t", "-195.8", "--hvap", "-e", "PR"]).unwrap();
+    let value: f64 = out.trim().parse().unwrap();
+    assert!((value - 5570f64).abs() < 300f64);
+
+    // the critical temperature of N2 is -146.95°C
+    let out = hvap(&["-g", "N2", "-t", "-190:-140:10", "--hvap"]).unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines[0], "T[°C]\tHvap[J/mol]");
+    assert_eq!(lines.len(), 6);
+    assert!(lines[5].starts_with("-150\t"));
+
+    assert!(hvap(&["-g", "N2", "-t", "-140", "--hvap"]).is_err());
+    assert!(hvap(&["-g", "N2+O2", "-t", "-190", "--hvap"]).is_err());
+}
+
+#[test]
+fn test_sat_vapor_density() {
+    let density = |args: &[&str]| run_with(process_saturation, args);
+    // about 4.6 kg/m3 at the normal boiling point of N2
+    let out = density(&[
+        "-g",
+        "N2",
+        "-t",
+        "-195.8",
+        "--sat-vapor-density",
+        "-e",
+        "PR",
+    ])
+    .unwrap();
+    let value: f64 = out.trim().parse().unwrap();
+    assert!((value - 4.6f64).abs() < 0.5f64, "{}", value);
+
+    let out = density(&["-g", "N2", "-t", "-190:-150:10", "--sat-vapor-density"]).unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines[0], "T[°C]\trho_vapor[kg/m3]");
+    assert_eq!(lines.len(), 6);
+    let values: Vec<f64> = lines[1..]
+        .iter()
+        .map(|l| l.split('\t').nth(1).unwrap().parse().unwrap())
+        .collect();
+    assert!(values.windows(2).all(|w| w[0] < w[1]));
+
+    assert!(density(&["-g", "N2", "-t", "-140", "--sat-vapor-density"]).is_err());
+    assert!(density(&["-g", "N2+O2", "-t", "-190", "--sat-vapor-density"]).is_err());
+}
+
+#[test]
+fn test_color() {
+    let args = ["-g", "N2", "-p", "1:200:100", "-t", "20", "--color"];
+    let never = run(&[&args[..], &["never"]].concat()).unwrap();
+    assert!(!never.contains('\x1b'));
+    // not a terminal: STDOUT_IS_TERMINAL is only set by main
+    assert_eq!(run(&[&args[..], &["auto"]].concat()).unwrap(), never);
+
+    let always = run(&[&args[..], &["always"]].concat()).unwrap();
+    assert!(always.contains("\x1b[32m"));
+    assert_eq!(
+        always
+            .replace("\x1b[32m", "")
+            .replace("\x1b[33m", "")
+            .replace("\x1b[0m", ""),
+        never
+    );
+
+    // only Z-factors are colored
+    let out = run(&[
+        "-g", "N2", "-p", "1", "-t", "20", "--gamma", "--color", "always",
+    ])
+    .unwrap();
+    assert!(!out.contains('\x1b'));
+}
+
+#[test]
+fn test_sort_components() {
+    let dry_run = |spec| {
+        run(&[
+            "-g",
+            spec,
+            "-p",
+            "1",
+            "-t",
+            "20",
+            "--dry-run",
+            "--sort-components",
+        ])
+        .unwrap()
+        .replace(spec, "")
+    };
+    assert_eq!(dry_run("O2+20%CO2+N2"), dry_run("N2+O2+20%CO2"));
+    assert!(dry_run("O2+20%CO2+N2").contains("N2 0.40, O2 0.40, CO2 0.20"));
+}
+
+#[test]
+fn test_fraction_precision() {
+    let args = [
+        "-g",
+        "N2+O2+CO2",
+        "-p",
+        "1",
+        "-t",
+        "20",
+        "--fraction-precision",
+    ];
+    let run_with = |decimals| run(&[&args[..], &[decimals]].concat());
+    assert!(run_with("15").is_ok());
+    for decimals in ["16", "400", "x"].iter() {
+        match run_with(decimals) {
+            Err(Error::Usage(msg)) => assert!(msg.contains("from 0 to 15"), "{}", msg),
+            res => panic!("expected a usage error, got {:?}", res),
+        }
+    }
+}
+
+#[test]
+fn test_gas_list() {
+    let mut out = Vec::new();
+    write_gas_list(GASES, &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("\n    ID        Name\n"));
+    assert!(out.contains("\n    N2        Nitrogen\n"));
+
+    let long = PureGas {
+        id: "C6H5CH2CH3",
+        name: "Ethylbenzene",
+        ..PureGas::generic(0.3)
+    };
+    let mut out = Vec::new();
+    write_gas_list(&[GASES[0], long], &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("\n    C6H5CH2CH3  Ethylbenzene\n"));
+    assert!(out.contains("\n    Ar          Argon\n"));
+}
+
+#[test]
+fn test_gas_card() {
+    let card = |id| {
+        let mut out = Vec::new();
+        write_gas_card(find_gas(id).unwrap(), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    };
+    let co2 = card("CO2");
+    assert!(co2.starts_with("CO2 - Carbon dioxide\n"));
+    assert!(co2.contains("    critical temperature  304.1 K (30.95°C)\n"));
+    assert!(co2.contains("    critical pressure     73.8 bar\n"));
+    assert!(co2.contains("    molar mass            44.010 g/mol\n"));
+    assert!(co2.contains("    recommended EOS       PR (Peng-Robinson)\n"));
+    assert!(co2.contains("note: condensable at ambient temperature: the volume translation"));
+    assert!(!co2.contains("hydrogen bonds"));
+
+    assert!(card("H2O").contains("note: forms hydrogen bonds"));
+    let he = card("He");
+    assert!(he.contains("recommended EOS       RK"));
+    assert!(!he.contains("volume translation"));
+}
+
+#[test]
+fn test_units() {
+    let n2 = Gas::from_string("N2").unwrap();
+    let z = |p, t| rkz::compute_z(&n2, Eos::RedlichKwong, Pascal(p), Kelvin(t)).unwrap();
+    let value = |args: &[&str]| -> f64 { run(args).unwrap().trim().parse().unwrap() };
+
+    let args = [
+        "-g",
+        "N2",
+        "-p",
+        "200",
+        "-t",
+        "300",
+        "--temperature-unit",
+        "K",
+    ];
+    assert_eq!(value(&args), z(200e5, 300f64));
+    let args = [
+        "-g",
+        "N2",
+        "-p",
+        "200",
+        "-t",
+        "68",
+        "--temperature-unit",
+        "f",
+    ];
+    assert!((value(&args) - z(200e5, 293.15)).abs() < 1e-12);
+    let args = ["-g", "N2", "-p", "20", "-t", "20", "--pressure-unit", "MPa"];
+    assert_eq!(value(&args), z(200e5, 293.15));
+    let args = [
+        "-g",
+        "N2",
+        "-p",
+        "100",
+        "-t",
+        "20",
+        "--pressure-unit",
+        "psi",
+    ];
+    assert!((value(&args) - z(689475.7293168361, 293.15)).abs() < 1e-12);
+
+    // the relative offset is still in hPa
+    let args = [
+        "-g",
+        "N2",
+        "-p",
+        "0",
+        "-t",
+        "20",
+        "--pressure-unit",
+        "kPa",
+        "-r",
+        "1000",
+    ];
+    assert!((value(&args) - z(1e5, 293.15)).abs() < 1e-12);
+
+    let out = run(&[
+        "-g",
+        "N2",
+        "-p",
+        "1:2",
+        "-t",
+        "300",
+        "--temperature-unit",
+        "K",
+        "-r",
+        "stdatm",
+        "--pressure-unit",
+        "atm",
+    ])
+    .unwrap();
+    assert_eq!(out.lines().next().unwrap(), "P[atmG] \\ T[K]\t300 K");
+}
+
+#[test]
+fn test_summary() {
+    let mut summary = Summary::default();
+    for &(z, p, t, eos) in [
+        (1f64, 1f64, 0f64, "PR"),
+        (0.5, 50f64, 0f64, "RK"),
+        (1.25, 20f64, 40f64, "PR"),
+        // ties keep the first extreme
+        (0.5, 20f64, 20f64, "PR"),
+    ]
+    .iter()
+    {
+        summary.add(z, p, t, eos);
+    }
+    let (min, max) = (summary.min.as_ref().unwrap(), summary.max.as_ref().unwrap());
+    assert_eq!((min.value, min.p, min.t, min.eos), (0.5, 50f64, 0f64, "RK"));
+    assert_eq!(
+        (max.value, max.p, max.t, max.eos),
+        (1.25, 20f64, 40f64, "PR")
+    );
+    assert_eq!(summary.mean(), Some(0.8125));
+
+    let cond = Conditions {
+        temperature: Range::try_from("0:40:3n").unwrap(),
+        pressure: Range::try_from("1:50:4n").unwrap(),
+        relative: None,
+        strict: false,
+        reduced: None,
+        t_unit: TemperatureUnit::Celsius,
+        p_unit: PressureUnit::Bar,
+    };
+    let mut footer = Vec::new();
+    summary
+        .write(Quantity::Z, &cond, true, &mut footer)
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(footer).unwrap(),
+        "min Z = 0.5 at 50 bar, 0°C (RK)\nmax Z = 1.25 at 20 bar, 40°C (PR)\nmean Z = 0.8125 over 4 values\n"
+    );
+
+    let mut footer = Vec::new();
+    Summary::default()
+        .write(Quantity::Z, &cond, false, &mut footer)
+        .unwrap();
+    assert_eq!(String::from_utf8(footer).unwrap(), "summary: no values\n");
+}
+
+#[test]
+fn test_root_selection() {
+    // CO2 liquid-vapor region: three roots
+    let co2 = Gas::from_string("CO2").unwrap();
+    let roots = co2
+        .z_roots(Eos::PengRobinson, Pascal(20e5), Kelvin(260f64))
+        .unwrap();
+    assert_eq!(roots.len(), 3);
+    for (root, expected) in ["min", "middle", "max"].iter().zip(roots.iter()) {
+        let out = run(&[
+            "-g", "CO2", "-e", "PR", "-p", "20", "-t", "-13.15", "--root", root,
+        ])
+        .unwrap();
+        let z: f64 = out.trim().parse().unwrap();
+        assert!(
+            (z - expected).abs() < 1e-12,
+            "{}: {} != {}",
+            root,
+            z,
+            expected
+        );
+    }
+    // supercritical: a single root
+    let min = run(&["-g", "N2", "-p", "200", "-t", "20", "--root", "min"]).unwrap();
+    let max = run(&["-g", "N2", "-p", "200", "-t", "20", "--root", "max"]).unwrap();
+    assert_eq!(min, max);
+    match run(&["-g", "N2", "-p", "200", "-t", "20", "--root", "middle"]) {
+        Err(Error::Compute(msg)) => assert!(msg.contains("no middle root"), "{}", msg),
+        res => panic!("expected a computation error, got {:?}", res.map(|_| ())),
+    }
+
+    // --phase: at 20 bar and -13.15°C, below the saturation pressure, the vapor is stable
+    let phase = |phase| -> f64 {
+        run(&[
+            "-g", "CO2", "-e", "PR", "-p", "20", "-t", "-13.15", "--phase", phase,
+        ])
+        .unwrap()
+        .trim()
+        .parse()
+        .unwrap()
+    };
+    assert_eq!(phase("liquid"), roots[0]);
+    assert_eq!(phase("vapor"), roots[2]);
+    assert_eq!(phase("gibbs"), roots[2]);
+}
+
+#[test]
+fn test_liquid_density() {
+    let co2 = Gas::from_string("CO2").unwrap();
+    let out = run(&[
+        "-g",
+        "CO2",
+        "-e",
+        "PR",
+        "-p",
+        "20",
+        "-t",
+        "-13.15",
+        "--liquid-density",
+    ])
+    .unwrap();
+    let rho: f64 = out.trim().parse().unwrap();
+    let expected = co2
+        .liquid_density(Eos::PengRobinson, Pascal(20e5), Kelvin(260f64))
+        .unwrap();
+    assert!((rho - expected).abs() < 1e-9 * expected);
+    assert!(rho > 900f64 && rho < 1200f64, "{}", rho);
+
+    // the translated equation with --volume-shift
+    let out = run(&[
+        "-g",
+        "CO2",
+        "-e",
+        "PR",
+        "--volume-shift",
+        "constant",
+        "-p",
+        "20",
+        "-t",
+        "-13.15",
+        "--liquid-density",
+    ])
+    .unwrap();
+    let expected = co2
+        .liquid_density(Eos::PrPeneloux, Pascal(20e5), Kelvin(260f64))
+        .unwrap();
+    assert!((out.trim().parse::<f64>().unwrap() - expected).abs() < 1e-9 * expected);
+
+    let out = run(&[
+        "-g",
+        "C4H10",
+        "-p",
+        "50",
+        "-t",
+        "-20:30:10",
+        "--liquid-density",
+    ])
+    .unwrap();
+    // a row per pressure, a column per temperature
+    let densities: Vec<f64> = out
+        .lines()
+        .nth(1)
+        .unwrap()
+        .split('\t')
+        .skip(1)
+        .map(|v| v.parse().unwrap())
+        .collect();
+    assert_eq!(densities.len(), 6);
+    assert!(densities.windows(2).all(|w| w[1] < w[0]));
+
+    match run(&[
+        "-g",
+        "crit:Tc=500,Pc=30e5,w=0.2",
+        "-p",
+        "50",
+        "-t",
+        "20",
+        "--liquid-density",
+    ]) {
+        Err(Error::Compute(msg)) => assert!(msg.contains("molar mass"), "{}", msg),
+        res => panic!("expected a computation error, got {:?}", res.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_set_w() {
+    let z = |args: &[&str]| -> f64 { run(args).unwrap().trim().parse().unwrap() };
+    for &(eos, changed) in [("PR", true), ("SRK", true), ("VdW", false), ("RK", false)].iter() {
+        let args = ["-g", "78%N2+21%O2+Ar", "-p", "200", "-t", "20", "-e", eos];
+        let base = z(&args);
+        let overridden = z(&[&args[..], &["--set-w", "n2=0.2", "--set-w", "Ar=0.1"]].concat());
+        if changed {
+            // above the critical temperature, a higher acentric factor lowers a(T)
+            assert!(overridden > base, "{}: {} <= {}", eos, overridden, base);
+        } else {
+            assert_eq!(overridden, base);
+        }
+    }
+
+    // the modes without the pressure table take the override as well
+    let args = ["-g", "CO2", "-t", "0", "--saturation", "-e", "PR"];
+    let psat = |args: &[&str]| -> f64 {
+        let out = run_with(process_saturation, args).unwrap();
+        out.lines()
+            .nth(1)
+            .unwrap()
+            .split('\t')
+            .nth(1)
+            .unwrap()
+            .parse()
+            .unwrap()
+    };
+    // a higher acentric factor lowers the vapor pressure
+    assert!(psat(&[&args[..], &["--set-w", "CO2=0.3"]].concat()) < psat(&args));
+    let args = ["-g", "CO2", "--boyle", "-e", "PR"];
+    assert_ne!(
+        run_with(
+            process_boyle,
+            &[&args[..], &["--set-w", "CO2=0.3"]].concat()
+        )
+        .unwrap(),
+        run_with(process_boyle, &args).unwrap()
+    );
+
+    let err = |arg| run(&["-g", "N2", "-p", "1", "-t", "20", "--set-w", arg]).unwrap_err();
+    assert!(matches!(err("N2"), Error::Usage(_)));
+    assert!(matches!(err("N2=5"), Error::Usage(_)));
+    assert!(matches!(err("XX=0.1"), Error::Parse(_)));
+}
+
+#[test]
+fn test_viscosity() {
+    let args = ["-g", "C2H6", "-p", "50", "-t", "100", "--viscosity"];
+    let mu: f64 = run(&args).unwrap().trim().parse().unwrap();
+    // ethane, a component of natural gases, in the domain of the correlation
+    assert!(mu > 10f64 && mu < 16f64, "{}", mu);
+    assert_eq!(
+        run(&[&args[..], &["--annotate"]].concat()).unwrap(),
+        format!("mu(50 bar, 100°C) = {} µPa.s\n", mu)
+    );
+}
+
+#[test]
+fn test_gradient() {
+    let out = run(&["-g", "N2", "-p", "1:3", "-t", "20:21", "--gradient"]).unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines[0], "P[bar]\tT[°C]\tZ\tdZ/dP[1/bar]\tdZ/dT[1/°C]");
+    assert_eq!(lines.len(), 7);
+    let rows: Vec<Vec<f64>> = lines[1..]
+        .iter()
+        .map(|l| l.split('\t').map(|c| c.parse().unwrap()).collect())
+        .collect();
+    assert_eq!(&rows[0][..2], &[1f64, 20f64]);
+    assert_eq!(&rows[5][..2], &[3f64, 21f64]);
+    for row in rows.iter() {
+        // nitrogen at ambient temperature is more compressible than an ideal gas,
+        // less and less with the temperature
+        assert!(row[3] < 0f64 && row[4] > 0f64, "{:?}", row);
+    }
+    // close to the central difference in pressure on the middle row, per bar
+    let central = (rows[4][2] - rows[0][2]) / 2f64;
+    assert!((rows[2][3] / central - 1f64).abs() < 1e-3, "{}", central);
+
+    // a single condition
+    let out = run(&["-g", "N2", "-p", "2", "-t", "20", "--gradient"]).unwrap();
+    assert_eq!(out.lines().nth(1), lines.get(3).copied());
+}
+
+#[test]
+fn test_coeffs() {
+    let value = |out: &str, name: &str| -> f64 {
+        out.lines()
+            .find_map(|l| l.strip_prefix(&format!("{:8}", name)))
+            .unwrap()
+            .parse()
+            .unwrap()
+    };
+    for &eos in ["PR", "SRKP"].iter() {
+        let out = run(&["-g", "CO2", "-p", "50", "-t", "20", "--coeffs", "-e", eos]).unwrap();
+        let coeffs: Vec<f64> = ["a3", "a2", "a1", "a0"]
+            .iter()
+            .map(|c| value(&out, c))
+            .collect();
+        let z = value(&out, "Z");
+        let shift = if eos == "SRKP" {
+            value(&out, "shift")
+        } else {
+            0f64
+        };
+        let roots = match roots::find_roots_cubic(coeffs[0], coeffs[1], coeffs[2], coeffs[3]) {
+            roots::Roots::One(r) => r.to_vec(),
+            roots::Roots::Two(r) => r.to_vec(),
+            roots::Roots::Three(r) => r.to_vec(),
+            _ => panic!("no root"),
+        };
+        let vapor = roots.iter().cloned().fold(f64::MIN, f64::max);
+        assert!((vapor - shift - z).abs() < 1e-12, "{}", out);
+        assert!(value(&out, "A") > 0f64 && value(&out, "B") > 0f64);
+    }
+    assert!(run(&["-g", "CO2", "-p", "50:60", "-t", "20", "--coeffs"]).is_err());
+}
+
+#[test]
+fn test_properties() {
+    let out = run(&[
+        "-g",
+        "N2",
+        "-p",
+        "1",
+        "-t",
+        "26.85",
+        "--properties",
+        "-e",
+        "PR",
+    ])
+    .unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines.len(), 10);
+    let z: f64 = lines[0].strip_prefix("Z").unwrap().trim().parse().unwrap();
+    assert!((z - 1f64).abs() < 1e-3);
+    let volume = lines[1].strip_prefix("molar volume").unwrap().trim();
+    let volume: f64 = volume.strip_suffix(" cm3/mol").unwrap().parse().unwrap();
+    // RT/P
+    assert!((volume - 24_943f64).abs() < 30f64, "{}", volume);
+    assert!(lines[2].starts_with("density         1.12"));
+    assert!(lines[8].starts_with("speed of sound  35"));
+
+    let out = run(&[
+        "-g",
+        "crit:Tc=126.2,Pc=33.9e5,w=0.039",
+        "-p",
+        "1",
+        "-t",
+        "20",
+        "--properties",
+    ])
+    .unwrap();
+    assert!(out.contains("density         n/a (unknown molar mass)"));
+    assert!(out.contains("Cp/Cv           n/a (unknown ideal gas heat capacity)"));
+
+    let out = run(&[
+        "-g",
+        "N2",
+        "-p",
+        "1",
+        "-t",
+        "20",
+        "--properties",
+        "-e",
+        "PR",
+        "-e",
+        "RK",
+    ])
+    .unwrap();
+    assert!(out.starts_with("[PR]\nZ "));
+    assert!(out.contains("\n\n[RK]\nZ "));
+    assert!(run(&["-g", "N2", "-p", "1:10", "-t", "20", "--properties"]).is_err());
+}
+
+#[test]
+fn test_path() {
+    let args = [
+        "-g",
+        "N2",
+        "-p",
+        "10",
+        "-t",
+        "26.85",
+        "--target-pressure",
+        "1",
+    ];
+    let out = run(&[&args[..], &["--path", "isentropic", "-e", "PR"]].concat()).unwrap();
+    let values: Vec<f64> = out.trim().split('\t').map(|v| v.parse().unwrap()).collect();
+    assert_eq!(values.len(), 2);
+    // 155 K for an ideal gas
+    assert!((values[0] + 118f64).abs() < 5f64, "{}", values[0]);
+    assert!(values[1] < 1f64 && values[1] > 0.99);
+
+    let out = run(&[
+        &args[..],
+        &["--path", "isenthalpic", "-e", "PR", "-e", "RK"],
+    ]
+    .concat())
+    .unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("PR\t"));
+    assert!(lines[1].starts_with("RK\t"));
+
+    assert!(run(&[
+        "-g",
+        "N2",
+        "-p",
+        "1:10",
+        "-t",
+        "20",
+        "--path",
+        "isentropic",
+        "--target-pressure",
+        "1"
+    ])
+    .is_err());
+}
+
+#[test]
+fn test_sensitivity() {
+    let values = |out: &str| -> Vec<(String, f64, f64)> {
+        out.lines()
+            .map(|l| {
+                let v: Vec<&str> = l.split('\t').collect();
+                (
+                    v[0].to_string(),
+                    v[1].parse().unwrap(),
+                    v[2].parse().unwrap(),
+                )
+            })
+            .collect()
+    };
+    // ethanol vapor (w = 0.644)
+    let args = [
+        "-g",
+        "C2H5OH",
+        "-p",
+        "20",
+        "-t",
+        "200",
+        "-e",
+        "RK",
+        "-e",
+        "PR",
+        "--sensitivity",
+        "w",
+    ];
+    let lines = values(&run(&args).unwrap());
+    assert_eq!(lines.len(), 2);
+    let gas = Gas::from_string("C2H5OH").unwrap();
+    let (p, t) = (20e5, 473.15);
+    let (rk, pr) = (&lines[0], &lines[1]);
+    assert_eq!(rk.0, "RK");
+    assert_eq!(
+        rk.1,
+        gas.z(Eos::RedlichKwong, Pascal(p), Kelvin(t)).unwrap()
+    );
+    assert_eq!(rk.2, 0f64);
+    assert_eq!(pr.0, "PR");
+    assert_eq!(
+        pr.1,
+        gas.z(Eos::PengRobinson, Pascal(p), Kelvin(t)).unwrap()
+    );
+    // a higher acentric factor strengthens the attraction below Tc
+    assert!(pr.2 < -0.01, "{}", pr.2);
+
+    // converges with the offset
+    let fine = run(&[
+        "-g",
+        "C2H5OH",
+        "-p",
+        "20",
+        "-t",
+        "200",
+        "-e",
+        "PR",
+        "--sensitivity",
+        "w",
+        "--sensitivity-delta",
+        "0.001",
+    ])
+    .unwrap();
+    let fine: f64 = fine.trim().split('\t').nth(1).unwrap().parse().unwrap();
+    assert!((fine - pr.2).abs() < 1e-3 * pr.2.abs());
+
+    assert!(run(&["-g", "N2", "-p", "1:10", "-t", "20", "--sensitivity", "w"]).is_err());
+}
+
+#[test]
+fn test_partial_pressures() {
+    let out = run(&[
+        "-g",
+        "78%N2+21%O2+Ar",
+        "-p",
+        "200",
+        "-t",
+        "20",
+        "--partial-pressures",
+    ])
+    .unwrap();
+    let rows: Vec<Vec<&str>> = out.lines().map(|l| l.split('\t').collect()).collect();
+    let ids: Vec<&str> = rows.iter().map(|r| r[0]).collect();
+    assert_eq!(ids, ["N2", "O2", "Ar"]);
+    let pis: Vec<f64> = rows.iter().map(|r| r[1].parse().unwrap()).collect();
+    assert!((pis.iter().sum::<f64>() - 200f64).abs() < 1e-9);
+    assert!((pis[0] - 156f64).abs() < 1e-9);
+    // the fugacities deviate from the partial pressures at 200 bar
+    for r in rows.iter() {
+        let (pi, fi): (f64, f64) = (r[1].parse().unwrap(), r[2].parse().unwrap());
+        assert!((fi / pi - 1f64).abs() > 1e-3 && (fi / pi - 1f64).abs() < 0.2);
+    }
+
+    let out = run(&[
+        "-g",
+        "N2+O2",
+        "-p",
+        "1",
+        "-t",
+        "20",
+        "--pressure-unit",
+        "MPa",
+        "--partial-pressures",
+    ])
+    .unwrap();
+    assert!(out.lines().all(|l| l.split('\t').nth(1) == Some("0.5")));
+
+    assert!(run(&["-g", "N2", "-p", "200", "-t", "20", "--partial-pressures"]).is_err());
+    assert!(run(&[
+        "-g",
+        "N2+O2",
+        "-p",
+        "1:200",
+        "-t",
+        "20",
+        "--partial-pressures"
+    ])
+    .is_err());
+}
+
+#[test]
+fn test_volume_shift() {
+    let run_z = |args: &[&str]| -> f64 { run(args).unwrap().trim().parse().unwrap() };
+    let args = ["-g", "CO2", "-p", "100", "-t", "10"];
+    let with = |extra: &[&str]| {
+        let mut v = args.to_vec();
+        v.extend_from_slice(extra);
+        run_z(&v)
+    };
+    assert_eq!(
+        with(&["-e", "PR", "--volume-shift", "constant"]),
+        with(&["-e", "PRP"])
+    );
+    assert_eq!(
+        with(&["-e", "PR", "--volume-shift", "temperature-dependent"]),
+        with(&["-e", "PRPT"])
+    );
+    assert_eq!(
+        with(&["-e", "SRK", "--volume-shift", "constant"]),
+        with(&["-e", "SRKP"])
+    );
+    assert_ne!(with(&["-e", "PRPT"]), with(&["-e", "PR"]));
+
+    let err = run(&[
+        "-g",
+        "CO2",
+        "-p",
+        "100",
+        "-t",
+        "10",
+        "-e",
+        "RK",
+        "--volume-shift",
+        "constant",
+    ]);
+    assert!(err.is_err());
+}
+
+#[test]
+fn test_single_condition() {
+    let single = run(&["-g", "N2", "-p", "200", "-t", "20"]).unwrap();
+    assert_eq!(single.lines().count(), 1);
+
+    // degenerate range and scalar
+    let out = run(&["-g", "N2", "-p", "200:200", "-t", "20"]).unwrap();
+    assert_eq!(out, single);
+    let out = run(&["-g", "N2", "-p", "200", "-t", "20:20:5"]).unwrap();
+    assert_eq!(out, single);
+
+    // a single pressure left once the non-positive ones are skipped
+    let args = ["-g", "N2", "-p", "-2:0:2", "-r", "stdatm", "-t", "20"];
+    let out = run(&args).unwrap();
+    assert_eq!(out.lines().count(), 1);
+    assert_eq!(
+        out,
+        run(&["-g", "N2", "-p", "0", "-r", "stdatm", "-t", "20"]).unwrap()
+    );
+
+    // unless a table is forced
+    let out = run(&["-g", "N2", "-p", "200:200", "-t", "20", "--force-table"]).unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0], "P[bar] \\ T[°C]\t20°C");
+    assert_eq!(lines[1], format!("200\t{}", single.trim()));
+}
+
+#[test]
+fn test_transpose() {
+    let args = [
+        "-g",
+        "N2",
+        "-p",
+        "100:200:100",
+        "-t",
+        "0:40:20",
+        "-r",
+        "stdatm",
+    ];
+    let table = |out: String| -> Vec<Vec<String>> {
+        out.lines()
+            .map(|l| l.split('\t').map(|c| c.to_string()).collect())
+            .collect()
+    };
+    let normal = table(run(&args).unwrap());
+    let mut transposed_args = args.to_vec();
+    transposed_args.push("--transpose");
+    let transposed = table(run(&transposed_args).unwrap());
+
+    // 2 pressures x 3 temperatures
+    assert_eq!((normal.len(), normal[0].len()), (3, 4));
+    assert_eq!((transposed.len(), transposed[0].len()), (4, 3));
+    assert_eq!(normal[0][0], "P[barG] \\ T[°C]");
+    assert_eq!(transposed[0][0], "T[°C] \\ P[barG]");
+    assert_eq!(transposed[0][1..], ["100 barG", "200 barG"]);
+    assert_eq!(
+        transposed[1..]
+            .iter()
+            .map(|r| r[0].as_str())
+            .collect::<Vec<_>>(),
+        ["0", "20", "40"]
+    );
+    for i in 1..3 {
+        for j in 1..4 {
+            assert_eq!(normal[i][j], transposed[j][i]);
+        }
+    }
+
+    transposed_args.push("--bare-header");
+    let bare = table(run(&transposed_args).unwrap());
+    assert_eq!(bare[0], ["T \\ P", "100", "200"]);
+}
+
+#[test]
+fn test_benchmark_eos() {
+    let matches = app().get_matches_from(["rkz", "--benchmark-eos", "-g", "N2"]);
+    let mut out = Vec::new();
+    process_benchmark(&matches, &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines.len(), Eos::all().len());
+    for (line, eos) in lines.iter().zip(Eos::all()) {
+        let cols: Vec<&str> = line.split('\t').collect();
+        assert_eq!(cols[0], eos.as_abbrev());
+        let ns: f64 = cols[1].trim_end_matches(" ns/call").parse().unwrap();
+        assert!(ns >= 0f64);
+    }
+}
+
+#[test]
+fn test_table_format() {
+    let out = run(&[
+        "-g",
+        "N2",
+        "-p",
+        "1:2",
+        "-t",
+        "20:21",
+        "--format",
+        "table",
+        "--precision",
+        "4",
+    ])
+    .unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines.len(), 6);
+    let cells = |line: &str| -> Vec<String> {
+        line.trim_matches('│')
+            .split('│')
+            .map(|c| c.trim().to_string())
+            .collect()
+    };
+    assert_eq!(cells(lines[1]), ["P[bar] \\ T[°C]", "20°C", "21°C"]);
+    assert_eq!(cells(lines[3])[0], "1");
+    assert_eq!(cells(lines[4])[0], "2");
+    assert!(lines[0].starts_with('┌') && lines[2].starts_with('├') && lines[5].starts_with('└'));
+
+    // the column separators are at the same positions on every line
+    let separators = |line: &str| -> Vec<usize> {
+        line.chars()
+            .enumerate()
+            .filter(|&(_, c)| "┌┬┐│├┼┤└┴┘".contains(c))
+            .map(|(i, _)| i)
+            .collect()
+    };
+    for line in lines.iter() {
+        assert_eq!(separators(line), separators(lines[0]));
+    }
+    // numbers aligned to the right
+    assert!(lines[3].starts_with("│              1 │ 0.99"));
+}
+
+#[test]
+fn test_jsonl_format() {
+    let out = run(&[
+        "-g",
+        "N2",
+        "-p",
+        "100:200:100",
+        "-t",
+        "0:20:20",
+        "--format",
+        "jsonl",
+    ])
+    .unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert!(lines[0].starts_with(r#"{"p":100,"p_unit":"bar","t":0,"t_unit":"°C","z":"#));
+    assert!(lines[3].starts_with(r#"{"p":200,"p_unit":"bar","t":20,"t_unit":"°C","z":"#));
+    for line in lines.iter() {
+        let obj: serde_json::Value = serde_json::from_str(line).unwrap();
+        assert!(obj["z"].as_f64().unwrap() > 0.9);
+    }
+
+    // relative pressures in psi, one object per equation of state
+    let out = run(&[
+        "-g",
+        "N2",
+        "-p",
+        "100",
+        "-t",
+        "20",
+        "--pressure-unit",
+        "psi",
+        "--relative",
+        "stdatm",
+        "-e",
+        "PR",
+        "-e",
+        "RK",
+        "--format",
+        "jsonl",
+    ])
+    .unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines.len(), 2);
+    let obj: serde_json::Value = serde_json::from_str(lines[1]).unwrap();
+    assert_eq!(obj["p"], 100);
+    assert_eq!(obj["p_unit"], "psiG");
+    assert_eq!(obj["eos"], "RK");
+
+    let matches = app().get_matches_from(vec!["rkz", "--scientific"]);
+    let output = OutputOpts::from_matches(&matches).unwrap();
+    assert_eq!(output.format_json_value(1.5), "1.5e+00");
+    assert_eq!(output.format_json_value(f64::NAN), "null");
+    assert_eq!(output.format_json_value(f64::INFINITY), "null");
+}
+
+#[cfg(feature = "binary")]
+#[test]
+fn test_binary_format() {
+    let args = ["-g", "N2", "-e", "PR", "-p", "1:201:100", "-t", "0:50:50"];
+    let matches = app().get_matches_from(
+        std::iter::once(&"rkz")
+            .chain(args.iter())
+            .chain(["--format", "binary"].iter()),
+    );
+    let mut out = Vec::new();
+    process_args(&matches, &mut out).unwrap();
+    let grid = rkz::ZGrid::read_binary(&mut out.as_slice()).unwrap();
+    assert_eq!(grid.pressures(), &[1e5, 101e5, 201e5]);
+    assert_eq!(grid.temperatures(), &[273.15, 323.15]);
+
+    // same values as the text table
+    let text = run(&args).unwrap();
+    let row: Vec<f64> = text
+        .lines()
+        .nth(2)
+        .unwrap()
+        .split('\t')
+        .skip(1)
+        .map(|v| v.parse().unwrap())
+        .collect();
+    assert_eq!(grid.row(1), row.as_slice());
+
+    match run(&[
+        "-g",
+        "N2",
+        "-e",
+        "PR",
+        "-e",
+        "RK",
+        "-p",
+        "1:201:100",
+        "-t",
+        "0",
+        "--format",
+        "binary",
+    ]) {
+        Err(Error::Usage(msg)) => assert!(msg.contains("single equation of state"), "{}", msg),
+        res => panic!("expected a usage error, got {:?}", res.map(|_| ())),
+    }
+
+    // a scalar condition is not written as a table
+    match run(&["-g", "N2", "-p", "200", "-t", "20", "--format", "binary"]) {
+        Err(Error::Usage(msg)) => assert!(msg.contains("requires a range"), "{}", msg),
+        res => panic!("expected a usage error, got {:?}", res.map(|_| ())),
     }
+    let matches = app().get_matches_from([
+        "rkz", "-g", "N2", "-e", "PR", "--boyle", "--format", "binary",
+    ]);
+    let err = check_flags(&matches).unwrap_err();
+    assert!(err.contains("doesn't apply to --boyle"), "{}", err);
 }
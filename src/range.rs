@@ -0,0 +1,283 @@
+//! Ranges of pressures and temperatures of the command line
+use crate::util;
+use crate::ParseError;
+use std::convert::TryFrom;
+
+/// Range of values given as `start[:stop[:step]]` or `start:stop:Nn`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Range {
+    pub start: f64,
+    pub stop: f64,
+    /// Step between the values, also set for a number of points
+    pub step: f64,
+    pub spacing: Spacing,
+}
+
+/// How the values of a range are defined
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Spacing {
+    /// Explicit step, from start up to stop
+    Step,
+    /// Given number of evenly spaced values, including start and stop
+    Points(usize),
+}
+
+impl TryFrom<&str> for Range {
+    type Error = ParseError;
+
+    fn try_from(input: &str) -> Result<Range, ParseError> {
+        Range::parse(input).map_err(ParseError::Range)
+    }
+}
+
+impl Range {
+    fn parse(input: &str) -> Result<Range, String> {
+        let mut parts: Vec<&str> = input.split(':').collect();
+        // min:max:Nn
+        let points = match parts.as_slice() {
+            [_, _, points] if points.ends_with('n') => {
+                let n: usize = points[..points.len() - 1]
+                    .parse()
+                    .map_err(|_| format!("Can't parse \"{}\" as a number of points", points))?;
+                if n < 2 {
+                    return Err("Range must have at least 2 points".into());
+                }
+                parts.pop();
+                Some(n)
+            }
+            _ => None,
+        };
+        let v = {
+            let mut v: Vec<f64> = Vec::new();
+            for s in parts {
+                v.push(util::parse_num(s)?);
+            }
+            v
+        };
+
+        // start:start is a single value, whatever the step or number of points
+        if v.len() >= 2 && v[0] == v[1] {
+            return if v.len() == 3 && v[2] <= 0f64 {
+                Err("Range step must be positive".into())
+            } else {
+                Ok(Range::scalar(v[0]))
+            };
+        }
+
+        if let Some(n) = points {
+            let (start, stop) = (v[0], v[1]);
+            return if stop < start {
+                Err("Range stop must be higher than start".into())
+            } else {
+                Ok(Range {
+                    start,
+                    stop,
+                    step: (stop - start) / (n - 1) as f64,
+                    spacing: Spacing::Points(n),
+                })
+            };
+        }
+
+        match v.len() {
+            1 => Ok(Range::scalar(v[0])),
+            2 => {
+                let start = v[0];
+                let stop = v[1];
+                if stop < start {
+                    Err("Range stop must be higher than start".into())
+                } else {
+                    Ok(Range {
+                        start,
+                        stop,
+                        step: 1f64,
+                        spacing: Spacing::Step,
+                    })
+                }
+            }
+            3 => {
+                let start = v[0];
+                let stop = v[1];
+                let step = v[2];
+                if stop < start {
+                    Err("Range stop must be higher than start".into())
+                } else if step <= 0f64 {
+                    Err("Range step must be positive".into())
+                } else {
+                    Ok(Range {
+                        start,
+                        stop,
+                        step,
+                        spacing: Spacing::Step,
+                    })
+                }
+            }
+            _ => Err(format!("Can't parse \"{}\" as a range", input)),
+        }
+    }
+
+    /// Range of the single value `val`
+    pub fn scalar(val: f64) -> Range {
+        Range {
+            start: val,
+            stop: val,
+            step: 1f64,
+            spacing: Spacing::Step,
+        }
+    }
+
+    /// Restricts the range to the values between `min` and `max`, keeping the step grid.
+    /// Returns None if no value of the range is left.
+    pub fn clip(&self, min: f64, max: f64) -> Option<Range> {
+        let start = if self.start < min {
+            self.start + ((min - self.start) / self.step).ceil() * self.step
+        } else {
+            self.start
+        };
+        let stop = self.stop.min(max);
+        if start > stop {
+            None
+        } else {
+            // the clipped range keeps the step, but not necessarily the stop value
+            Some(Range {
+                start,
+                stop,
+                step: self.step,
+                spacing: Spacing::Step,
+            })
+        }
+    }
+
+    /// Whether the range holds a single value
+    pub fn is_scalar(&self) -> bool {
+        // start + step rounds to start for huge values
+        self.start + self.step > self.stop || self.start == self.stop
+    }
+
+    /// Number of values of the range, computed without iterating.
+    /// The rounding of the steps can make the iteration yield one value less.
+    pub fn num_values(&self) -> usize {
+        match self.spacing {
+            Spacing::Points(n) => n,
+            // saturates for absurd ranges
+            Spacing::Step => {
+                (((self.stop - self.start) / self.step).floor() as usize).saturating_add(1)
+            }
+        }
+    }
+
+    /// Iterator over the values of the range
+    pub fn iter(&self) -> ScalarIt {
+        ScalarIt {
+            cur: self.start,
+            stop: self.stop,
+            step: self.step,
+            remaining: match self.spacing {
+                Spacing::Step => None,
+                Spacing::Points(n) => Some(n),
+            },
+        }
+    }
+}
+
+/// Iterator over the values of a `Range`
+pub struct ScalarIt {
+    cur: f64,
+    stop: f64,
+    step: f64,
+    /// Number of values left, if given by the range
+    remaining: Option<usize>,
+}
+
+impl Iterator for ScalarIt {
+    type Item = f64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.remaining {
+            Some(0) => return None,
+            // the last value is exactly stop, whatever the rounding of the steps
+            Some(1) => {
+                self.remaining = Some(0);
+                return Some(self.stop);
+            }
+            Some(n) => {
+                self.remaining = Some(n - 1);
+                let res = self.cur;
+                self.cur += self.step;
+                return Some(res);
+            }
+            None => {}
+        }
+        if self.cur <= self.stop {
+            let res = self.cur;
+            // a step lost in the rounding of a huge value would never reach stop
+            let next = self.cur + self.step;
+            self.cur = if next > res { next } else { f64::INFINITY };
+            Some(res)
+        } else {
+            None
+        }
+    }
+}
+
+#[test]
+fn test_range_points() {
+    let range = Range::try_from("0:100:5n").unwrap();
+    assert_eq!(range.spacing, Spacing::Points(5));
+    assert!(!range.is_scalar());
+    let values: Vec<f64> = range.iter().collect();
+    assert_eq!(values, vec![0f64, 25f64, 50f64, 75f64, 100f64]);
+
+    // the last value isn't lost to rounding
+    let values: Vec<f64> = Range::try_from("0:1:11n").unwrap().iter().collect();
+    assert_eq!(values.len(), 11);
+    assert_eq!(values[10], 1f64);
+
+    assert_eq!(
+        Range::try_from("0:100:1n"),
+        Err(ParseError::Range(
+            "Range must have at least 2 points".into()
+        ))
+    );
+    assert!(Range::try_from("0:100:xn").is_err());
+    assert!(Range::try_from("100:0:5n").is_err());
+    assert!(Range::try_from("0:100:5n:1").is_err());
+}
+
+#[test]
+fn test_range_num_values() {
+    for input in &[
+        "0:100:5n",
+        "0:100",
+        "0:100:7",
+        "0:1:0.1",
+        "100",
+        "-40:80:2.5",
+    ] {
+        let range = Range::try_from(*input).unwrap();
+        assert_eq!(range.num_values(), range.iter().count(), "{}", input);
+    }
+    assert_eq!(Range::try_from("0:1e30").unwrap().num_values(), usize::MAX);
+}
+
+#[test]
+fn test_range_single_point() {
+    let range = Range::try_from("100:100").unwrap();
+    assert!(range.is_scalar());
+    assert_eq!(range.iter().collect::<Vec<f64>>(), vec![100f64]);
+    assert_eq!(range, Range::try_from("100").unwrap());
+
+    let range = Range::try_from("100:100:5").unwrap();
+    assert!(range.is_scalar());
+    assert_eq!(range.iter().collect::<Vec<f64>>(), vec![100f64]);
+    assert_eq!(Range::try_from("100:100:5n").unwrap(), range);
+
+    assert!(Range::try_from("100:100:-5").is_err());
+    assert!(Range::try_from("100:99").is_err());
+    assert!(Range::try_from("100:99:5").is_err());
+
+    // the step is lost in the rounding of huge values
+    let range = Range::try_from("1e17").unwrap();
+    assert!(range.is_scalar());
+    assert_eq!(range.iter().collect::<Vec<f64>>(), vec![1e17]);
+    assert_eq!(Range::try_from("1e17:2e17").unwrap().iter().count(), 1);
+}
@@ -0,0 +1,40 @@
+//! Settings and errors shared by the iterative solvers
+use std::fmt;
+
+/// Bounds of the iterative solvers
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SolverConfig {
+    /// Maximum number of iterations before giving up
+    pub max_iter: usize,
+    /// Relative tolerance on the solved quantity
+    pub tol: f64,
+}
+
+impl Default for SolverConfig {
+    fn default() -> Self {
+        SolverConfig {
+            max_iter: 1000,
+            tol: 1e-9,
+        }
+    }
+}
+
+/// Failure of an iterative solver
+#[derive(Clone, Debug, PartialEq)]
+pub enum SolverError {
+    /// The tolerance was not reached within `max_iter` iterations
+    NoConvergence { max_iter: usize },
+    /// The problem has no solution for the given input
+    Domain(String),
+}
+
+impl fmt::Display for SolverError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SolverError::NoConvergence { max_iter } => {
+                write!(f, "solver did not converge within {} iterations", max_iter)
+            }
+            SolverError::Domain(msg) => write!(f, "{}", msg),
+        }
+    }
+}
@@ -0,0 +1,86 @@
+//! Units of pressure and temperature.
+//! The state functions of the gases (`EosGas`, `Gas`, `z_grid`...) take and return
+//! `Pascal` and `Kelvin`. Below them, the parameters of the equations of state
+//! (a(T), c(T) and `EosParams`) compute with plain f64 in Pa and K, as do the fields
+//! of the result structs such as `Properties`, `Saturation` and `ZGrid`.
+
+/// Pressure in Pa
+#[derive(Copy, Clone, Debug, PartialEq, PartialOrd)]
+pub struct Pascal(pub f64);
+
+/// Pressure in bar
+#[derive(Copy, Clone, Debug, PartialEq, PartialOrd)]
+pub struct Bar(pub f64);
+
+/// Absolute temperature in K
+#[derive(Copy, Clone, Debug, PartialEq, PartialOrd)]
+pub struct Kelvin(pub f64);
+
+/// Temperature in °C
+#[derive(Copy, Clone, Debug, PartialEq, PartialOrd)]
+pub struct Celsius(pub f64);
+
+/// Temperature in °F
+#[derive(Copy, Clone, Debug, PartialEq, PartialOrd)]
+pub struct Fahrenheit(pub f64);
+
+/// Pascals in a bar
+const PA_PER_BAR: f64 = 100000f64;
+/// 0°C in K
+const ZERO_CELSIUS: f64 = 273.15;
+/// 0°C in °F
+const ZERO_CELSIUS_F: f64 = 32f64;
+/// °F in a °C (or K) interval
+const F_PER_C: f64 = 1.8;
+
+impl From<Bar> for Pascal {
+    fn from(p: Bar) -> Pascal {
+        Pascal(p.0 * PA_PER_BAR)
+    }
+}
+
+impl From<Pascal> for Bar {
+    fn from(p: Pascal) -> Bar {
+        Bar(p.0 / PA_PER_BAR)
+    }
+}
+
+impl From<Celsius> for Kelvin {
+    fn from(t: Celsius) -> Kelvin {
+        Kelvin(t.0 + ZERO_CELSIUS)
+    }
+}
+
+impl From<Kelvin> for Celsius {
+    fn from(t: Kelvin) -> Celsius {
+        Celsius(t.0 - ZERO_CELSIUS)
+    }
+}
+
+impl From<Fahrenheit> for Kelvin {
+    fn from(t: Fahrenheit) -> Kelvin {
+        Kelvin((t.0 - ZERO_CELSIUS_F) / F_PER_C + ZERO_CELSIUS)
+    }
+}
+
+impl From<Kelvin> for Fahrenheit {
+    fn from(t: Kelvin) -> Fahrenheit {
+        Fahrenheit((t.0 - ZERO_CELSIUS) * F_PER_C + ZERO_CELSIUS_F)
+    }
+}
+
+#[test]
+fn test_conversions() {
+    assert_eq!(Pascal::from(Bar(1.01325)), Pascal(101325f64));
+    assert_eq!(Bar::from(Pascal(200e5)), Bar(200f64));
+    assert_eq!(Kelvin::from(Celsius(20f64)), Kelvin(293.15));
+    assert_eq!(Celsius::from(Kelvin(273.15)), Celsius(0f64));
+    assert_eq!(Kelvin::from(Celsius(-273.15)), Kelvin(0f64));
+    assert_eq!(Kelvin::from(Fahrenheit(32f64)), Kelvin(273.15));
+    assert_eq!(Kelvin::from(Fahrenheit(212f64)), Kelvin(373.15));
+    assert_eq!(Fahrenheit::from(Kelvin(273.15)), Fahrenheit(32f64));
+    assert!((Fahrenheit::from(Kelvin(0f64)).0 + 459.67).abs() < 1e-9);
+    for &p in &[0f64, 1f64, 350.5] {
+        assert_eq!(Bar::from(Pascal::from(Bar(p))), Bar(p));
+    }
+}
@@ -1,5 +1,66 @@
 pub fn parse_num(input: &str) -> Result<f64, String> {
-    input
+    let num = input
         .parse::<f64>()
-        .map_err(|_| format!("Can't parse {} as a number", input))
+        .map_err(|_| format!("Can't parse {} as a number", input))?;
+    if num.is_finite() {
+        Ok(num)
+    } else {
+        Err(format!("{} is not a finite number", input))
+    }
+}
+
+/// Lines of a text file with their number (from 1), stripped of the '#' comments
+/// and of the surrounding whitespace, skipping the lines left blank
+pub fn content_lines(input: &str) -> impl Iterator<Item = (usize, &str)> + '_ {
+    input
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| line_content(line).map(|line| (i + 1, line)))
+}
+
+/// Line stripped of its '#' comment and of the surrounding whitespace, None if left blank.
+/// The filter of `content_lines`, for the lines read one at a time.
+pub fn line_content(line: &str) -> Option<&str> {
+    let line = match line.find('#') {
+        Some(pos) => &line[..pos],
+        None => line,
+    }
+    .trim();
+    if line.is_empty() {
+        None
+    } else {
+        Some(line)
+    }
+}
+
+#[test]
+fn test_parse_num() {
+    assert_eq!(parse_num("12.5"), Ok(12.5));
+    assert_eq!(parse_num("-40"), Ok(-40f64));
+    assert_eq!(parse_num("1e5"), Ok(100000f64));
+    assert!(parse_num("abc").is_err());
+    assert_eq!(
+        parse_num("inf"),
+        Err("inf is not a finite number".to_string())
+    );
+    assert!(parse_num("-inf").is_err());
+    assert!(parse_num("nan").is_err());
+    assert!(parse_num("1e400").is_err());
+}
+
+#[test]
+fn test_content_lines() {
+    let input = concat!(
+        "# header comment\n",
+        "\n",
+        "id,Tc  \n",
+        "   \t\n",
+        "  N2,126.2 # inline comment\n",
+        "    # indented comment\n",
+        "O2,154.6\r\n",
+        "#",
+    );
+    let lines: Vec<(usize, &str)> = content_lines(input).collect();
+    assert_eq!(lines, vec![(3, "id,Tc"), (5, "N2,126.2"), (7, "O2,154.6")]);
+    assert_eq!(content_lines("").count(), 0);
 }
@@ -0,0 +1,73 @@
+//! Gas viscosity estimated from the density of the equations of state
+use crate::units::{Bar, Celsius, Kelvin, Pascal};
+
+/// Temperature domain (min, max) of the Lee-Gonzalez-Eakin data (100 to 340°F)
+pub const LGE_VALID_T: (Kelvin, Kelvin) = (Kelvin(310.93), Kelvin(444.26));
+/// Pressure domain (min, max) of the Lee-Gonzalez-Eakin data (100 to 8000 psia)
+pub const LGE_VALID_P: (Pascal, Pascal) = (Pascal(689_476f64), Pascal(55_158_058f64));
+
+/// Viscosity in Pa.s of a gas of molar mass `molar_mass` in kg/mol and density `density`
+/// in kg/m3 at temperature `t`, with the correlation of Lee, Gonzalez and Eakin (1966).
+/// The correlation was fitted to natural gases: it is less accurate for other gases
+/// and out of `LGE_VALID_T` and `LGE_VALID_P`.
+pub fn lee_gonzalez_eakin(molar_mass: f64, density: f64, t: Kelvin) -> f64 {
+    // the correlation takes °R, g/mol and g/cm3 and gives cP
+    let t = 1.8 * t.0;
+    let m = molar_mass * 1000f64;
+    let rho = density / 1000f64;
+    let k = (9.4 + 0.02 * m) * t.powf(1.5) / (209f64 + 19f64 * m + t);
+    let x = 3.5 + 986f64 / t + 0.01 * m;
+    let y = 2.4 - 0.2 * x;
+    1e-4 * k * (x * rho.powf(y)).exp() * 1e-3
+}
+
+/// Notes about the conditions of `p_range` and `t_range` (min, max)
+/// out of the validity domain of the Lee-Gonzalez-Eakin correlation
+pub fn lge_warnings(p_range: (Pascal, Pascal), t_range: (Kelvin, Kelvin)) -> Vec<String> {
+    let mut res = Vec::new();
+    if t_range.0 < LGE_VALID_T.0 || t_range.1 > LGE_VALID_T.1 {
+        res.push(format!(
+            "the viscosity correlation of Lee, Gonzalez and Eakin is extrapolated out of {:.0} to {:.0}°C",
+            Celsius::from(LGE_VALID_T.0).0,
+            Celsius::from(LGE_VALID_T.1).0
+        ));
+    }
+    if p_range.0 < LGE_VALID_P.0 || p_range.1 > LGE_VALID_P.1 {
+        res.push(format!(
+            "the viscosity correlation of Lee, Gonzalez and Eakin is extrapolated out of {:.1} to {:.0} bar",
+            Bar::from(LGE_VALID_P.0).0,
+            Bar::from(LGE_VALID_P.1).0
+        ));
+    }
+    res
+}
+
+#[test]
+fn test_lee_gonzalez_eakin() {
+    use crate::eos::{Eos, EosGas, R};
+    use crate::gas::find_gas;
+
+    // methane at 320 K (47°C) and 10 bar, in the domain of the correlation:
+    // about 11.8 µPa.s (NIST Chemistry WebBook), overestimated by about 5%
+    // by the correlation fitted to natural gases
+    let ch4 = find_gas("CH4").unwrap();
+    let m = ch4.molar_mass.unwrap();
+    let (p, t) = (Pascal::from(Bar(10f64)), Kelvin(320f64));
+    assert!(lge_warnings((p, p), (t, t)).is_empty());
+    let z = ch4.z(Eos::PengRobinson, p, t).unwrap();
+    let density = m * p.0 / (z * R * t.0);
+    let mu = lee_gonzalez_eakin(m, density, t);
+    assert!((mu / 11.8e-6 - 1f64).abs() < 0.06, "{}", mu);
+
+    // the viscosity of a gas increases with the density
+    assert!(lee_gonzalez_eakin(m, 100f64, t) > mu);
+}
+
+#[test]
+fn test_lge_warnings() {
+    let p = |min, max| (Pascal(min), Pascal(max));
+    let t = |min, max| (Kelvin(min), Kelvin(max));
+    assert!(lge_warnings(p(1e6, 50e6), t(320f64, 400f64)).is_empty());
+    assert_eq!(lge_warnings(p(1e5, 50e6), t(320f64, 400f64)).len(), 1);
+    assert_eq!(lge_warnings(p(1e5, 100e6), t(293.15, 500f64)).len(), 2);
+}
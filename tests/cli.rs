@@ -0,0 +1,140 @@
+//! End-to-end tests of the command line, running the built rkz binary
+use std::process::{Command, Output};
+
+/// Environment variables read by rkz
+const ENV_VARS: &[&str] = &["RKZ_GAS", "RKZ_PRESSURE", "RKZ_TEMPERATURE", "RKZ_EOS"];
+
+/// Runs rkz with the given arguments
+fn rkz(args: &[&str]) -> Output {
+    rkz_with_env(args, &[])
+}
+
+/// Runs rkz with the given arguments and only the given rkz environment variables
+fn rkz_with_env(args: &[&str], vars: &[(&str, &str)]) -> Output {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_rkz"));
+    for var in ENV_VARS.iter() {
+        cmd.env_remove(var);
+    }
+    cmd.args(args)
+        .envs(vars.iter().copied())
+        .output()
+        .expect("failed to run rkz")
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8(output.stderr.clone()).unwrap()
+}
+
+#[test]
+fn test_list_gas() {
+    let output = rkz(&["--list-gas"]);
+    assert!(output.status.success());
+    let out = stdout(&output);
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines[0], "Gases referenced by RKZ:");
+    assert_eq!(
+        lines[1].split_whitespace().collect::<Vec<_>>(),
+        ["ID", "Name"]
+    );
+    assert!(lines
+        .iter()
+        .any(|l| l.split_whitespace().collect::<Vec<_>>() == ["N2", "Nitrogen"]));
+    assert!(stderr(&output).is_empty());
+}
+
+#[test]
+fn test_scalar() {
+    let output = rkz(&["-g", "N2", "-p", "200", "-t", "20"]);
+    assert!(output.status.success());
+    let z: f64 = stdout(&output).trim().parse().unwrap();
+    assert!((z - 1.0268).abs() < 1e-4);
+
+    let output = rkz(&["-g", "N2", "-p", "200", "-t", "20", "--precision", "3"]);
+    assert_eq!(stdout(&output), "1.027\n");
+}
+
+#[test]
+fn test_range() {
+    let output = rkz(&["-g", "N2", "-p", "1:3", "-t", "20:21"]);
+    assert!(output.status.success());
+    let out = stdout(&output);
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[0], "P[bar] \\ T[°C]\t20°C\t21°C");
+    for (line, p) in lines[1..].iter().zip(["1", "2", "3"].iter()) {
+        let cells: Vec<&str> = line.split('\t').collect();
+        assert_eq!(cells.len(), 3);
+        assert_eq!(&cells[0], p);
+        for z in cells[1..].iter() {
+            let z: f64 = z.parse().unwrap();
+            assert!(z < 1f64 && z > 0.99);
+        }
+    }
+}
+
+#[test]
+fn test_errors() {
+    // unknown gas
+    let output = rkz(&["-g", "XX", "-p", "1", "-t", "20"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert!(stdout(&output).is_empty());
+    assert_eq!(
+        stderr(&output).trim(),
+        "The requested gas is not referenced"
+    );
+
+    // missing temperature
+    let output = rkz(&["-g", "N2", "-p", "1"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).contains("Please specify gas, temperature and pressure"));
+
+    // invalid argument, reported by clap
+    let output = rkz(&["-g", "N2", "-p", "1", "-t", "20", "--no-such-flag"]);
+    assert_eq!(output.status.code(), Some(2));
+
+    let output = rkz(&["-g", "XX", "-p", "1", "-t", "20", "--error-format", "json"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert!(stderr(&output).contains("\"code\":3"));
+}
+
+#[test]
+fn test_env_vars() {
+    let vars = [
+        ("RKZ_GAS", "N2"),
+        ("RKZ_PRESSURE", "200"),
+        ("RKZ_TEMPERATURE", "20"),
+        ("RKZ_EOS", "PR"),
+    ];
+    let output = rkz_with_env(&[], &vars);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        stdout(&rkz(&["-g", "N2", "-p", "200", "-t", "20", "-e", "PR"]))
+    );
+
+    // the options take precedence
+    let output = rkz_with_env(&["-p", "100", "-e", "RK"], &vars);
+    assert!(output.status.success());
+    assert_eq!(
+        stdout(&output),
+        stdout(&rkz(&["-g", "N2", "-p", "100", "-t", "20"]))
+    );
+
+    // missing pressure
+    let output = rkz_with_env(&[], &vars[..1]);
+    assert_eq!(output.status.code(), Some(2));
+
+    // ignored by the modes that don't take the option
+    let output = rkz_with_env(&["--compare-gas", "N2,O2"], &vars);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let output = rkz_with_env(&["--boyle"], &vars);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        stdout(&rkz(&["--boyle", "-g", "N2", "-e", "PR"]))
+    );
+}